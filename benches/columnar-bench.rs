@@ -0,0 +1,71 @@
+extern crate differential_dataflow;
+
+use differential_dataflow::trace::{Batch, BatchReader, Builder, Cursor};
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::ord_col::OrdColBatch;
+
+type InterleavedBatch = OrdValBatch<u64, u64, usize, isize>;
+type ColumnarBatch = OrdColBatch<u64, u64, usize, isize>;
+
+fn build<B: Batch<u64, u64, usize, isize>>(keys: u64) -> B {
+    let mut builder = <B as Batch<u64, u64, usize, isize>>::Builder::new();
+    for key in 0 .. keys {
+        for val in 0 .. 4 {
+            for time in 0 .. 8 {
+                builder.push((key, val, time, 1));
+            }
+        }
+    }
+    builder.done(&[0], &[8], &[0])
+}
+
+// Sums the differences of a batch, visiting each update through its cursor.
+fn cursor_sum<B: BatchReader<u64, u64, usize, isize>>(batch: &B) -> isize {
+    let mut sum = 0;
+    let mut cursor = batch.cursor();
+    while cursor.key_valid(batch) {
+        while cursor.val_valid(batch) {
+            cursor.map_times(batch, |_time, diff| sum += diff);
+            cursor.step_val(batch);
+        }
+        cursor.step_key(batch);
+    }
+    sum
+}
+
+fn main() {
+
+    let keys: u64 = std::env::args().skip(1).find(|arg| !arg.starts_with("--")).unwrap_or("1000000".to_owned()).parse().unwrap();
+    let rounds = 10;
+
+    // This computation compares scans of the same updates stored with times and differences interleaved,
+    // as `OrdValBatch` does, and in separate columns, as `OrdColBatch` does. Scans of the differences alone
+    // are where the columnar layout should help; full scans through cursors should be comparable.
+    let interleaved = build::<InterleavedBatch>(keys);
+    let columnar = build::<ColumnarBatch>(keys);
+    let updates = interleaved.len() * rounds;
+
+    let timer = ::std::time::Instant::now();
+    let mut sum = 0;
+    for _ in 0 .. rounds { sum += interleaved.layer.vals.vals.vals.iter().map(|x| x.1).sum::<isize>(); }
+    println!("interleaved: summed {:?} differences in {:?}", updates, timer.elapsed());
+    assert_eq!(sum as usize, updates);
+
+    let timer = ::std::time::Instant::now();
+    let mut sum = 0;
+    for _ in 0 .. rounds { sum += columnar.layer.vals.vals.diffs.iter().sum::<isize>(); }
+    println!("columnar: summed {:?} differences in {:?}", updates, timer.elapsed());
+    assert_eq!(sum as usize, updates);
+
+    let timer = ::std::time::Instant::now();
+    let mut sum = 0;
+    for _ in 0 .. rounds { sum += cursor_sum(&interleaved); }
+    println!("interleaved: scanned {:?} updates by cursor in {:?}", updates, timer.elapsed());
+    assert_eq!(sum as usize, updates);
+
+    let timer = ::std::time::Instant::now();
+    let mut sum = 0;
+    for _ in 0 .. rounds { sum += cursor_sum(&columnar); }
+    println!("columnar: scanned {:?} updates by cursor in {:?}", updates, timer.elapsed());
+    assert_eq!(sum as usize, updates);
+}
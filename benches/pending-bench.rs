@@ -0,0 +1,35 @@
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+
+use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::trace::{Trace, TraceReader, Batch, Batcher};
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::spine_fueled::Spine;
+
+type IntegerTrace = Spine<UnsignedWrapper<u64>, u64, usize, isize, Rc<OrdValBatch<UnsignedWrapper<u64>, u64, usize, isize>>>;
+
+fn main() {
+
+    let batches: usize = std::env::args().skip(1).find(|arg| !arg.starts_with("--")).unwrap_or("100000".to_owned()).parse().unwrap();
+
+    // This computation inserts `batches` single-update batches into a trace whose physical compaction
+    // frontier holds them all back as pending, and then releases them at once. Both phases should take
+    // time linear in the number of batches.
+    let mut trace = IntegerTrace::new();
+    let mut batcher = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, isize>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, isize>>::Batcher::new();
+
+    let timer = ::std::time::Instant::now();
+    for i in 0 .. batches {
+        batcher.push_batch(&mut vec![(((i as u64).into(), i as u64), i, 1)]);
+        trace.insert(batcher.seal(&[i + 1]));
+    }
+    println!("inserted {:?} pending batches in {:?}", trace.num_batches(), timer.elapsed());
+
+    let timer = ::std::time::Instant::now();
+    trace.distinguish_since(&[batches]);
+    println!("released into {:?} batches in {:?}", trace.num_batches(), timer.elapsed());
+
+    assert_eq!(trace.len(), batches);
+}
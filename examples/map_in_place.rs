@@ -0,0 +1,53 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use differential_dataflow::input::Input;
+
+fn main() {
+
+    let records: usize = std::env::args().nth(1).unwrap().parse().unwrap();
+    let in_place: bool = std::env::args().find(|x| x == "in-place").is_some();
+    let batch: usize = 10_000;
+
+    // This computation compares `map` against `map_in_place` for a transform that does not change the record
+    // type, by pushing `records` updates through each and reporting the elapsed time.
+    timely::execute_from_args(std::env::args().skip(2), move |worker| {
+
+        let index = worker.index();
+        let peers = worker.peers();
+
+        let (mut input, probe) = worker.dataflow::<usize, _, _>(|scope| {
+            let (input, data) = scope.new_collection::<(u64, u64), isize>();
+            let probe = if in_place {
+                data.map_in_place(|x| { x.1 = x.0.wrapping_mul(x.1); }).probe()
+            }
+            else {
+                data.map(|(x, y)| (x, x.wrapping_mul(y))).probe()
+            };
+            (input, probe)
+        });
+
+        let timer = ::std::time::Instant::now();
+
+        let mut round = 0;
+        let mut counter = index;
+        while counter < records {
+            for _ in 0 .. batch {
+                if counter < records {
+                    input.insert((counter as u64, 3));
+                    counter += peers;
+                }
+            }
+            round += 1;
+            input.advance_to(round);
+            input.flush();
+            worker.step_while(|| probe.less_than(input.time()));
+        }
+
+        if index == 0 {
+            let method = if in_place { "map_in_place" } else { "map" };
+            println!("{}: {:?} records in {:?}", method, records, timer.elapsed());
+        }
+
+    }).unwrap();
+}
@@ -0,0 +1,37 @@
+extern crate differential_dataflow;
+
+use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::trace::{Batch, Builder};
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::parallel::merge_parallel;
+use differential_dataflow::trace::implementations::spine_fueled::merge_batches;
+
+type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, isize>;
+
+fn build(keys: u64, lower: usize, upper: usize, diff: isize) -> IntegerBatch {
+    let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, isize>>::Builder::new();
+    for key in 0 .. keys {
+        builder.push((key.into(), key % 7, lower, diff));
+    }
+    builder.done(&[lower], &[upper], &[lower])
+}
+
+fn main() {
+
+    let keys: u64 = std::env::args().nth(1).unwrap_or("10000000".to_owned()).parse().unwrap();
+
+    // This computation compares sequential and parallel merging of two batches of `keys` keys each, whose
+    // keys entirely overlap and whose updates cancel once compacted.
+    let batch1 = build(keys, 0, 1, 1);
+    let batch2 = build(keys, 1, 2, -1);
+
+    let timer = ::std::time::Instant::now();
+    let sequential = merge_batches(&batch1, &batch2, Some(&[2]));
+    println!("sequential: {:?} keys in {:?}", keys, timer.elapsed());
+
+    let timer = ::std::time::Instant::now();
+    let parallel = merge_parallel(&batch1, &batch2, Some(&[2]));
+    println!("parallel: {:?} keys in {:?}", keys, timer.elapsed());
+
+    assert!(sequential.layer == parallel.layer);
+}
@@ -12,11 +12,13 @@ use std::hash::Hash;
 use std::ops::Mul;
 
 use timely::Data;
+use timely::order::PartialOrder;
 use timely::progress::Timestamp;
 use timely::progress::nested::product::Product;
 use timely::dataflow::scopes::Child;
 use timely::dataflow::{Scope, Stream};
 use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Pipeline;
 
 use ::Diff;
 use lattice::Lattice;
@@ -89,6 +91,11 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
     /// but rather re-uses the underlying allocations in its implementation. The method is semantically 
     /// equivalent to `map`, but can be more efficient.
     ///
+    /// Records are updated where they sit in each batch, with their times and differences unchanged. Should the
+    /// input be sorted, for example by an upstream `consolidate`, the closure may break that order if it changes
+    /// the ordering of records; operators that arrange their input re-sort it, but others relying on sorted input
+    /// would observe out-of-order records.
+    ///
     /// # Examples
     ///
     /// ```
@@ -204,6 +211,109 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
             .filter(move |&(ref data, _, _)| logic(data))
             .as_collection()
     }
+    /// Divides a collection in two, by whether the supplied predicate holds for each element.
+    ///
+    /// The first collection contains the elements for which `logic` returns true, and the second contains
+    /// the rest, each with their original times and differences. The method is semantically equivalent to
+    /// a `filter` by `logic` and one by its negation, but evaluates `logic` once per update in a single operator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///         let (odds, evens) = data.split(|x| x % 2 == 1);
+    ///
+    ///         odds.assert_eq(&data.filter(|x| x % 2 == 1));
+    ///         evens.concat(&odds).assert_eq(&data);
+    ///     });
+    /// }
+    /// ```
+    pub fn split<L>(&self, logic: L) -> (Collection<G, D, R>, Collection<G, D, R>)
+    where L: Fn(&D) -> bool + 'static {
+        let mut streams = self.inner.partition(2, move |update| (if logic(&update.0) { 0 } else { 1 }, update));
+        let rest = streams.pop().expect("partition produced too few streams");
+        let matching = streams.pop().expect("partition produced too few streams");
+        (matching.as_collection(), rest.as_collection())
+    }
+    /// Exchanges the updates of a collection among workers, by a supplied hash of their data.
+    ///
+    /// Each update is assigned to the partition `logic(data) % num_parts`, and partition `p` is sent to the
+    /// worker with index `p % peers`. Operators that exchange their inputs otherwise route by the `Hashable`
+    /// hash of the key; this method allows one to place records explicitly, for example to spread heavily
+    /// skewed keys over several workers. The contents of the collection are unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_parts` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let data = scope.new_collection_from(1 .. 10u64).1;
+    ///
+    ///         data.hashed_partition(4, |x| *x)
+    ///             .assert_eq(&data);
+    ///     });
+    /// }
+    /// ```
+    pub fn hashed_partition<L>(&self, num_parts: u64, logic: L) -> Collection<G, D, R>
+    where D: ::Data, L: Fn(&D) -> u64 + 'static {
+        assert!(num_parts > 0, "`hashed_partition`: the number of partitions must be positive");
+        self.inner
+            .exchange(move |update| logic(&update.0) % num_parts)
+            .as_collection()
+    }
+    /// Creates a new collection by applying the supplied function to each input element, and retaining the results
+    /// which are `Some`.
+    ///
+    /// The method is semantically equivalent to a `filter` followed by a `map`, but uses a single operator and so
+    /// avoids materializing the intermediate stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::*;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///
+    ///         let x1 = data.filter(|x| x % 2 == 1).map(|x| x * 2);
+    ///         let x2 = data.filter_map(|x| if x % 2 == 1 { Some(x * 2) } else { None });
+    ///
+    ///         x1.assert_eq(&x2);
+    ///     });
+    /// }
+    /// ```
+    pub fn filter_map<D2, L>(&self, logic: L) -> Collection<G, D2, R>
+    where D2: Data,
+          L: Fn(D) -> Option<D2> + 'static
+    {
+        self.inner
+            .flat_map(move |(data, time, delta)| logic(data).map(move |x| (x, time, delta)))
+            .as_collection()
+    }
     /// Creates a new collection accumulating the contents of the two collections.
     ///
     /// Despite the name, differential dataflow collections are unordered. This method is so named because the 
@@ -275,6 +385,55 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
             .as_collection()
     }
 
+    /// Replaces each update's time with the result of the supplied function.
+    ///
+    /// The function must map each time to a time greater or equal to it, which is checked in debug builds.
+    /// Updates are sent with capabilities for their delayed times, and so the output frontier may advance
+    /// past their original times as soon as the input frontier does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use timely::progress::timestamp::RootTimestamp;
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // delaying all updates by a round leaves the accumulated collection unchanged.
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///         data.delay(|t| RootTimestamp::new(t.inner + 1))
+    ///             .assert_eq(&data);
+    ///     });
+    /// }
+    /// ```
+    pub fn delay<L>(&self, logic: L) -> Collection<G, D, R>
+    where L: Fn(&G::Timestamp) -> G::Timestamp + 'static {
+        self.inner
+            .unary_stream(Pipeline, "Delay", move |input, output| {
+                input.for_each(|time, data| {
+                    // sort updates by their delayed times, each of which needs its own capability.
+                    let mut updates = data.drain(..).map(|(datum, original, diff)| {
+                        let delayed = logic(&original);
+                        debug_assert!(original.less_equal(&delayed));
+                        (datum, delayed, diff)
+                    }).collect::<Vec<_>>();
+                    updates.sort_by(|x, y| x.1.cmp(&y.1));
+                    let mut updates = updates.into_iter().peekable();
+                    while let Some(update) = updates.next() {
+                        let capability = time.delayed(&update.1);
+                        let mut session = output.session(&capability);
+                        while updates.peek().map(|next| next.1 == update.1) == Some(true) {
+                            session.give(updates.next().unwrap());
+                        }
+                        session.give(update);
+                    }
+                });
+            })
+            .as_collection()
+    }
     /// Brings a Collection into a nested scope.
     ///
     /// # Examples
@@ -417,6 +576,49 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
             .inspect_batch(func)
             .as_collection()
     }
+    /// Applies a supplied function to the input frontier each time it advances.
+    ///
+    /// The updates of the collection pass through unchanged and without delay; the operator only observes
+    /// progress, and holds no capabilities of its own. This can be used to flush external sinks once the
+    /// collection is complete up to some time. The first call reports the first frontier to differ from the
+    /// initial one, and once the collection is complete the function is called with the empty frontier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .on_frontier(|frontier| println!("complete up to {:?}", frontier));
+    ///     });
+    /// }
+    /// ```
+    pub fn on_frontier<L>(&self, mut logic: L) -> Collection<G, D, R>
+    where L: FnMut(&[G::Timestamp])+'static {
+        use timely::dataflow::operators::generic::Operator;
+        self.inner
+            .unary_frontier(Pipeline, "OnFrontier", move |capability, _info| {
+                let mut frontier = vec![capability.time().clone()];
+                move |input, output| {
+                    input.for_each(|time, data| {
+                        let mut session = output.session(&time);
+                        for update in data.drain(..) {
+                            session.give(update);
+                        }
+                    });
+                    if input.frontier().frontier() != &frontier[..] {
+                        frontier = input.frontier().frontier().to_vec();
+                        logic(&frontier[..]);
+                    }
+                }
+            })
+            .as_collection()
+    }
     /// Attaches a timely dataflow probe to the output of a Collection.
     ///
     /// This probe is used to determine when the state of the Collection has stabilized and can
@@ -444,6 +646,9 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
     /// Typically, a timely dataflow computation runs to completion on drop, and so clean exit from a program should
     /// indicate that this assertion never found cause to complain.
     ///
+    /// The assertion panics with the first residual update it finds, as a record, time, and difference, where
+    /// a positive difference indicates a record `other` has in excess of `self`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -482,6 +687,8 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
     /// non-empty. Typically, a timely dataflow computation runs to completion on drop, and so clean exit from a 
     /// program should indicate that this assertion never found cause to complain.
     ///
+    /// The assertion panics with the first update of the consolidated collection it finds.
+    ///
     /// # Examples
     ///
     /// ```
@@ -506,7 +713,40 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
     {
         use operators::consolidate::Consolidate;
         self.consolidate()
-            .inspect(|_| assert!(false));
+            .inspect(|x| panic!("assertion failed: collection is non-empty, with update {:?}", x));
+    }
+
+    /// The distinct times of the updates of the collection, each as a record with count one.
+    ///
+    /// Each update contributes its time as a record at that same time, whatever its data and difference, and
+    /// the records are then reduced to one occurrence each. The result therefore accumulates, at any time, to
+    /// the set of distinct times at or before it at which the collection has updates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // all updates are at the initial time.
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///         data.distinct_times()
+    ///             .assert_eq(&scope.new_collection_from(Some(Default::default())).1);
+    ///     });
+    /// }
+    /// ```
+    pub fn distinct_times(&self) -> Collection<G, G::Timestamp, isize>
+    where G::Timestamp: ::Data+Hashable+Lattice+Ord {
+        use operators::group::Threshold;
+        self.inner
+            .map(|(_, time, _)| (time.clone(), time, 1))
+            .as_collection()
+            .distinct()
     }
 
     /// The scope containing the underlying timely dataflow stream.
@@ -515,6 +755,54 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
     }
 }
 
+impl<G: Scope, K: ::Data, V: ::Data, R: Diff> Collection<G, (K, V), R> where G::Timestamp: Data {
+    /// Replaces the key of each record with `logic(key, val)`, retaining the original record as the value.
+    ///
+    /// Rather than a `map`, after which the updates remain with the workers that held them and in no order of
+    /// the new key, the updates are exchanged by the hash of their new key, as `arrange_by_key` would exchange
+    /// them, and each batch of updates is sorted by the new key if it is not already. The result is ready for
+    /// arrangement by the new key: its updates are resident at the workers that will arrange them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // key records by their value rather than their key.
+    ///         let data = scope.new_collection_from((1 .. 10).map(|x| (x, x % 3))).1;
+    ///         data.rekey(|_key, val| *val)
+    ///             .assert_eq(&data.map(|(key, val)| (val, (key, val))));
+    ///     });
+    /// }
+    /// ```
+    pub fn rekey<K2, L>(&self, logic: L) -> Collection<G, (K2, (K, V)), R>
+    where K2: ::Data+Hashable, L: Fn(&K, &V) -> K2 + 'static {
+        use timely::dataflow::channels::pact::Exchange;
+        use timely_sort::Unsigned;
+        let exchange = Exchange::new(|update: &((K2, (K, V)), G::Timestamp, R)| (update.0).0.hashed().as_u64());
+        self.map(move |(key, val)| (logic(&key, &val), (key, val)))
+            .inner
+            .unary_stream(exchange, "Rekey", move |input, output| {
+                input.for_each(|time, data| {
+                    // updates from a single worker may already be in order; only sort those that are not.
+                    if !data.windows(2).all(|x| (x[0].0).0 <= (x[1].0).0) {
+                        data.sort_by(|x, y| (x.0).0.cmp(&(y.0).0));
+                    }
+                    let mut session = output.session(&time);
+                    for update in data.drain(..) {
+                        session.give(update);
+                    }
+                });
+            })
+            .as_collection()
+    }
+}
+
 impl<'a, G: Scope, T: Timestamp, D: Data, R: Diff> Collection<Child<'a, G, T>, D, R> {
     /// Returns the final value of a Collection from a nested scope to its containing scope.
     ///
@@ -550,6 +838,44 @@ impl<'a, G: Scope, T: Timestamp, D: Data, R: Diff> Collection<Child<'a, G, T>, D
     }
 }
 
+/// Concatenates multiple collections.
+///
+/// This method has the effect of a sequence of calls to `concat`, but it does so in one operator rather than a
+/// chain of many operators. The scope is required to produce an empty collection for an empty iterator.
+///
+/// # Examples
+///
+/// ```
+/// extern crate timely;
+/// extern crate differential_dataflow;
+///
+/// use differential_dataflow::input::Input;
+///
+/// fn main() {
+///     ::timely::example(|scope| {
+///
+///         let data = scope.new_collection_from(1 .. 10).1;
+///
+///         let odds = data.filter(|x| x % 2 == 1);
+///         let evens = data.filter(|x| x % 2 == 0);
+///
+///         differential_dataflow::collection::concatenate(scope, vec![odds, evens])
+///             .assert_eq(&data);
+///     });
+/// }
+/// ```
+pub fn concatenate<G, D, R, I>(scope: &mut G, iterator: I) -> Collection<G, D, R>
+where
+    G: Scope,
+    D: Data,
+    R: Diff,
+    I: IntoIterator<Item=Collection<G, D, R>>,
+{
+    scope
+        .concatenate(iterator.into_iter().map(|x| x.inner))
+        .as_collection()
+}
+
 /// Conversion to a differential dataflow Collection.
 pub trait AsCollection<G: Scope, D: Data, R: Diff> {
     /// Converts the type to a differential dataflow collection.
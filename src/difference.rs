@@ -5,6 +5,23 @@
 //! is when we maintain both a count and another accumulation, for example height. The differential 
 //! dataflow collections would then track for each record the total of counts and heights, which allows 
 //! us to track something like the average.
+//!
+//! #Group and semiring semantics
+//!
+//! The accumulation of differences, in consolidation, in merging batches, and in the accumulation of
+//! input and output collections in `group`, only adds differences and tests them with `is_zero`. Types
+//! whose addition is commutative and associative, with `zero` as an identity, accumulate correctly along
+//! these paths even without inverses. However, several operators do rely on inverses:
+//!
+//!  * `Collection::negate`, and operators built from it like `antijoin` and some uses of `concat`, negate
+//!    differences so that they cancel with others.
+//!  * `group` and its relatives retract previously produced output by negating it, and so produce correct
+//!    output only for differences forming a group.
+//!  * `iterate` negates its initial collection to retract it at later iterations.
+//!
+//! A type like `SaturatingI64` satisfies the trait, but its addition is not associative once it saturates,
+//! and its negation does not cancel saturated values, so it is only appropriate for computations that
+//! avoid these operators or that produce accumulations within the range of the type.
 
 use std::ops::{Add, Sub, Neg, Mul};
 
@@ -129,3 +146,47 @@ where <R1 as Mul<T>>::Output: Diff, <R2 as Mul<T>>::Output: Diff {
 // }
 
 impl<R1: Diff, R2: Diff> Abomonation for DiffPair<R1, R2> { }
+
+/// A 64-bit signed integer difference, whose arithmetic saturates rather than overflows.
+///
+/// Additions and subtractions that would overflow produce `i64::max_value()` or `i64::min_value()`, and
+/// negation of `i64::min_value()` produces `i64::max_value()`. Accumulations that remain in range are as
+/// for `i64`; those that saturate depend on the order of accumulation, which differential dataflow does
+/// not specify.
+#[derive(Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Default)]
+pub struct SaturatingI64 {
+	/// The value of the difference.
+	pub value: i64,
+}
+
+impl SaturatingI64 {
+	/// Creates a new difference from a value.
+	#[inline(always)] pub fn new(value: i64) -> Self { SaturatingI64 { value: value } }
+}
+
+impl Diff for SaturatingI64 {
+	#[inline(always)] fn is_zero(&self) -> bool { self.value == 0 }
+	#[inline(always)] fn zero() -> Self { SaturatingI64 { value: 0 } }
+}
+
+impl Add<SaturatingI64> for SaturatingI64 {
+	type Output = Self;
+	#[inline(always)] fn add(self, rhs: Self) -> Self { SaturatingI64 { value: self.value.saturating_add(rhs.value) } }
+}
+
+impl Sub<SaturatingI64> for SaturatingI64 {
+	type Output = Self;
+	#[inline(always)] fn sub(self, rhs: Self) -> Self { SaturatingI64 { value: self.value.saturating_sub(rhs.value) } }
+}
+
+impl Neg for SaturatingI64 {
+	type Output = Self;
+	#[inline(always)] fn neg(self) -> Self { SaturatingI64 { value: self.value.checked_neg().unwrap_or(i64::max_value()) } }
+}
+
+impl Mul<SaturatingI64> for SaturatingI64 {
+	type Output = Self;
+	#[inline(always)] fn mul(self, rhs: Self) -> Self { SaturatingI64 { value: self.value.saturating_mul(rhs.value) } }
+}
+
+impl Abomonation for SaturatingI64 { }
@@ -94,6 +94,8 @@ extern crate timely_communication;
 #[macro_use]
 extern crate abomonation_derive;
 extern crate abomonation;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 
 pub mod hashable;
 pub mod operators;
@@ -76,6 +76,13 @@ where T: Lattice+Ord+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R
 impl<K, V, T, R, Tr> TraceWriter<K, V, T, R, Tr>
 where T: Lattice+Ord+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R> {
 
+    /// Indicates whether the contents of the trace have been requested by a reader.
+    ///
+    /// A trace whose readers have all been dropped can no longer be read, and is reported as not requested.
+    pub fn demanded(&self) -> bool {
+        self.trace.upgrade().map(|trace| trace.borrow().demanded).unwrap_or(false)
+    }
+
     /// Advances the trace to `frontier`, providing batch data if it exists.
     pub fn seal(&mut self, frontier: &[T], data: Option<(T, Tr::Batch)>) {
 
@@ -158,8 +165,18 @@ where T: Lattice+Ord+Clone+'static, Tr: TraceReader<K,V,T,R> {
     fn distinguish_frontier(&mut self) -> &[T] {
         &self.through[..]
     }
-    fn cursor_through(&mut self, frontier: &[T]) -> Option<(Tr::Cursor, <Tr::Cursor as Cursor<K, V, T, R>>::Storage)> { self.trace.borrow_mut().trace.cursor_through(frontier) }
-    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) { self.trace.borrow_mut().trace.map_batches(f) }
+    fn cursor_through(&mut self, frontier: &[T]) -> Option<(Tr::Cursor, <Tr::Cursor as Cursor<K, V, T, R>>::Storage)> {
+        let mut borrow = self.trace.borrow_mut();
+        borrow.demanded = true;
+        borrow.trace.cursor_through(frontier)
+    }
+    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) {
+        let mut borrow = self.trace.borrow_mut();
+        borrow.demanded = true;
+        borrow.trace.map_batches(f)
+    }
+    fn num_updates_hint(&self) -> Option<usize> { self.trace.borrow().trace.num_updates_hint() }
+    fn estimated_bytes_hint(&self) -> Option<usize> { self.trace.borrow().trace.estimated_bytes_hint() }
 }
 
 impl<K, V, T, R, Tr> TraceAgent<K, V, T, R, Tr>
@@ -199,6 +216,7 @@ where T: Lattice+Ord+Clone+Debug+'static, Tr: TraceReader<K,V,T,R> {
 
         // add the existing batches from the trace
         let mut upper = vec![T::default()];
+        self.trace.borrow_mut().demanded = true;
         self.trace.borrow_mut().trace.map_batches(|batch| {
             upper = batch.upper().to_vec();
             new_queue.push_back((vec![T::default()], Some((T::default(), batch.clone()))));
@@ -448,6 +466,68 @@ impl<G: Scope, K, V, R, T> Arranged<G, K, V, R, T> where G::Timestamp: Lattice+O
         })
         .as_collection()
     }
+
+    /// Reports the frontier and size of the arrangement to `probe`, each time the arrangement receives batches.
+    ///
+    /// The attached operator holds a handle to the trace, whose frontiers it advances along with its input, and
+    /// which it releases once the arrangement is complete. The size reported is that of the trace, including
+    /// batches awaiting merging, and is `None` if the trace does not provide estimates.
+    pub fn probe_with_sink(&self, probe: &TraceProbe<G::Timestamp>) where T: 'static, T::Batch: 'static {
+
+        let mut trace = Some(self.trace.clone());
+        let state = probe.state.clone();
+
+        let _: Stream<G, ()> = self.stream.unary_frontier(Pipeline, "TraceProbe", move |_capability, _info|
+            move |input, _output| {
+
+                // batches are already in the trace, and need not be examined.
+                input.for_each(|_time, data| data.clear());
+
+                let mut borrow = state.borrow_mut();
+                borrow.0 = input.frontier().frontier().to_vec();
+                if let Some(ref mut trace) = trace {
+                    borrow.1 = trace.num_updates_hint();
+                    borrow.2 = trace.estimated_bytes_hint();
+                    trace.advance_by(&input.frontier().frontier());
+                    trace.distinguish_since(&input.frontier().frontier());
+                }
+                if input.frontier().is_empty() {
+                    trace = None;
+                }
+            }
+        );
+    }
+}
+
+/// A shared report of the progress and size of an arrangement, maintained by `Arranged::probe_with_sink`.
+///
+/// Clones of a probe share the same report. A coordinator may consult the probe to throttle the introduction of
+/// inputs, for example until the trace has caught up to the input or its size has fallen below some bound.
+pub struct TraceProbe<T> {
+    // the frontier of the arrangement, and the estimated updates and bytes of its trace.
+    state: Rc<RefCell<(Vec<T>, Option<usize>, Option<usize>)>>,
+}
+
+impl<T: Default> TraceProbe<T> {
+    /// Allocates a probe reporting the initial frontier, and no size.
+    pub fn new() -> Self {
+        TraceProbe { state: Rc::new(RefCell::new((vec![T::default()], None, None))) }
+    }
+}
+
+impl<T: Clone> TraceProbe<T> {
+    /// The frontier of the arrangement, which the upper frontier of its trace reaches as batches are added.
+    pub fn upper(&self) -> Vec<T> { self.state.borrow().0.clone() }
+    /// The number of updates in the trace, as last reported by `TraceReader::num_updates_hint`.
+    pub fn num_updates(&self) -> Option<usize> { self.state.borrow().1 }
+    /// The heap memory used by the trace, as last reported by `TraceReader::estimated_bytes_hint`.
+    pub fn estimated_bytes(&self) -> Option<usize> { self.state.borrow().2 }
+}
+
+impl<T> Clone for TraceProbe<T> {
+    fn clone(&self) -> Self {
+        TraceProbe { state: self.state.clone() }
+    }
 }
 
 /// Report values associated with keys at certain times.
@@ -553,7 +633,25 @@ where
     /// This operator arranges a stream of values into a shared trace, whose contents it maintains.
     /// This trace is current for all times marked completed in the output stream, and probing this stream
     /// is the correct way to determine that times in the shared trace are committed.
-    fn arrange(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>;
+    fn arrange(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+        self.arrange_named("Arrange", empty_trace)
+    }
+    /// Arranges a stream of `(Key, Val)` updates by `Key`, as `arrange` does, with a name for the operator.
+    ///
+    /// The name is that of the underlying timely dataflow operator, and so distinguishes it in logging.
+    fn arrange_named(&self, name: &str, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>;
+    /// Arranges a stream of `(Key, Val)` updates by `Key`, as `arrange` does, but only once the trace is read.
+    ///
+    /// Until some handle to the trace requests a cursor or its batches, for example by `import`ing it, received
+    /// updates are discarded rather than batched. The trace and the output stream still advance as they would for
+    /// `arrange`, but with empty batches. Updates discarded in this way are not recovered, and the trace reflects
+    /// only the updates received after it is first read. This suits arrangements that may go unread in some
+    /// dataflow configurations; they should be read through the trace, rather than through the output stream.
+    ///
+    /// Already arranged data are returned as they are.
+    fn arrange_lazy(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+        self.arrange_named("Arrange", empty_trace)
+    }
 }
 
 impl<G: Scope, K: Data+Hashable, V: Data, R: Diff, T> Arrange<G, K, V, R, T> for Collection<G, (K, V), R>
@@ -562,7 +660,22 @@ where
     T: Trace<K, V, G::Timestamp, R>+'static,
     T::Batch: Batch<K, V, G::Timestamp, R> {
 
-    fn arrange(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+    fn arrange_named(&self, name: &str, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+        self.arrange_core(name, empty_trace, false)
+    }
+
+    fn arrange_lazy(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+        self.arrange_core("ArrangeLazy", empty_trace, true)
+    }
+}
+
+// Arranges the collection into `empty_trace`; if `lazy`, updates are discarded until the trace is read.
+impl<G: Scope, K: Data+Hashable, V: Data, R: Diff> Collection<G, (K, V), R> where G::Timestamp: Lattice+Ord {
+
+    fn arrange_core<T>(&self, name: &str, empty_trace: T, lazy: bool) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+    where
+        T: Trace<K, V, G::Timestamp, R>+'static,
+        T::Batch: Batch<K, V, G::Timestamp, R> {
 
         let (reader, mut writer) = TraceAgent::new(empty_trace);
 
@@ -574,16 +687,25 @@ where
 
         // fabricate a data-parallel operator using the `unary_notify` pattern.
         let exchange = Exchange::new(move |update: &((K,V),G::Timestamp,R)| (update.0).0.hashed().as_u64());
-        let stream = self.inner.unary_frontier(exchange, "Arrange", move |_capability, _info|
+        let stream = self.inner.unary_frontier(exchange, name, move |_capability, _info|
             move |input, output| {
 
+            // A lazy arrangement discards updates until some reader asks for the contents of the trace.
+            // Capabilities are still retained, so that (empty) batches advance the trace and the output.
+            let demanded = !lazy || writer.demanded();
+
             // As we receive data, we need to (i) stash the data and (ii) keep *enough* capabilities.
             // We don't have to keep all capabilities, but we need to be able to form output messages
             // when we realize that time intervals are complete.
 
             input.for_each(|cap, data| {
                 capabilities.insert(cap.retain());
-                batcher.push_batch(data.deref_mut());
+                if demanded {
+                    batcher.push_batch(data.deref_mut());
+                }
+                else {
+                    data.clear();
+                }
             });
 
             // The frontier may have advanced by multiple elements, which is an issue because
@@ -656,9 +778,14 @@ where
     T: Trace<K, (), G::Timestamp, R>+'static,
     T::Batch: Batch<K, (), G::Timestamp, R> {
 
-    fn arrange(&self, empty_trace: T) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, T>> {
+    fn arrange_named(&self, name: &str, empty_trace: T) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, T>> {
+        self.map(|k| (k, ()))
+            .arrange_named(name, empty_trace)
+    }
+
+    fn arrange_lazy(&self, empty_trace: T) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, T>> {
         self.map(|k| (k, ()))
-            .arrange(empty_trace)
+            .arrange_lazy(empty_trace)
     }
 }
 
@@ -670,7 +797,7 @@ where
     T: Trace<K, V, G::Timestamp, R>+Clone+'static,
     T::Batch: Batch<K, V, G::Timestamp, R>
 {
-    fn arrange(&self, _: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+    fn arrange_named(&self, _: &str, _: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
         (*self).clone()
     }
 }
@@ -687,13 +814,17 @@ where G::Timestamp: Lattice+Ord {
     /// This operator arranges a stream of values into a shared trace, whose contents it maintains.
     /// This trace is current for all times completed by the output stream, which can be used to
     /// safely identify the stable times and values in the trace.
-    fn arrange_by_key(&self) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, DefaultValTrace<K, V, G::Timestamp, R>>>;
+    fn arrange_by_key(&self) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, DefaultValTrace<K, V, G::Timestamp, R>>> {
+        self.arrange_by_key_named("ArrangeByKey")
+    }
+    /// Arranges a collection of `(Key, Val)` records by `Key`, as `arrange_by_key` does, with a name for the operator.
+    fn arrange_by_key_named(&self, name: &str) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, DefaultValTrace<K, V, G::Timestamp, R>>>;
 }
 
 impl<G: Scope, K: Data+Hashable, V: Data, R: Diff> ArrangeByKey<G, K, V, R> for Collection<G, (K,V), R>
 where G::Timestamp: Lattice+Ord {
-    fn arrange_by_key(&self) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, DefaultValTrace<K, V, G::Timestamp, R>>> {
-        self.arrange(DefaultValTrace::new())
+    fn arrange_by_key_named(&self, name: &str) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, DefaultValTrace<K, V, G::Timestamp, R>>> {
+        self.arrange_named(name, DefaultValTrace::new())
     }
 }
 
@@ -709,14 +840,18 @@ where G::Timestamp: Lattice+Ord {
     /// This operator arranges a collection of records into a shared trace, whose contents it maintains.
     /// This trace is current for all times complete in the output stream, which can be used to safely
     /// identify the stable times and values in the trace.
-    fn arrange_by_self(&self) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, DefaultKeyTrace<K, G::Timestamp, R>>>;
+    fn arrange_by_self(&self) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, DefaultKeyTrace<K, G::Timestamp, R>>> {
+        self.arrange_by_self_named("ArrangeBySelf")
+    }
+    /// Arranges a collection of `Key` records by `Key`, as `arrange_by_self` does, with a name for the operator.
+    fn arrange_by_self_named(&self, name: &str) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, DefaultKeyTrace<K, G::Timestamp, R>>>;
 }
 
 
 impl<G: Scope, K: Data+Hashable, R: Diff> ArrangeBySelf<G, K, R> for Collection<G, K, R>
 where G::Timestamp: Lattice+Ord {
-    fn arrange_by_self(&self) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, DefaultKeyTrace<K, G::Timestamp, R>>> {
+    fn arrange_by_self_named(&self, name: &str) -> Arranged<G, K, (), R, TraceAgent<K, (), G::Timestamp, R, DefaultKeyTrace<K, G::Timestamp, R>>> {
         self.map(|k| (k, ()))
-            .arrange(DefaultKeyTrace::new())
+            .arrange_named(name, DefaultKeyTrace::new())
     }
 }
@@ -6,10 +6,17 @@
 //! underlying system can more clearly see that no work must be done in the later case, and we can
 //! drop out of, e.g. iterative computations.
 
+use std::collections::HashMap;
+
 use timely::dataflow::Scope;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Exchange;
+
+use timely_sort::Unsigned;
 
-use ::{Collection, Data, Diff, Hashable};
+use ::{Collection, AsCollection, Data, Diff, Hashable};
 use operators::arrange::ArrangeBySelf;
+use trace::consolidate;
 
 /// An extension method for consolidating weighted streams.
 pub trait Consolidate<D: Data+Hashable> {
@@ -39,7 +46,66 @@ pub trait Consolidate<D: Data+Hashable> {
     ///     });
     /// }
     /// ```
-    fn consolidate(&self) -> Self;
+    fn consolidate(&self) -> Self {
+        self.consolidate_named("Consolidate")
+    }
+
+    /// Aggregates the weights of equal records into at most one record, with a name for the operator.
+    ///
+    /// The name is that of the underlying arrangement operator, and so distinguishes it in logging,
+    /// where all operators of `consolidate` are otherwise named alike.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Consolidate;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(1 .. 10u32).1;
+    ///
+    ///         x.negate()
+    ///          .concat(&x)
+    ///          .consolidate_named("Cancellation")
+    ///          .assert_empty();
+    ///     });
+    /// }
+    /// ```
+    fn consolidate_named(&self, name: &str) -> Self;
+
+    /// Aggregates the weights of equal records at each time into at most one record, without arranging.
+    ///
+    /// Unlike `consolidate`, this method does not maintain a trace, and so does not consolidate records
+    /// across times, but only cancels and sums the weights of records at the same time. Records are held
+    /// back until their timestamp has completed, and those whose weights sum to zero are not produced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Consolidate;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(1 .. 10u32).1;
+    ///
+    ///         x.negate()
+    ///          .concat(&x)
+    ///          .consolidate_stream() // <-- ensures cancellation occurs
+    ///          .assert_empty();
+    ///     });
+    /// }
+    /// ```
+    fn consolidate_stream(&self) -> Self;
 }
 
 impl<G: Scope, D, R> Consolidate<D> for Collection<G, D, R>
@@ -48,7 +114,37 @@ where
     R: Diff,
     G::Timestamp: ::lattice::Lattice+Ord,
  {
-    fn consolidate(&self) -> Self {
-       self.arrange_by_self().as_collection(|d,_| d.clone())
+    fn consolidate_named(&self, name: &str) -> Self {
+       self.arrange_by_self_named(name).as_collection(|d,_| d.clone())
+    }
+
+    fn consolidate_stream(&self) -> Self {
+
+        // Updates received at each time not yet complete.
+        let mut stash = HashMap::new();
+
+        let exchange = Exchange::new(move |update: &(D,G::Timestamp,R)| update.0.hashed().as_u64());
+        self.inner.unary_notify(exchange, "ConsolidateStream", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                stash.entry(capability.time().clone())
+                     .or_insert_with(|| {
+                         notificator.notify_at(capability.retain());
+                         Vec::new()
+                     })
+                     .extend(data.drain(..).map(|(d,_,r)| (d,r)));
+            });
+
+            notificator.for_each(|capability, _count, _notificator| {
+                if let Some(mut updates) = stash.remove(capability.time()) {
+                    consolidate(&mut updates, 0);
+                    let mut session = output.session(&capability);
+                    for (d, r) in updates.drain(..) {
+                        session.give((d, capability.time().clone(), r));
+                    }
+                }
+            });
+        })
+        .as_collection()
     }
 }
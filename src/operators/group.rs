@@ -12,6 +12,11 @@
 //! The list of values are presented as an iterator which internally merges sorted lists of values.
 //! This ordering can be exploited in several cases to avoid computation when only the first few
 //! elements are required.
+//!
+//! The reduction logic is re-applied for a key only at times its input may have changed. The
+//! `group_arranged_reusing` variant additionally reuses a key's previous output when its input is
+//! unchanged since the key's previous evaluation, which requires that the logic be a deterministic
+//! function of its key and input.
 
 use std::fmt::Debug;
 
@@ -124,6 +129,34 @@ pub trait Threshold<G: Scope, K: Data, R1: Diff> where G::Timestamp: Lattice+Ord
     fn distinct(&self) -> Collection<G, K, isize> {
         self.threshold(|c| if c.is_zero() { 0 } else { 1 })
     }
+    /// Reduces the collection to one occurrence of each distinct element, with the sign of its count.
+    ///
+    /// Elements whose accumulated count is positive are produced with count one, those whose count is
+    /// negative with count minus one, and those whose count is zero are absent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Threshold;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report one of each key, negated for odd keys.
+    ///         let data = scope.new_collection_from(1 .. 10).1;
+    ///         data.filter(|x| x % 2 == 0)
+    ///             .concat(&data.filter(|x| x % 2 == 1).negate())
+    ///             .concat(&data.filter(|x| x % 2 == 1).negate())
+    ///             .distinct_signed();
+    ///     });
+    /// }
+    /// ```
+    fn distinct_signed(&self) -> Collection<G, K, isize> where R1: PartialOrd {
+        self.threshold(|c| if c > R1::zero() { 1 } else if c < R1::zero() { -1 } else { 0 })
+    }
 }
 
 impl<G: Scope, K: Data+Hashable, R1: Diff> Threshold<G, K, R1> for Collection<G, K, R1>
@@ -170,6 +203,59 @@ impl<G: Scope, K: Data+Hashable, R: Diff> Count<G, K, R> for Collection<G, K, R>
     }
 }
 
+/// Extension trait for the `topk` differential dataflow method.
+pub trait TopK<G: Scope, K: Data, V: Data> where G::Timestamp: Lattice+Ord {
+    /// Retains, for each key, the `k` values with the greatest images under `order`.
+    ///
+    /// Values with equal images are ordered by the values themselves, and a value with multiplicity greater
+    /// than one may occupy several places. Values whose counts are not positive are ignored. As the output is
+    /// maintained by `group`, a value displaced by a greater value is retracted, and a value may return when
+    /// the values that displaced it are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::group::TopK;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // retain the two largest values of each group
+    ///         let data = scope.new_collection_from(1 .. 10).1
+    ///                         .map(|x| (x % 3, x));
+    ///         let top = scope.new_collection_from(vec![(0, 9), (0, 6), (1, 7), (1, 4), (2, 8), (2, 5)]).1;
+    ///
+    ///         data.topk(2, |&x| x)
+    ///             .assert_eq(&top);
+    ///     });
+    /// }
+    /// ```
+    fn topk<O, F>(&self, k: usize, order: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static;
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data> TopK<G, K, V> for Collection<G, (K, V), isize>
+ where G::Timestamp: Lattice+Ord+::std::fmt::Debug, <K as Hashable>::Output: Data {
+    fn topk<O, F>(&self, k: usize, order: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static {
+        self.group(move |_key, input, output| {
+            // order values by decreasing image, and take the first `k` occurrences.
+            let mut sorted = input.iter().filter(|x| x.1 > 0).map(|&(value, count)| (order(value), value, count)).collect::<Vec<_>>();
+            sorted.sort_by(|x, y| (&y.0, y.1).cmp(&(&x.0, x.1)));
+            let mut remaining = k as isize;
+            for (_, value, count) in sorted {
+                if remaining == 0 { break; }
+                let taken = ::std::cmp::min(count, remaining);
+                output.push((value.clone(), taken));
+                remaining -= taken;
+            }
+        })
+    }
+}
+
 
 /// Extension trait for the `group_arranged` differential dataflow method.
 pub trait GroupArranged<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
@@ -206,6 +292,72 @@ pub trait GroupArranged<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp:
     /// }
     /// ```
     fn group_arranged<L, V2, T2, R2>(&self, logic: L, empty: T2) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+        where
+            V2: Data,
+            R2: Diff,
+            T2: Trace<K, V2, G::Timestamp, R2>+'static,
+            T2::Batch: Batch<K, V2, G::Timestamp, R2>,
+            L: Fn(&K, &[(&V, R)], &mut Vec<(V2, R2)>)+'static
+    {
+        self.group_arranged_diagnosed(logic, empty, None)
+    }
+
+    /// Applies `group` to arranged data as `group_arranged` does, reporting each evaluation of `logic` to `diagnostics`.
+    ///
+    /// Each time the operator re-evaluates a key, `diagnostics` is called with the key, the time, the accumulated
+    /// input at that time, the output previously accumulated at that time, and the output `logic` now produces
+    /// for it. The operator produces the difference of the latter two, and so these reports can explain where
+    /// unexpected retractions come from. Keys whose input has accumulated to nothing are reported with empty
+    /// inputs and new outputs. Recovering the previous output costs additional work, which is avoided when
+    /// `diagnostics` is `None`.
+    fn group_arranged_diagnosed<L, V2, T2, R2>(
+        &self,
+        logic: L,
+        empty: T2,
+        diagnostics: Option<Box<Fn(&K, &G::Timestamp, &[(&V, R)], &[(V2, R2)], &[(V2, R2)])>>,
+    ) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+        where
+            V2: Data,
+            R2: Diff,
+            T2: Trace<K, V2, G::Timestamp, R2>+'static,
+            T2::Batch: Batch<K, V2, G::Timestamp, R2>,
+            L: Fn(&K, &[(&V, R)], &mut Vec<(V2, R2)>)+'static
+    {
+        self.group_arranged_core(logic, empty, diagnostics, None)
+    }
+
+    /// Applies `group` to arranged data as `group_arranged` does, reusing output for keys whose input is unchanged.
+    ///
+    /// When a key is re-evaluated at several times, the operator hashes the accumulated input at each time before
+    /// calling `logic`. If the hash and then the input itself equal those of the key's previous evaluation, the
+    /// previous output is reused rather than calling `logic` again. This pays off when consecutive evaluations often
+    /// see the same input, as with partially ordered times in iterative scopes, and otherwise costs a hash of each
+    /// input. `logic` must be a deterministic function of its key and input.
+    fn group_arranged_reusing<L, V2, T2, R2>(&self, logic: L, empty: T2) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+        where
+            V: ::std::hash::Hash,
+            R: ::std::hash::Hash,
+            V2: Data,
+            R2: Diff,
+            T2: Trace<K, V2, G::Timestamp, R2>+'static,
+            T2::Batch: Batch<K, V2, G::Timestamp, R2>,
+            L: Fn(&K, &[(&V, R)], &mut Vec<(V2, R2)>)+'static
+    {
+        let fingerprint: Box<Fn(&[(&V, R)]) -> u64> = Box::new(|input: &[(&V, R)]| input.hashed());
+        self.group_arranged_core(logic, empty, None, Some(fingerprint))
+    }
+
+    /// Applies `group` to arranged data, with optional diagnostics and an optional fingerprint of inputs.
+    ///
+    /// This method underlies the other `group_arranged` methods. When `fingerprint` is supplied, the output of a
+    /// key's previous evaluation is reused if the fingerprint and then the input itself are unchanged.
+    fn group_arranged_core<L, V2, T2, R2>(
+        &self,
+        logic: L,
+        empty: T2,
+        diagnostics: Option<Box<Fn(&K, &G::Timestamp, &[(&V, R)], &[(V2, R2)], &[(V2, R2)])>>,
+        fingerprint: Option<Box<Fn(&[(&V, R)]) -> u64>>,
+    ) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
         where
             V2: Data,
             R2: Diff,
@@ -223,7 +375,13 @@ where
     V: Data,
     R: Diff,
 {
-    fn group_arranged<L, V2, T2, R2>(&self, logic: L, empty: T2) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+    fn group_arranged_core<L, V2, T2, R2>(
+        &self,
+        logic: L,
+        empty: T2,
+        diagnostics: Option<Box<Fn(&K, &G::Timestamp, &[(&V, R)], &[(V2, R2)], &[(V2, R2)])>>,
+        fingerprint: Option<Box<Fn(&[(&V, R)]) -> u64>>,
+    ) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
         where
             V2: Data,
             R2: Diff,
@@ -232,7 +390,7 @@ where
             L: Fn(&K, &[(&V, R)], &mut Vec<(V2, R2)>)+'static
     {
         self.arrange_by_key()
-            .group_arranged(logic, empty)
+            .group_arranged_core(logic, empty, diagnostics, fingerprint)
     }
 }
 
@@ -242,7 +400,13 @@ where
     T1: TraceReader<K, V, G::Timestamp, R>+Clone+'static,
     T1::Batch: BatchReader<K, V, G::Timestamp, R> {
 
-    fn group_arranged<L, V2, T2, R2>(&self, logic: L, empty: T2) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+    fn group_arranged_core<L, V2, T2, R2>(
+        &self,
+        logic: L,
+        empty: T2,
+        diagnostics: Option<Box<Fn(&K, &G::Timestamp, &[(&V, R)], &[(V2, R2)], &[(V2, R2)])>>,
+        fingerprint: Option<Box<Fn(&[(&V, R)]) -> u64>>,
+    ) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
         where
             V2: Data,
             R2: Diff,
@@ -429,6 +593,8 @@ where
                         (&mut batch_cursor, batch_storage),
                         &mut interesting_times,
                         &logic,
+                        diagnostics.as_ref().map(|d| &**d),
+                        fingerprint.as_ref().map(|f| &**f),
                         &upper_limit,
                         &mut buffers[..],
                         &mut new_interesting_times,
@@ -577,6 +743,8 @@ where
         batch_cursor: (&mut C3, &'a C3::Storage),
         times: &mut Vec<T>,
         logic: &L,
+        diagnostics: Option<&Fn(&K, &T, &[(&V1, R1)], &[(V2, R2)], &[(V2, R2)])>,
+        fingerprint: Option<&Fn(&[(&V1, R1)]) -> u64>,
         upper_limit: &Antichain<T>,
         outputs: &mut [(T, Vec<(V2, T, R2)>)],
         new_interesting: &mut Vec<T>) -> (usize, usize)
@@ -617,6 +785,9 @@ mod history_replay {
         output_history: ValueHistory<'a, V2, T, R2>,
         input_buffer: Vec<(&'a V1, R1)>,
         output_buffer: Vec<(V2, R2)>,
+        cached_input: Vec<(&'a V1, R1)>,
+        cached_output: Vec<(V2, R2)>,
+        prior_output: Vec<(V2, R2)>,
         output_produced: Vec<((V2, T), R2)>,
         synth_times: Vec<T>,
         meets: Vec<T>,
@@ -639,6 +810,9 @@ mod history_replay {
                 output_history: ValueHistory::new(),
                 input_buffer: Vec::new(),
                 output_buffer: Vec::new(),
+                cached_input: Vec::new(),
+                cached_output: Vec::new(),
+                prior_output: Vec::new(),
                 output_produced: Vec::new(),
                 synth_times: Vec::new(),
                 meets: Vec::new(),
@@ -655,6 +829,8 @@ mod history_replay {
             (batch_cursor, batch_storage): (&mut C3, &'a C3::Storage),
             times: &mut Vec<T>,
             logic: &L,
+            diagnostics: Option<&Fn(&K, &T, &[(&V1, R1)], &[(V2, R2)], &[(V2, R2)])>,
+            fingerprint: Option<&Fn(&[(&V1, R1)]) -> u64>,
             upper_limit: &Antichain<T>,
             outputs: &mut [(T, Vec<(V2, T, R2)>)],
             new_interesting: &mut Vec<T>) -> (usize, usize)
@@ -714,6 +890,10 @@ mod history_replay {
             let mut compute_counter = 0;
             let mut output_counter = 0;
 
+            // The fingerprint of `cached_input`, if it and `cached_output` hold the most recent evaluation of `logic`
+            // for `key`. Only maintained when `fingerprint` is supplied.
+            let mut cached = None;
+
             // We have candidate times from `batch` and `times`, as well as times identified by either
             // `input` or `output`. Finally, we may have synthetic times produced as the join of times
             // we consider in the course of evaluation. As long as any of these times exist, we need to
@@ -796,13 +976,47 @@ mod history_replay {
                         }
                         consolidate(&mut self.input_buffer);
 
-                        // Apply user logic if non-empty input and see what happens!
+                        // Apply user logic if non-empty input and see what happens! If fingerprinting, and the input
+                        // is unchanged since the previous evaluation, user logic would produce the same output, which
+                        // we reuse.
                         if self.input_buffer.len() > 0 {
-                            logic(key, &self.input_buffer[..], &mut self.output_buffer);
-                            self.input_buffer.clear();
+                            let hash = fingerprint.map(|fingerprint| fingerprint(&self.input_buffer[..]));
+                            if hash.is_some() && hash == cached && self.input_buffer == self.cached_input {
+                                self.output_buffer.extend(self.cached_output.iter().cloned());
+                            }
+                            else {
+                                logic(key, &self.input_buffer[..], &mut self.output_buffer);
+                                if hash.is_some() {
+                                    self.cached_input.clear();
+                                    self.cached_input.extend(self.input_buffer.iter().cloned());
+                                    self.cached_output.clear();
+                                    self.cached_output.extend(self.output_buffer.iter().cloned());
+                                    cached = hash;
+                                }
+                            }
                         }
 
                         output_replay.advance_buffer_by(&meet);
+
+                        // Report the evaluation, if requested, along with the output accumulated at `next_time`.
+                        // Both `input_buffer` and `output_buffer` still describe the evaluation.
+                        if let Some(diagnostics) = diagnostics {
+                            self.prior_output.clear();
+                            for &((ref value, ref time), diff) in output_replay.buffer().iter() {
+                                if time.less_equal(&next_time) {
+                                    self.prior_output.push(((*value).clone(), diff));
+                                }
+                            }
+                            for &((ref value, ref time), diff) in self.output_produced.iter() {
+                                if time.less_equal(&next_time) {
+                                    self.prior_output.push((value.clone(), diff));
+                                }
+                            }
+                            consolidate(&mut self.prior_output);
+                            diagnostics(key, &next_time, &self.input_buffer[..], &self.prior_output[..], &self.output_buffer[..]);
+                        }
+                        self.input_buffer.clear();
+
                         for &((ref value, ref time), diff) in output_replay.buffer().iter() {
                             if time.less_equal(&next_time) {
                                 self.output_buffer.push(((*value).clone(), -diff));
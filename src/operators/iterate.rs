@@ -71,6 +71,38 @@ pub trait Iterate<G: Scope, D: Data, R: Diff> {
     fn iterate<F>(&self, logic: F) -> Collection<G, D, R>
         where G::Timestamp: Lattice,
               for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>)->Collection<Child<'a, G, u64>, D, R>;
+
+    /// Iteratively apply `logic` to the source collection until convergence, or for at most `max_iters` rounds.
+    ///
+    /// If the iteration has not converged after `max_iters` applications of `logic` (and at least one), the
+    /// result is the output of the last application. This guards against recursions that diverge or converge
+    /// too slowly, at the cost of returning a partial result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Iterate;
+    /// use differential_dataflow::operators::Consolidate;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // would never converge, but stops with values 4 .. 13.
+    ///         scope.new_collection_from(1 .. 10u32).1
+    ///              .iterate_limited(3, |values| {
+    ///                  values.map(|x| x + 1)
+    ///                        .consolidate()
+    ///              });
+    ///     });
+    /// }
+    /// ```
+    fn iterate_limited<F>(&self, max_iters: u64, logic: F) -> Collection<G, D, R>
+        where G::Timestamp: Lattice,
+              for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>)->Collection<Child<'a, G, u64>, D, R>;
 }
 
 impl<G: Scope, D: Ord+Data+Debug, R: Diff> Iterate<G, D, R> for Collection<G, D, R> {
@@ -91,6 +123,18 @@ impl<G: Scope, D: Ord+Data+Debug, R: Diff> Iterate<G, D, R> for Collection<G, D,
             result.leave()
         })
     }
+
+    fn iterate_limited<F>(&self, max_iters: u64, logic: F) -> Collection<G, D, R>
+        where G::Timestamp: Lattice,
+              for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>)->Collection<Child<'a, G, u64>, D, R> {
+
+        self.inner.scope().scoped(|subgraph| {
+            let variable = Variable::from_limited(self.enter(subgraph), max_iters);
+            let result = logic(&variable);
+            variable.set(&result);
+            result.leave()
+        })
+    }
 }
 
 /// A differential dataflow collection variable
@@ -133,6 +177,7 @@ where G::Timestamp: Lattice {
     collection: Collection<Child<'a, G, u64>, D, R>,
     feedback: Handle<G::Timestamp, u64,(D, Product<G::Timestamp, u64>, R)>,
     source: Collection<Child<'a, G, u64>, D, R>,
+    limit: u64,
 }
 
 impl<'a, G: Scope, D: Data, R: Diff> Variable<'a, G, D, R> where G::Timestamp: Lattice {
@@ -140,14 +185,25 @@ impl<'a, G: Scope, D: Data, R: Diff> Variable<'a, G, D, R> where G::Timestamp: L
     pub fn from(source: Collection<Child<'a, G, u64>, D, R>) -> Variable<'a, G, D, R> {
         let (feedback, updates) = source.inner.scope().loop_variable(u64::max_value(), 1);
         let collection = Collection::new(updates).concat(&source);
-        Variable { collection: collection, feedback: feedback, source: source }
+        Variable { collection: collection, feedback: feedback, source: source, limit: u64::max_value() }
+    }
+    /// Creates a new `Variable` whose value is determined by at most `max_iters` rounds of its definition.
+    ///
+    /// Updates to the variable are fed back only while the iteration counter of their timestamp is less than
+    /// `max_iters`, so that the value of the variable stops changing after `max_iters` rounds.
+    pub fn from_limited(source: Collection<Child<'a, G, u64>, D, R>, max_iters: u64) -> Variable<'a, G, D, R> {
+        let mut variable = Variable::from(source);
+        variable.limit = max_iters;
+        variable
     }
     /// Adds a new source of data to the `Variable`.
     pub fn set(self, result: &Collection<Child<'a, G, u64>, D, R>) -> Collection<Child<'a, G, u64>, D, R> {
+        let limit = self.limit;
         self.source.negate()
                    .concat(result)
                    .inner
                    .map(|(x,t,d)| (x, Product::new(t.outer, t.inner+1), d))
+                   .filter(move |&(_, ref t, _)| t.inner < limit)
                    .connect_loop(self.feedback);
 
         self.collection
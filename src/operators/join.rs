@@ -4,24 +4,33 @@
 //! the multiplication distributes over addition. That is, we will repeatedly evaluate (a + b) * c as (a * c)
 //! + (b * c), and if this is not equal to the former term, little is known about the actual output.
 use std::fmt::Debug;
-use std::ops::Mul;
+use std::ops::{Add, Sub, Neg, Mul};
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
+use timely::order::PartialOrder;
 use timely::progress::Timestamp;
+use timely::progress::frontier::Antichain;
 use timely::dataflow::Scope;
-use timely::dataflow::operators::generic::{Binary, OutputHandle};
-use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::generic::{Binary, Operator, OutputHandle};
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
 use timely::dataflow::operators::Capability;
 use timely::dataflow::channels::pushers::tee::Tee;
 
+use timely_sort::Unsigned;
+
+use abomonation::Abomonation;
+
 use hashable::Hashable;
 use ::{Data, Diff, Collection, AsCollection};
 use lattice::Lattice;
-use operators::arrange::{Arranged, ArrangeByKey, ArrangeBySelf};
-use trace::{BatchReader, Cursor, consolidate};
+use operators::arrange::{Arranged, ArrangeByKey, ArrangeBySelf, TraceAgent};
+use operators::group::GroupArranged;
+use trace::{BatchReader, Cursor, Trace, consolidate};
 use operators::ValueHistory;
 
 use trace::TraceReader;
+use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
 
 /// Join implementations for `(key,val)` data.
 pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
@@ -81,11 +90,11 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
     fn join_map<V2, R2: Diff, D, L>(&self, other: &Collection<G, (K,V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
     where V2: Data, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static;
 
-    /// Matches pairs `(key, val)` and `key` based on `key`, producing the former with frequencies multiplied.
+    /// Matches pairs `(key, val)` and `key` based on `key`, retaining the former if their key is present in the latter.
     ///
-    /// When the second collection contains frequencies that are either zero or one this is the more traditional
-    /// relational semijoin. When the second collection may contain multiplicities, this operation may scale up
-    /// the counts of the records in the first input.
+    /// A key is present in the second collection whenever its accumulated count is non-zero, and its
+    /// multiplicity is otherwise ignored: records of the first collection are produced with their own
+    /// frequencies, rather than scaled up by the count of their key.
     ///
     /// # Examples
     ///
@@ -100,7 +109,7 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
     ///     ::timely::example(|scope| {
     ///
     ///         let x = scope.new_collection_from(vec![(0, 1), (1, 3)]).1;
-    ///         let y = scope.new_collection_from(vec![0, 2]).1;
+    ///         let y = scope.new_collection_from(vec![0, 0, 2]).1;
     ///         let z = scope.new_collection_from(vec![(0, 1)]).1;
     ///
     ///         x.semijoin(&y)
@@ -108,11 +117,14 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
     ///     });
     /// }
     /// ```
-    fn semijoin<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
-    where R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff;
+    fn semijoin<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
+    where R2: Diff;
     /// Matches pairs `(key, val)` and `key` based on `key`, discarding values
     /// in the first collection if their key is present in the second.
     ///
+    /// As with `semijoin`, the multiplicity of a present key is ignored: records of the first collection are
+    /// produced with their own frequencies exactly when the count of their key is zero.
+    ///
     /// # Examples
     ///
     /// ```
@@ -153,11 +165,9 @@ where
         arranged1.join_core(&arranged2, move |k,v1,v2| Some(logic(k,v1,v2)))
     }
 
-    fn semijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
-    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
+    fn semijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R> {
         let arranged1 = self.arrange_by_key();
-        let arranged2 = other.arrange_by_self();
-        arranged1.join_core(&arranged2, |k,v,_| Some((k.clone(), v.clone())))
+        presence(other).join_core(&arranged1, |k,_,v| Some((k.clone(), v.clone())))
     }
 
     fn antijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
@@ -182,10 +192,8 @@ impl<G, K, V, R, T> Join<G, K, V, R> for Arranged<G,K,V,R,T>
         self.join_core(&arranged2, move |k,v1,v2| Some(logic(k,v1,v2)))
     }
 
-    fn semijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
-    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
-        let arranged2 = other.arrange_by_self();
-        self.join_core(&arranged2, |k,v,_| Some((k.clone(), v.clone())))
+    fn semijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R> {
+        presence(other).join_core(self, |k,_,v| Some((k.clone(), v.clone())))
     }
 
     fn antijoin<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
@@ -195,6 +203,64 @@ impl<G, K, V, R, T> Join<G, K, V, R> for Arranged<G,K,V,R,T>
     }
 }
 
+/// Arranges the keys of `keys` whose accumulated count is non-zero, each with count one.
+///
+/// Joining this arrangement with another, rather than `keys` itself, ignores the multiplicities of present keys,
+/// and retracts matches exactly when the count of their key returns to zero.
+fn presence<G, K, R>(keys: &Collection<G, K, R>) -> Arranged<G, K, (), Presence, TraceAgent<K, (), G::Timestamp, Presence, DefaultKeyTrace<K, G::Timestamp, Presence>>>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: Data+Hashable,
+    R: Diff,
+{
+    keys.arrange_by_self()
+        .group_arranged(|_k,s,t| if !s[0].1.is_zero() { t.push(((), Presence { count: 1 })) }, DefaultKeyTrace::new())
+}
+
+/// The count of the presence of a key, which multiplies any difference by at most negating it.
+///
+/// Presence accumulates to zero or one at each time, and so changes by at most one at each time. Multiplication
+/// then requires of the other difference nothing beyond `Diff` itself.
+#[derive(Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone)]
+struct Presence {
+    count: isize,
+}
+
+impl Diff for Presence {
+    #[inline(always)] fn is_zero(&self) -> bool { self.count == 0 }
+    #[inline(always)] fn zero() -> Self { Presence { count: 0 } }
+}
+
+impl Add<Presence> for Presence {
+    type Output = Self;
+    #[inline(always)] fn add(self, rhs: Self) -> Self { Presence { count: self.count + rhs.count } }
+}
+
+impl Sub<Presence> for Presence {
+    type Output = Self;
+    #[inline(always)] fn sub(self, rhs: Self) -> Self { Presence { count: self.count - rhs.count } }
+}
+
+impl Neg for Presence {
+    type Output = Self;
+    #[inline(always)] fn neg(self) -> Self { Presence { count: -self.count } }
+}
+
+impl<R: Diff> Mul<R> for Presence {
+    type Output = R;
+    fn mul(self, rhs: R) -> R {
+        debug_assert!(self.count.abs() <= 1, "presence changed by {:?}", self.count);
+        match self.count {
+            0 => R::zero(),
+            count if count < 0 => -rhs,
+            _ => rhs,
+        }
+    }
+}
+
+impl Abomonation for Presence { }
+
 /// Matches the elements of two arranged traces.
 ///
 /// This method is used by the various `join` implementations, but it can also be used
@@ -391,6 +457,193 @@ impl<G, K, V, R1, T1> JoinCore<G, K, V, R1> for Arranged<G,K,V,R1,T1>
     }
 }
 
+/// Joins each update of `stream` against the accumulated contents of `arranged`.
+///
+/// Each update `((key, val1), time, diff1)` of `stream` is matched against the updates `(key, val2, time2, diff2)`
+/// of `arranged` whose times satisfy `time2 <= time_func(&time)`. For each `val2` whose accumulated count is
+/// non-zero, the update `(logic(key, val1, val2), time, diff1 * count)` is produced. Unlike `join`, updates to
+/// `arranged` do not themselves produce output, which makes this the one-sided building block of delta query
+/// plans, in which each relation is joined against the arrangements of the others as of carefully chosen times.
+///
+/// Updates are held back until the frontier of `arranged` is no longer less or equal to `time_func(&time)`, so that
+/// each lookup sees all updates it must. The trace is allowed to compact up to the frontier of `stream`, except
+/// that it is held back by the lookup times of updates still waiting. So that later lookups stay accurate,
+/// `time_func` must map times in advance of the frontier of `stream` to times that are also in advance of it;
+/// the identity and joins with other times both do.
+///
+/// # Examples
+///
+/// ```
+/// extern crate timely;
+/// extern crate differential_dataflow;
+///
+/// use differential_dataflow::input::Input;
+/// use differential_dataflow::operators::arrange::ArrangeByKey;
+/// use differential_dataflow::operators::join::half_join;
+///
+/// fn main() {
+///     ::timely::example(|scope| {
+///
+///         let x = scope.new_collection_from(vec![(0, 1), (1, 3)]).1;
+///         let y = scope.new_collection_from(vec![(0, 'a'), (1, 'b')]).1
+///                      .arrange_by_key();
+///         let z = scope.new_collection_from(vec![(1, 'a'), (3, 'b')]).1;
+///
+///         half_join(&x, &y, |time| time.clone(), |_key, &a, &b| (a, b))
+///             .assert_eq(&z);
+///     });
+/// }
+/// ```
+pub fn half_join<G, K, V, V2, R, R2, T, FF, S, D>(stream: &Collection<G, (K, V), R>, arranged: &Arranged<G, K, V2, R2, T>, time_func: FF, logic: S) -> Collection<G, D, <R as Mul<R2>>::Output>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: Data+Hashable,
+    V: Data,
+    V2: Data,
+    R: Diff+Mul<R2>,
+    R2: Diff,
+    <R as Mul<R2>>::Output: Diff,
+    T: TraceReader<K, V2, G::Timestamp, R2>+Clone+'static,
+    T::Batch: BatchReader<K, V2, G::Timestamp, R2>+'static,
+    FF: Fn(&G::Timestamp)->G::Timestamp+'static,
+    S: Fn(&K, &V, &V2)->D+'static,
+    D: Data,
+{
+    let mut trace = arranged.trace.clone();
+
+    // release `distinguish_since` capability, as we only read through complete times.
+    trace.distinguish_since(&[]);
+
+    let mut trace = Some(trace);
+    let mut stash = HashMap::new();
+
+    // re-used allocation for the frontier to which the trace may be compacted.
+    let mut compaction = Antichain::new();
+
+    // while the arrangement is already correctly distributed, the stream may not be.
+    let exchange = Exchange::new(move |update: &((K,V),G::Timestamp,R)| (update.0).0.hashed().as_u64());
+
+    stream.inner.binary_frontier(&arranged.stream, exchange, Pipeline, "HalfJoin", move |_capability, _info|
+        move |input1, input2, output| {
+
+            // drain the stream input, stashing updates.
+            input1.for_each(|capability, data|
+                stash.entry(capability.retain())
+                     .or_insert(Vec::new())
+                     .extend(data.drain(..))
+            );
+
+            // batches are read from the shared trace, and need not be retained.
+            input2.for_each(|_capability, data| data.clear());
+
+            if let Some(ref mut trace) = trace {
+
+                let frontier = input2.frontier();
+                for (capability, updates) in stash.iter_mut() {
+
+                    // updates whose lookups are not yet complete remain stashed, and most may be waiting.
+                    if !updates.iter().any(|update| !frontier.less_equal(&time_func(&update.1))) {
+                        continue;
+                    }
+                    let (mut ready, waiting): (Vec<_>, Vec<_>) = updates.drain(..).partition(|update| !frontier.less_equal(&time_func(&update.1)));
+                    *updates = waiting;
+
+                    let mut session = output.session(capability);
+
+                    // sort updates for in-order cursor traversal.
+                    ready.sort_by(|x,y| (x.0).0.cmp(&(y.0).0));
+
+                    let (mut cursor, storage) = trace.cursor();
+
+                    for ((key, val1), time, diff1) in ready.drain(..) {
+                        let lookup = time_func(&time);
+                        cursor.seek_key(&storage, &key);
+                        if cursor.get_key(&storage) == Some(&key) {
+                            while let Some(val2) = cursor.get_val(&storage) {
+                                let mut count = R2::zero();
+                                cursor.map_times(&storage, |t, d| if t.less_equal(&lookup) {
+                                    count = count + d;
+                                });
+                                if !count.is_zero() {
+                                    session.give((logic(&key, &val1, val2), time.clone(), diff1 * count));
+                                }
+                                cursor.step_val(&storage);
+                            }
+                            cursor.rewind_vals(&storage);
+                        }
+                    }
+                }
+            }
+
+            // drop fully processed capabilities.
+            stash.retain(|_,updates| !updates.is_empty());
+
+            // stashed updates may be behind the stream's frontier, and their lookups must remain accurate.
+            compaction.clear();
+            for time in input1.frontier().frontier().iter() {
+                compaction.insert(time.clone());
+            }
+            for updates in stash.values() {
+                for &(_, ref time, _) in updates.iter() {
+                    compaction.insert(time_func(time));
+                }
+            }
+            trace.as_mut().map(|trace| trace.advance_by(compaction.elements()));
+            if input1.frontier().is_empty() && stash.is_empty() {
+                trace = None;
+            }
+        }
+    )
+    .as_collection()
+}
+
+/// Looks up the accumulated values of each key of `keys` in `arranged`.
+///
+/// Each update `(key, time, diff)` of `keys` produces `((key, val), time, diff * count)` for each `val` whose
+/// accumulated count in `arranged` at `time` is a non-zero `count`, and keys absent from `arranged` produce
+/// nothing. As with `half_join`, of which this is a specialization, updates to `arranged` do not themselves
+/// produce output, and so the results reflect the arrangement as of the times of the queries.
+///
+/// # Examples
+///
+/// ```
+/// extern crate timely;
+/// extern crate differential_dataflow;
+///
+/// use differential_dataflow::input::Input;
+/// use differential_dataflow::operators::arrange::ArrangeByKey;
+/// use differential_dataflow::operators::join::lookup;
+///
+/// fn main() {
+///     ::timely::example(|scope| {
+///
+///         let keys = scope.new_collection_from(vec![0, 2]).1;
+///         let data = scope.new_collection_from(vec![(0, 'a'), (0, 'b'), (1, 'c')]).1
+///                         .arrange_by_key();
+///         let result = scope.new_collection_from(vec![(0, 'a'), (0, 'b')]).1;
+///
+///         lookup(&keys, &data)
+///             .assert_eq(&result);
+///     });
+/// }
+/// ```
+pub fn lookup<G, K, V, R, R2, T>(keys: &Collection<G, K, R>, arranged: &Arranged<G, K, V, R2, T>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: Data+Hashable,
+    V: Data,
+    R: Diff+Mul<R2>,
+    R2: Diff,
+    <R as Mul<R2>>::Output: Diff,
+    T: TraceReader<K, V, G::Timestamp, R2>+Clone+'static,
+    T::Batch: BatchReader<K, V, G::Timestamp, R2>+'static,
+{
+    let queries = keys.map(|key| (key, ()));
+    half_join(&queries, arranged, |time| time.clone(), |key, &(), val| (key.clone(), val.clone()))
+}
+
 /// Deferred join computation.
 ///
 /// The structure wraps cursors which allow us to play out join computation at whatever rate we like.
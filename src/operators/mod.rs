@@ -4,12 +4,13 @@
 //! operators have specialized implementations to make them work efficiently, and are in addition 
 //! to several operations defined directly on the `Collection` type (e.g. `map` and `filter`).
 
-pub use self::group::{Group, Threshold, Count, consolidate_from};
+pub use self::group::{Group, Threshold, Count, TopK, consolidate_from};
 pub use self::consolidate::Consolidate;
 pub use self::iterate::Iterate;
 pub use self::join::{Join, JoinCore};
 pub use self::count::CountTotal;
 pub use self::threshold::ThresholdTotal;
+pub use self::monotone::ReduceMonotone;
 
 pub mod arrange;
 pub mod group;
@@ -18,6 +19,7 @@ pub mod iterate;
 pub mod join;
 pub mod count;
 pub mod threshold;
+pub mod monotone;
 // pub mod min;
 
 use ::Diff;
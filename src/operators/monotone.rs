@@ -0,0 +1,197 @@
+//! Maintain a monotone aggregate, such as a minimum or maximum, of the values of each key.
+//!
+//! The `reduce_monotone` operator is an optimization of `group` for aggregations whose result is one of
+//! the values aggregated, when time is totally ordered. Rather than re-evaluating the aggregation over all
+//! of a key's values each time they change, the operator maintains the current result for each key and folds
+//! in newly present values. Only the disappearance of the current result requires a scan of the key's values.
+
+use std::collections::BTreeMap;
+
+use timely::order::TotalOrder;
+use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Pipeline;
+
+use lattice::Lattice;
+use ::{Data, Collection};
+use hashable::Hashable;
+use collection::AsCollection;
+use operators::arrange::{Arranged, ArrangeByKey};
+use trace::{BatchReader, Cursor, TraceReader, consolidate};
+
+/// Extension trait for the `reduce_monotone` differential dataflow method.
+pub trait ReduceMonotone<G: Scope, K: Data, V: Data> where G::Timestamp: TotalOrder+Lattice+Ord {
+    /// Reduces the values of each key to one, by repeatedly applying `logic` to the result so far and a value.
+    ///
+    /// The function `logic` should be associative and commutative, and should return one of its arguments, as
+    /// minimum and maximum do; the result is then not changed by the removal of any value but itself. Values whose
+    /// counts are not positive are ignored, and keys without any other values are absent from the output.
+    ///
+    /// The result for each key is maintained as the input changes, and newly present values are folded into it
+    /// one at a time. If the current result is removed, the result is recomputed from all values of the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::ReduceMonotone;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report the largest value of each key
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .map(|x| (x % 3, x))
+    ///              .reduce_monotone(|acc, x| ::std::cmp::max(acc, x).clone());
+    ///     });
+    /// }
+    /// ```
+    fn reduce_monotone<L>(&self, logic: L) -> Collection<G, (K, V), isize>
+    where L: Fn(&V, &V)->V+'static;
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data> ReduceMonotone<G, K, V> for Collection<G, (K, V), isize>
+where G::Timestamp: TotalOrder+Lattice+Ord {
+    fn reduce_monotone<L>(&self, logic: L) -> Collection<G, (K, V), isize>
+    where L: Fn(&V, &V)->V+'static {
+        reduce_monotone_core(&self.arrange_by_key(), logic)
+    }
+}
+
+// Folds `logic` over the values with positive counts.
+fn fold<V: Clone, L: Fn(&V, &V)->V>(logic: &L, counts: &[(V, isize)]) -> Option<V> {
+    counts.iter().filter(|x| x.1 > 0).fold(None, |acc, &(ref val, _)| match acc {
+        Some(acc) => Some(logic(&acc, val)),
+        None => Some(val.clone()),
+    })
+}
+
+fn reduce_monotone_core<G, K, V, T1, L>(arranged: &Arranged<G, K, V, isize, T1>, logic: L) -> Collection<G, (K, V), isize>
+where
+    G: Scope,
+    G::Timestamp: TotalOrder+Lattice+Ord,
+    K: Data,
+    V: Data,
+    T1: TraceReader<K, V, G::Timestamp, isize>+Clone+'static,
+    T1::Batch: BatchReader<K, V, G::Timestamp, isize>,
+    L: Fn(&V, &V)->V+'static,
+{
+    let mut trace = arranged.trace.clone();
+
+    // the current result for each key with one.
+    let mut results = BTreeMap::<K, V>::new();
+
+    // re-used allocations for the updates of each key in a batch, and the counts of their values.
+    let mut updates = Vec::new();
+    let mut counts = Vec::new();
+    let mut scanned = Vec::new();
+
+    arranged.stream.unary_stream(Pipeline, "ReduceMonotone", move |input, output| {
+
+        input.for_each(|capability, batches| {
+
+            let mut session = output.session(&capability);
+            for batch in batches.drain(..).map(|x| x.item) {
+
+                let mut batch_cursor = batch.cursor();
+                let (mut trace_cursor, trace_storage) = trace.cursor_through(batch.lower()).unwrap();
+
+                while batch_cursor.key_valid(&batch) {
+
+                    let key = batch_cursor.key(&batch);
+
+                    // the updates to the key in the batch, in order of time and value.
+                    while batch_cursor.val_valid(&batch) {
+                        let val = batch_cursor.val(&batch);
+                        batch_cursor.map_times(&batch, |time, diff| updates.push((time.clone(), val.clone(), diff)));
+                        batch_cursor.step_val(&batch);
+                    }
+                    updates.sort();
+
+                    // the counts of the updated values, accumulated from the trace before the batch.
+                    counts.extend(updates.iter().map(|x| (x.1.clone(), 0)));
+                    counts.sort();
+                    counts.dedup();
+                    trace_cursor.seek_key(&trace_storage, key);
+                    let key_in_trace = trace_cursor.get_key(&trace_storage) == Some(key);
+                    if key_in_trace {
+                        for &mut (ref val, ref mut count) in counts.iter_mut() {
+                            trace_cursor.seek_val(&trace_storage, val);
+                            if trace_cursor.get_val(&trace_storage) == Some(val) {
+                                trace_cursor.map_times(&trace_storage, |_, diff| *count += diff);
+                            }
+                        }
+                    }
+
+                    let mut result = results.remove(key);
+                    let mut lower = 0;
+                    while lower < updates.len() {
+
+                        // the updates at the next time.
+                        let mut upper = lower + 1;
+                        while upper < updates.len() && updates[upper].0 == updates[lower].0 { upper += 1; }
+                        let time = updates[lower].0.clone();
+
+                        // apply the updates, noting values that appear and whether the result disappears.
+                        let prior = result.clone();
+                        let mut rescan = false;
+                        for &(_, ref val, diff) in updates[lower .. upper].iter() {
+                            let position = counts.binary_search_by(|x| x.0.cmp(val)).unwrap();
+                            let count = counts[position].1;
+                            counts[position].1 += diff;
+                            if count <= 0 && count + diff > 0 {
+                                result = Some(match result.take() { Some(acc) => logic(&acc, val), None => val.clone() });
+                            }
+                            if count > 0 && count + diff <= 0 && prior.as_ref() == Some(val) {
+                                rescan = true;
+                            }
+                        }
+
+                        // the current result is gone, and must be recomputed from all of the key's values.
+                        if rescan {
+                            if key_in_trace {
+                                trace_cursor.rewind_vals(&trace_storage);
+                                while let Some(val) = trace_cursor.get_val(&trace_storage) {
+                                    let mut count = 0;
+                                    trace_cursor.map_times(&trace_storage, |_, diff| count += diff);
+                                    scanned.push((val.clone(), count));
+                                    trace_cursor.step_val(&trace_storage);
+                                }
+                            }
+                            scanned.extend(updates[.. upper].iter().map(|x| (x.1.clone(), x.2)));
+                            consolidate(&mut scanned, 0);
+                            result = fold(&logic, &scanned[..]);
+                            scanned.clear();
+                        }
+
+                        if result != prior {
+                            if let Some(prior) = prior {
+                                session.give(((key.clone(), prior), time.clone(), -1));
+                            }
+                            if let Some(ref result) = result {
+                                session.give(((key.clone(), result.clone()), time.clone(), 1));
+                            }
+                        }
+
+                        lower = upper;
+                    }
+
+                    if let Some(result) = result {
+                        results.insert(key.clone(), result);
+                    }
+                    updates.clear();
+                    counts.clear();
+
+                    batch_cursor.step_key(&batch);
+                }
+
+                // tidy up the shared input trace.
+                trace.advance_by(batch.upper());
+                trace.distinguish_since(batch.upper());
+            }
+        });
+    })
+    .as_collection()
+}
@@ -17,7 +17,10 @@ use trace::{BatchReader, Cursor, TraceReader};
 
 /// Extension trait for the `distinct` differential dataflow method.
 pub trait ThresholdTotal<G: Scope, K: Data, R: Diff> where G::Timestamp: TotalOrder+Lattice+Ord {
-    /// Reduces the collection to one occurrence of each distinct element.
+    /// Transforms the accumulated count of each element, retaining the element with the resulting count.
+    ///
+    /// The function `thresh` is applied to each key and its non-zero accumulated count, and the output contains
+    /// the key with the result as its count. Keys whose accumulated count is zero are absent from the output.
     ///
     /// # Examples
     ///
@@ -30,14 +33,14 @@ pub trait ThresholdTotal<G: Scope, K: Data, R: Diff> where G::Timestamp: TotalOr
     ///
     /// fn main() {
     ///     ::timely::example(|scope| {
-    ///         // report the number of occurrences of each key
+    ///         // report keys occurring at least twice
     ///         scope.new_collection_from(1 .. 10).1
     ///              .map(|x| x / 3)
-    ///              .threshold_total(|c| c % 2);
+    ///              .threshold_total(|_k, c| if c >= 2 { 1 } else { 0 });
     ///     });
     /// }
     /// ```
-    fn threshold_total<R2: Diff, F: Fn(R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2>;
+    fn threshold_total<R2: Diff, F: Fn(&K, R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2>;
     /// Reduces the collection to one occurrence of each distinct element.
     ///
     /// This reduction only tests whether the weight associated with a record is non-zero, and otherwise
@@ -63,13 +66,13 @@ pub trait ThresholdTotal<G: Scope, K: Data, R: Diff> where G::Timestamp: TotalOr
     /// }
     /// ```
     fn distinct_total(&self) -> Collection<G, K, isize> {
-        self.threshold_total(|c| if c.is_zero() { 0 } else { 1 })        
+        self.threshold_total(|_k, c| if c.is_zero() { 0 } else { 1 })
     }
 }
 
 impl<G: Scope, K: Data+Hashable, R: Diff> ThresholdTotal<G, K, R> for Collection<G, K, R>
 where G::Timestamp: TotalOrder+Lattice+Ord {
-    fn threshold_total<R2: Diff, F: Fn(R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2> {
+    fn threshold_total<R2: Diff, F: Fn(&K, R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2> {
         self.arrange_by_self()
             .threshold_total(thresh)
     }
@@ -81,7 +84,7 @@ where
     T1: TraceReader<K, (), G::Timestamp, R>+Clone+'static,
     T1::Batch: BatchReader<K, (), G::Timestamp, R> {
 
-    fn threshold_total<R2: Diff, F:Fn(R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2> {
+    fn threshold_total<R2: Diff, F: Fn(&K, R)->R2+'static>(&self, thresh: F) -> Collection<G, K, R2> {
 
         let mut trace = self.trace.clone();
 
@@ -110,9 +113,9 @@ where
                         // Apply `thresh` both before and after `diff` is applied to `count`.
                         // If the result is non-zero, send it along.
                         batch_cursor.map_times(&batch, |time, diff| {
-                            let old_weight = if count.is_zero() { R2::zero() } else { thresh(count) };
+                            let old_weight = if count.is_zero() { R2::zero() } else { thresh(key, count) };
                             count = count + diff;
-                            let new_weight = if count.is_zero() { R2::zero() } else { thresh(count) };
+                            let new_weight = if count.is_zero() { R2::zero() } else { thresh(key, count) };
                             let difference = new_weight - old_weight;
                             if !difference.is_zero() {
                                 session.give((key.clone(), time.clone(), difference));
@@ -0,0 +1,95 @@
+//! A generic cursor implementation restricting another cursor to updates at times not exceeding a bound.
+
+use timely::order::PartialOrder;
+
+use super::Cursor;
+
+/// A cursor over the updates of another cursor whose times are less or equal to `as_of`.
+///
+/// Keys and values without any such updates are skipped entirely, so that navigation only
+/// ever lands on keys and values with visible updates.
+pub struct CursorFilterTime<K, V, T, R, C: Cursor<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, R)>,
+    cursor: C,
+    as_of: T,
+}
+
+impl<K, V, T: PartialOrder, R, C: Cursor<K, V, T, R>> CursorFilterTime<K, V, T, R, C> {
+    /// Wraps `cursor`, revealing only updates at times less or equal to `as_of`.
+    pub fn new(cursor: C, storage: &C::Storage, as_of: T) -> Self {
+        let mut result = CursorFilterTime {
+            phantom: ::std::marker::PhantomData,
+            cursor,
+            as_of,
+        };
+        result.skip_keys(storage);
+        result
+    }
+
+    // Indicates that the current value has updates at times less or equal to `as_of`.
+    fn visible(&mut self, storage: &C::Storage) -> bool {
+        let as_of = &self.as_of;
+        let mut visible = false;
+        self.cursor.map_times(storage, |time, _diff| if time.less_equal(as_of) { visible = true; });
+        visible
+    }
+
+    // Advances past values without visible updates.
+    fn skip_vals(&mut self, storage: &C::Storage) {
+        while self.cursor.val_valid(storage) && !self.visible(storage) {
+            self.cursor.step_val(storage);
+        }
+    }
+
+    // Advances past keys without visible updates, leaving the cursor on a visible value.
+    fn skip_keys(&mut self, storage: &C::Storage) {
+        while self.cursor.key_valid(storage) {
+            self.skip_vals(storage);
+            if self.cursor.val_valid(storage) { break; }
+            self.cursor.step_key(storage);
+        }
+    }
+}
+
+impl<K, V, T: PartialOrder, R, C: Cursor<K, V, T, R>> Cursor<K, V, T, R> for CursorFilterTime<K, V, T, R, C> {
+
+    type Storage = C::Storage;
+
+    fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.key_valid(storage) }
+    fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(storage) }
+
+    fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(storage) }
+    fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(storage) }
+
+    fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, mut logic: L) {
+        let as_of = &self.as_of;
+        self.cursor.map_times(storage, |time, diff| if time.less_equal(as_of) { logic(time, diff); });
+    }
+
+    fn step_key(&mut self, storage: &Self::Storage) {
+        self.cursor.step_key(storage);
+        self.skip_keys(storage);
+    }
+    fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
+        self.cursor.seek_key(storage, key);
+        self.skip_keys(storage);
+    }
+
+    fn step_val(&mut self, storage: &Self::Storage) {
+        self.cursor.step_val(storage);
+        self.skip_vals(storage);
+    }
+    fn seek_val(&mut self, storage: &Self::Storage, val: &V) {
+        self.cursor.seek_val(storage, val);
+        self.skip_vals(storage);
+    }
+
+    fn rewind_keys(&mut self, storage: &Self::Storage) {
+        self.cursor.rewind_keys(storage);
+        self.skip_keys(storage);
+    }
+    fn rewind_vals(&mut self, storage: &Self::Storage) {
+        self.cursor.rewind_vals(storage);
+        self.skip_vals(storage);
+    }
+}
@@ -0,0 +1,41 @@
+//! An iterator adapter over the updates of a cursor.
+
+use super::Cursor;
+
+/// An iterator over the `(key, val, updates)` triples of a cursor.
+///
+/// Each item holds a key and value of the cursor together with all of their `(time, diff)` updates. Iteration
+/// starts from the cursor's current position, which for a newly created cursor is its first key and value.
+pub struct CursorIter<'a, K, V, T, R, C: Cursor<K, V, T, R>> where C::Storage: 'a {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: C,
+    storage: &'a C::Storage,
+}
+
+/// Converts a cursor and its storage into an iterator over the cursor's `(key, val, updates)` triples.
+pub fn into_iter<'a, K, V, T, R, C: Cursor<K, V, T, R>>(cursor: C, storage: &'a C::Storage) -> CursorIter<'a, K, V, T, R, C> {
+    CursorIter {
+        phantom: ::std::marker::PhantomData,
+        cursor,
+        storage,
+    }
+}
+
+impl<'a, K: Clone, V: Clone, T: Clone, R, C: Cursor<K, V, T, R>> Iterator for CursorIter<'a, K, V, T, R, C> {
+
+    type Item = (K, V, Vec<(T, R)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.cursor.key_valid(self.storage) {
+            if self.cursor.val_valid(self.storage) {
+                let mut updates = Vec::new();
+                self.cursor.map_times(self.storage, |time, diff| updates.push((time.clone(), diff)));
+                let item = (self.cursor.key(self.storage).clone(), self.cursor.val(self.storage).clone(), updates);
+                self.cursor.step_val(self.storage);
+                return Some(item);
+            }
+            self.cursor.step_key(self.storage);
+        }
+        None
+    }
+}
@@ -0,0 +1,72 @@
+//! An iterator over the keys two batches have in common.
+
+use trace::BatchReader;
+use super::Cursor;
+
+/// An iterator over the keys present in both of two batches, with a cursor into each batch for the key.
+///
+/// The batches are traversed together, as in a merge join, and each item holds a common key together with
+/// a newly acquired cursor into each batch, positioned at that key and its first value. The cursors may be
+/// used to navigate the values and times of the key, and should not be stepped to other keys, which the
+/// iterator will present in turn.
+pub struct JoinCursors<'a, K, V1, V2, T, R1, R2, B1: 'a, B2: 'a>
+where
+    B1: BatchReader<K, V1, T, R1>,
+    B2: BatchReader<K, V2, T, R2>,
+{
+    phantom: ::std::marker::PhantomData<(K, V1, V2, T, R1, R2)>,
+    batch1: &'a B1,
+    batch2: &'a B2,
+    cursor1: B1::Cursor,
+    cursor2: B2::Cursor,
+}
+
+/// Creates an iterator over the keys common to `batch1` and `batch2`, with cursors into each for the key.
+pub fn join_cursors<'a, K, V1, V2, T, R1, R2, B1, B2>(batch1: &'a B1, batch2: &'a B2) -> JoinCursors<'a, K, V1, V2, T, R1, R2, B1, B2>
+where
+    B1: BatchReader<K, V1, T, R1>,
+    B2: BatchReader<K, V2, T, R2>,
+{
+    JoinCursors {
+        phantom: ::std::marker::PhantomData,
+        batch1,
+        batch2,
+        cursor1: batch1.cursor(),
+        cursor2: batch2.cursor(),
+    }
+}
+
+impl<'a, K, V1, V2, T, R1, R2, B1, B2> Iterator for JoinCursors<'a, K, V1, V2, T, R1, R2, B1, B2>
+where
+    K: Ord+Clone,
+    B1: BatchReader<K, V1, T, R1>,
+    B2: BatchReader<K, V2, T, R2>,
+{
+    type Item = (K, B1::Cursor, B2::Cursor);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.cursor1.key_valid(self.batch1) && self.cursor2.key_valid(self.batch2) {
+            match self.cursor1.key(self.batch1).cmp(self.cursor2.key(self.batch2)) {
+                ::std::cmp::Ordering::Less => {
+                    let key = self.cursor2.key(self.batch2).clone();
+                    self.cursor1.seek_key(self.batch1, &key);
+                },
+                ::std::cmp::Ordering::Greater => {
+                    let key = self.cursor1.key(self.batch1).clone();
+                    self.cursor2.seek_key(self.batch2, &key);
+                },
+                ::std::cmp::Ordering::Equal => {
+                    let key = self.cursor1.key(self.batch1).clone();
+                    let mut cursor1 = self.batch1.cursor();
+                    cursor1.seek_key(self.batch1, &key);
+                    let mut cursor2 = self.batch2.cursor();
+                    cursor2.seek_key(self.batch2, &key);
+                    self.cursor1.step_key(self.batch1);
+                    self.cursor2.step_key(self.batch2);
+                    return Some((key, cursor1, cursor2));
+                },
+            }
+        }
+        None
+    }
+}
@@ -1,28 +1,34 @@
 //! A generic cursor implementation merging multiple cursors.
 
-use super::Cursor;
+use super::{Cursor, ReverseCursor};
 
 /// Provides a cursor interface over a list of cursors.
 ///
 /// The `CursorList` tracks the indices of cursors with the minimum key, and the the indices of cursors with
 /// the minimum key and minimum value. It performs no clever management of these sets otherwise.
+///
+/// After seeking a key that some cursors report they lack, through `Cursor::lacks_key`, and that another cursor
+/// has, the list leaves the lacking cursors uninspected until it moves off the key.
 #[derive(Debug)]
 pub struct CursorList<K, V, T, R, C: Cursor<K, V, T, R>> {
     _phantom: ::std::marker::PhantomData<(K, V, T, R)>,
     cursors: Vec<C>,
     min_key: Vec<usize>,
     min_val: Vec<usize>,
+    deferred: Vec<bool>,    // cursors sought to a key they lack, and so positioned beyond the current key.
 }
 
 impl<K, V, T, R, C: Cursor<K, V, T, R>> CursorList<K, V, T, R, C> where K: Ord, V: Ord {
     /// Creates a new cursor list from pre-existing cursors.
     pub fn new(cursors: Vec<C>, storage: &Vec<C::Storage>) -> Self {
 
+        let deferred = vec![false; cursors.len()];
         let mut result = CursorList {
             _phantom: ::std::marker::PhantomData,
             cursors: cursors.into_iter().collect(),
             min_key: Vec::new(),
             min_val: Vec::new(),
+            deferred: deferred,
         };
 
         result.minimize_keys(storage);
@@ -31,15 +37,30 @@ impl<K, V, T, R, C: Cursor<K, V, T, R>> CursorList<K, V, T, R, C> where K: Ord,
 
     // Initialize min_key with the indices of cursors with the minimum key.
     fn minimize_keys(&mut self, storage: &Vec<C::Storage>) {
+        self.select_keys(storage, |key1, key2| key1.lt(key2));
+    }
+
+    // Returns deferred cursors to consideration, before moving off the key they were deferred at.
+    fn undefer(&mut self) {
+        for deferred in self.deferred.iter_mut() { *deferred = false; }
+    }
+
+    // Initialize min_key with the indices of cursors with the maximum key, for reverse navigation.
+    fn maximize_keys(&mut self, storage: &Vec<C::Storage>) {
+        self.select_keys(storage, |key1, key2| key1.gt(key2));
+    }
+
+    // Initialize min_key with the indices of cursors whose keys are least under `precedes`.
+    fn select_keys<F: Fn(&K, &K)->bool>(&mut self, storage: &Vec<C::Storage>, precedes: F) {
 
         self.min_key.clear();
 
         // Determine the index of the cursor with minimum key.
         let mut min_key_index: Option<usize> = None;
         for (index, cursor) in self.cursors.iter().enumerate() {
-            if cursor.key_valid(&storage[index]) {
+            if !self.deferred[index] && cursor.key_valid(&storage[index]) {
                 if let Some(min_index) = min_key_index {
-                    if cursor.key(&storage[index]).lt(self.cursors[min_index].key(&storage[min_index])) {
+                    if precedes(cursor.key(&storage[index]), self.cursors[min_index].key(&storage[min_index])) {
                         min_key_index = Some(index);
                     }
                 }
@@ -51,7 +72,7 @@ impl<K, V, T, R, C: Cursor<K, V, T, R>> CursorList<K, V, T, R, C> where K: Ord,
         // Install each index with equal key.
         if let Some(min_index) = min_key_index {
             for (index, cursor) in self.cursors.iter().enumerate() {
-                if cursor.key_valid(&storage[index]) {
+                if !self.deferred[index] && cursor.key_valid(&storage[index]) {
                     if cursor.key(&storage[index]).eq(self.cursors[min_index].key(&storage[min_index])) {
                         self.min_key.push(index);
                     }
@@ -128,13 +149,22 @@ where
         for &index in self.min_key.iter() {
             self.cursors[index].step_key(&storage[index]);
         }
+        self.undefer();
         self.minimize_keys(storage);
     }
     fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
         for index in 0 .. self.cursors.len() {
             self.cursors[index].seek_key(&storage[index], key);
+            self.deferred[index] = self.cursors[index].lacks_key(&storage[index], key);
         }
         self.minimize_keys(storage);
+        // deferred cursors are beyond `key`, and may be left uninspected only if another cursor is at `key`.
+        if self.deferred.iter().any(|&x| x) {
+            if self.min_key.is_empty() || self.key(storage) != key {
+                self.undefer();
+                self.minimize_keys(storage);
+            }
+        }
     }
 
     // value methods
@@ -156,6 +186,7 @@ where
         for index in 0 .. self.cursors.len() {
             self.cursors[index].rewind_keys(&storage[index]);
         }
+        self.undefer();
         self.minimize_keys(storage);
     }
     fn rewind_vals(&mut self, storage: &Self::Storage) {
@@ -164,4 +195,29 @@ where
         }
         self.minimize_vals(storage);
     }
-}
\ No newline at end of file
+
+    fn lacks_key(&self, storage: &Self::Storage, key: &K) -> bool {
+        self.cursors.iter().enumerate().all(|(index, cursor)| cursor.lacks_key(&storage[index], key))
+    }
+}
+
+impl<K, V, T, R, C: ReverseCursor<K, V, T, R>> ReverseCursor<K, V, T, R> for CursorList<K, V, T, R, C>
+where
+    K: Ord,
+    V: Ord {
+
+    fn step_key_back(&mut self, storage: &Self::Storage) {
+        for &index in self.min_key.iter() {
+            self.cursors[index].step_key_back(&storage[index]);
+        }
+        self.undefer();
+        self.maximize_keys(storage);
+    }
+    fn rewind_keys_back(&mut self, storage: &Self::Storage) {
+        for index in 0 .. self.cursors.len() {
+            self.cursors[index].rewind_keys_back(&storage[index]);
+        }
+        self.undefer();
+        self.maximize_keys(storage);
+    }
+}
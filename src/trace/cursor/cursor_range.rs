@@ -0,0 +1,59 @@
+//! A generic cursor implementation restricting another cursor to keys in a range.
+
+use super::Cursor;
+
+/// A cursor over the updates of another cursor whose keys lie in `[lower, upper)`.
+///
+/// The wrapped cursor is seeked to `lower` on construction and when rewound, and reports its keys as
+/// exhausted once it reaches `upper`, so that keys outside the range are never compared or visited.
+pub struct CursorRange<K, V, T, R, C: Cursor<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(V, T, R)>,
+    cursor: C,
+    lower: K,
+    upper: K,
+}
+
+impl<K: Ord, V, T, R, C: Cursor<K, V, T, R>> CursorRange<K, V, T, R, C> {
+    /// Wraps `cursor`, revealing only keys greater or equal to `lower` and less than `upper`.
+    pub fn new(mut cursor: C, storage: &C::Storage, lower: K, upper: K) -> Self {
+        cursor.seek_key(storage, &lower);
+        CursorRange {
+            phantom: ::std::marker::PhantomData,
+            cursor,
+            lower,
+            upper,
+        }
+    }
+}
+
+impl<K: Ord, V, T, R, C: Cursor<K, V, T, R>> Cursor<K, V, T, R> for CursorRange<K, V, T, R, C> {
+
+    type Storage = C::Storage;
+
+    fn key_valid(&self, storage: &Self::Storage) -> bool {
+        self.cursor.key_valid(storage) && self.cursor.key(storage) < &self.upper
+    }
+    fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(storage) }
+
+    fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(storage) }
+    fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(storage) }
+
+    fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(storage, logic)
+    }
+
+    fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(storage) }
+    fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
+        if key < &self.lower { self.cursor.seek_key(storage, &self.lower); }
+        else { self.cursor.seek_key(storage, key); }
+    }
+
+    fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage) }
+    fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage, val) }
+
+    fn rewind_keys(&mut self, storage: &Self::Storage) {
+        self.cursor.rewind_keys(storage);
+        self.cursor.seek_key(storage, &self.lower);
+    }
+    fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage) }
+}
@@ -8,6 +8,14 @@
 pub mod cursor_list;
 pub mod cursor_pair;
 pub mod cursor_list_neu;
+pub mod cursor_filter;
+pub mod cursor_range;
+pub mod cursor_iter;
+pub mod cursor_join;
+
+use timely::order::PartialOrder;
+
+use ::Diff;
 
 pub use self::cursor_list_neu::CursorList;
 // pub use self::cursor_list::CursorList;
@@ -45,6 +53,24 @@ pub trait Cursor<K, V, T, R> {
 	/// closure's scope.
 	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, logic: L);
 
+	/// The number of `(time, diff)` pairs for the current key and value.
+	fn count_times(&mut self, storage: &Self::Storage) -> usize {
+		let mut count = 0;
+		self.map_times(storage, |_, _| count += 1);
+		count
+	}
+	/// The accumulated difference for the current key and value, over times not greater or equal to any
+	/// element of `upper`.
+	///
+	/// With an empty `upper` all times are accumulated, and the result is the current multiplicity.
+	fn accumulate(&mut self, storage: &Self::Storage, upper: &[T]) -> R where T: PartialOrder, R: Diff {
+		let mut sum = R::zero();
+		self.map_times(storage, |time, diff| {
+			if !upper.iter().any(|t| t.less_equal(time)) { sum = sum + diff; }
+		});
+		sum
+	}
+
 	/// Advances the cursor to the next key. Indicates if the key is valid.
 	fn step_key(&mut self, storage: &Self::Storage);
 	/// Advances the cursor to the specified key. Indicates if the key is valid.
@@ -59,6 +85,24 @@ pub trait Cursor<K, V, T, R> {
 	fn rewind_keys(&mut self, storage: &Self::Storage);
 	/// Rewinds the cursor to the first value for current key.
 	fn rewind_vals(&mut self, storage: &Self::Storage);
+
+	/// Indicates whether `storage` certainly has no key equal to `key`.
+	///
+	/// A cursor over several sources may then leave a source it has sought to `key` uninspected while it is
+	/// positioned at `key`, as the source's next key must be greater. The default implementation returns `false`,
+	/// which is always correct.
+	fn lacks_key(&self, _storage: &Self::Storage, _key: &K) -> bool { false }
+}
+
+/// A cursor that may also navigate keys in descending order.
+///
+/// Values for each key are still presented in ascending order. Cursors are not expected to support
+/// interleaving forward and backward navigation of keys without an intervening rewind.
+pub trait ReverseCursor<K, V, T, R> : Cursor<K, V, T, R> {
+	/// Retreats the cursor to the previous key. The cursor becomes invalid when retreating from the first key.
+	fn step_key_back(&mut self, storage: &Self::Storage);
+	/// Rewinds the cursor to the last key.
+	fn rewind_keys_back(&mut self, storage: &Self::Storage);
 }
 
 /// Debugging and testing utilities for Cursor.
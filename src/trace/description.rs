@@ -55,6 +55,8 @@
 //! will often be a logic bug, as `since` does not advance without a corresponding advance in
 //! times at which data may possibly be sent.
 
+use timely::order::PartialOrder;
+
 /// Describes an interval of partially ordered times.
 ///
 /// A `Description` indicates a set of partially ordered times, and a moment at which they are
@@ -92,4 +94,19 @@ impl<Time> Description<Time> {
 	pub fn upper(&self) -> &[Time] { &self.upper[..] }
 	/// Times from whose future the interval may be observed.
 	pub fn since(&self) -> &[Time] { &self.since[..] }
+}
+
+impl<Time: PartialOrder> Description<Time> {
+	/// The `since` frontier for the merge of intervals described by `self` and `other`.
+	///
+	/// Each interval may only be observed from times in advance of its `since` frontier, and so their merge
+	/// may only be observed from times in advance of both. The result is the more advanced of the two
+	/// frontiers, which is their join. The frontiers must be comparable, one in advance of the other, as
+	/// they are for batches compacted by the same trace; merging incomparable frontiers panics.
+	pub fn merged_since<'a>(&'a self, other: &'a Description<Time>) -> &'a [Time] {
+		let advanced = |these: &[Time], those: &[Time]| these.iter().all(|t1| those.iter().any(|t2| t2.less_equal(t1)));
+		if advanced(self.since(), other.since()) { self.since() }
+		else if advanced(other.since(), self.since()) { other.since() }
+		else { panic!("`merged_since`: incomparable `since` frontiers cannot be merged"); }
+	}
 }
\ No newline at end of file
@@ -0,0 +1,217 @@
+//! Batches with a bloom filter over their keys, to skip batches in point lookups of absent keys.
+//!
+//! A `BloomBatch` wraps another batch type together with a bloom filter of the hashes of its keys. The filter
+//! is built by the builder as updates are pushed, which includes batches sealed by the batcher, and from the
+//! keys of the merged batch when batches are merged. It is consulted by the cursor's `seek_key`: if the filter
+//! certainly lacks the sought key the cursor does not yet search the wrapped batch, and otherwise it performs
+//! the exact search of the wrapped cursor.
+//!
+//! A search skipped this way is deferred rather than abandoned: the cursor remembers the sought key, and seeks
+//! the wrapped cursor to it the first time it is next inspected or stepped. The cursor therefore behaves exactly
+//! as the wrapped cursor, and only avoids searching for absent keys that it is asked to seek past, or rewound
+//! from, before it is inspected. The cursor reports such keys through `Cursor::lacks_key`, which lets a
+//! `CursorList` over the batches of a trace leave it uninspected while the list is positioned at the key.
+
+use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+use ::Diff;
+use lattice::Lattice;
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, Description};
+use super::merge_batcher::MergeBatcher;
+
+// The number of filter bits allocated per distinct key.
+const BITS_PER_KEY: usize = 10;
+// The number of bits set for each key, near optimal for `BITS_PER_KEY` (about a one percent false positive rate).
+const NUM_PROBES: u64 = 7;
+
+/// A bloom filter over the hashes of a set of keys.
+#[derive(Clone, Debug)]
+pub struct BloomFilter {
+	bits: Vec<u64>,
+}
+
+impl BloomFilter {
+	/// Builds a filter containing each key of the supplied batch.
+	pub fn from_batch<K: Hash, V, T, R, B: BatchReader<K, V, T, R>>(batch: &B) -> Self {
+		let mut hashes = Vec::new();
+		let mut cursor = batch.cursor();
+		while cursor.key_valid(batch) {
+			hashes.push(hash_of(cursor.key(batch)));
+			cursor.step_key(batch);
+		}
+		BloomFilter::from_hashes(&hashes)
+	}
+
+	// Builds a filter containing each of the supplied key hashes, sized for one key per hash.
+	fn from_hashes(hashes: &[u64]) -> Self {
+		let words = ((hashes.len() * BITS_PER_KEY) + 63) / 64;
+		let mut filter = BloomFilter { bits: vec![0; words] };
+		for &hash in hashes {
+			filter.insert(hash);
+		}
+		filter
+	}
+
+	/// Indicates whether `key` may be present; a `false` result means it is certainly absent.
+	pub fn may_contain<K: Hash>(&self, key: &K) -> bool {
+		if self.bits.is_empty() { return false; }
+		let hash = hash_of(key);
+		(0 .. NUM_PROBES).all(|i| { let bit = self.probe(hash, i); self.bits[bit / 64] & (1 << (bit % 64)) != 0 })
+	}
+
+	fn insert(&mut self, hash: u64) {
+		for i in 0 .. NUM_PROBES {
+			let bit = self.probe(hash, i);
+			self.bits[bit / 64] |= 1 << (bit % 64);
+		}
+	}
+
+	// Double hashing, as in Kirsch and Mitzenmacher, simulating `NUM_PROBES` hash functions.
+	fn probe(&self, hash: u64, index: u64) -> usize {
+		let delta = hash.rotate_left(32) | 1;
+		(hash.wrapping_add(index.wrapping_mul(delta)) % (self.bits.len() * 64) as u64) as usize
+	}
+}
+
+fn hash_of<K: Hash>(key: &K) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	key.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// A batch wrapped with a bloom filter over its keys.
+#[derive(Clone, Debug)]
+pub struct BloomBatch<B> {
+	/// The wrapped batch.
+	pub batch: B,
+	/// A filter containing each key of `batch`.
+	pub filter: BloomFilter,
+}
+
+impl<B> BloomBatch<B> {
+	/// Wraps `batch`, building a filter over its keys.
+	pub fn new<K: Hash, V, T, R>(batch: B) -> Self where B: BatchReader<K, V, T, R> {
+		let filter = BloomFilter::from_batch(&batch);
+		BloomBatch { batch: batch, filter: filter }
+	}
+}
+
+impl<K: Hash+Ord+Clone, V, T, R, B: BatchReader<K, V, T, R>> BatchReader<K, V, T, R> for BloomBatch<B> {
+	type Cursor = BloomCursor<K, V, T, R, B>;
+	fn cursor(&self) -> Self::Cursor {
+		BloomCursor { phantom: ::std::marker::PhantomData, cursor: RefCell::new(self.batch.cursor()), pending: RefCell::new(None) }
+	}
+	fn len(&self) -> usize { self.batch.len() }
+	fn description(&self) -> &Description<T> { self.batch.description() }
+	fn key_bounds(&self) -> Option<(&K, &K)> { self.batch.key_bounds() }
+	fn distinct_keys_hint(&self) -> usize { self.batch.distinct_keys_hint() }
+	fn estimated_bytes(&self) -> usize { self.batch.estimated_bytes() + self.filter.bits.capacity() * ::std::mem::size_of::<u64>() }
+}
+
+// Batches are sealed by a `MergeBatcher`, which pushes updates into the builder in key order, as the builders
+// of ordered batches expect.
+impl<K, V, T, R, B> Batch<K, V, T, R> for BloomBatch<B>
+where K: Hash+Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff, B: Batch<K, V, T, R> {
+	type Batcher = MergeBatcher<K, V, T, R, Self>;
+	type Builder = BloomBuilder<K, V, T, R, B>;
+	type Merger = BloomMerger<K, V, T, R, B>;
+	fn empty(lower: &[T], upper: &[T]) -> Self { BloomBatch { batch: B::empty(lower, upper), filter: BloomFilter::from_hashes(&[]) } }
+}
+
+/// A cursor over a `BloomBatch`, which defers searching for keys the filter excludes.
+pub struct BloomCursor<K, V, T, R, B: BatchReader<K, V, T, R>> {
+	phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+	cursor: RefCell<B::Cursor>,
+	pending: RefCell<Option<K>>,	// a sought key the filter excludes, which the wrapped cursor has yet to seek.
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BloomCursor<K, V, T, R, B> {
+	// Performs any deferred seek, for methods that can only inspect the cursor.
+	fn settle(&self, storage: &BloomBatch<B>) {
+		if let Some(key) = self.pending.borrow_mut().take() {
+			self.cursor.borrow_mut().seek_key(&storage.batch, &key);
+		}
+	}
+	// Performs any deferred seek, and provides the wrapped cursor.
+	fn settled(&mut self, storage: &BloomBatch<B>) -> &mut B::Cursor {
+		if let Some(key) = self.pending.get_mut().take() {
+			self.cursor.get_mut().seek_key(&storage.batch, &key);
+		}
+		self.cursor.get_mut()
+	}
+}
+
+impl<K: Hash+Ord+Clone, V, T, R, B: BatchReader<K, V, T, R>> Cursor<K, V, T, R> for BloomCursor<K, V, T, R, B> {
+
+	type Storage = BloomBatch<B>;
+
+	#[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.settle(storage); self.cursor.borrow().key_valid(&storage.batch) }
+	#[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.settle(storage); self.cursor.borrow().val_valid(&storage.batch) }
+
+	#[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.settle(storage); self.cursor.borrow().key(&storage.batch) }
+	#[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.settle(storage); self.cursor.borrow().val(&storage.batch) }
+
+	#[inline]
+	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, logic: L) {
+		self.settled(storage).map_times(&storage.batch, logic)
+	}
+
+	#[inline] fn step_key(&mut self, storage: &Self::Storage) { self.settled(storage).step_key(&storage.batch) }
+	#[inline]
+	fn seek_key(&mut self, storage: &Self::Storage, key: &K) {
+		// seeking never moves the cursor backwards, so a deferred seek to a greater key subsumes this one.
+		let pending = self.pending.get_mut().take();
+		let key = match pending { Some(ref pending) if pending > key => pending, _ => key };
+		if storage.filter.may_contain(key) { self.cursor.get_mut().seek_key(&storage.batch, key) }
+		else { *self.pending.get_mut() = Some(key.clone()); }
+	}
+
+	#[inline] fn step_val(&mut self, storage: &Self::Storage) { self.settled(storage).step_val(&storage.batch) }
+	#[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.settled(storage).seek_val(&storage.batch, val) }
+
+	#[inline]
+	fn rewind_keys(&mut self, storage: &Self::Storage) {
+		*self.pending.get_mut() = None;
+		self.cursor.get_mut().rewind_keys(&storage.batch)
+	}
+	#[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.settled(storage).rewind_vals(&storage.batch) }
+
+	#[inline] fn lacks_key(&self, storage: &Self::Storage, key: &K) -> bool { !storage.filter.may_contain(key) }
+}
+
+/// Wrapper type for building bloom filtered batches, which hashes keys as they are pushed.
+pub struct BloomBuilder<K, V, T, R, B: Batch<K, V, T, R>> {
+	builder: B::Builder,
+	hashes: Vec<u64>,	// the hashes of pushed keys, omitting repeats of the previous key's hash.
+}
+
+impl<K, V, T, R, B> Builder<K, V, T, R, BloomBatch<B>> for BloomBuilder<K, V, T, R, B>
+where K: Hash+Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff, B: Batch<K, V, T, R> {
+	fn new() -> Self { BloomBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::new(), hashes: Vec::new() } }
+	fn with_capacity(cap: usize) -> Self {
+		BloomBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::with_capacity(cap), hashes: Vec::new() }
+	}
+	fn push(&mut self, element: (K, V, T, R)) {
+		let hash = hash_of(&element.0);
+		if self.hashes.last() != Some(&hash) { self.hashes.push(hash); }
+		self.builder.push(element)
+	}
+	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> BloomBatch<B> {
+		BloomBatch { batch: self.builder.done(lower, upper, since), filter: BloomFilter::from_hashes(&self.hashes) }
+	}
+}
+
+/// Wrapper type for merging bloom filtered batches.
+pub struct BloomMerger<K, V, T, R, B: Batch<K, V, T, R>> { merger: B::Merger }
+
+impl<K, V, T, R, B> Merger<K, V, T, R, BloomBatch<B>> for BloomMerger<K, V, T, R, B>
+where K: Hash+Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff, B: Batch<K, V, T, R> {
+	fn new(source1: &BloomBatch<B>, source2: &BloomBatch<B>) -> Self { BloomMerger { merger: B::begin_merge(&source1.batch, &source2.batch) } }
+	fn work(&mut self, source1: &BloomBatch<B>, source2: &BloomBatch<B>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
+		self.merger.work(&source1.batch, &source2.batch, frontier, fuel)
+	}
+	fn progress(&self) -> (usize, usize) { self.merger.progress() }
+	fn done(self) -> BloomBatch<B> { BloomBatch::new(self.merger.done()) }
+}
@@ -0,0 +1,62 @@
+//! A `Builder` wrapper that consolidates updates as they are pushed.
+
+use ::Diff;
+
+use trace::{Batch, Builder};
+
+/// Builds batches from sorted updates, folding together adjacent updates with equal `(key, val, time)`.
+///
+/// Updates are held back until an update with a different `(key, val, time)` arrives, at which point
+/// the accumulated difference is passed to the wrapped builder unless it is zero. Updates that are
+/// equal but not adjacent are not consolidated, which is only a concern if pushes are not sorted.
+pub struct ConsolidatingBuilder<K, V, T, R, B> {
+    builder: B,
+    pending: Option<(K, V, T, R)>,
+}
+
+impl<K: Eq, V: Eq, T: Eq, R: Diff, B> ConsolidatingBuilder<K, V, T, R, B> {
+    /// Wraps an existing builder.
+    pub fn from_builder(builder: B) -> Self {
+        ConsolidatingBuilder {
+            builder: builder,
+            pending: None,
+        }
+    }
+}
+
+impl<K, V, T, R, B, Output> Builder<K, V, T, R, Output> for ConsolidatingBuilder<K, V, T, R, B>
+where
+    K: Eq,
+    V: Eq,
+    T: Eq,
+    R: Diff,
+    B: Builder<K, V, T, R, Output>,
+    Output: Batch<K, V, T, R>,
+{
+    fn new() -> Self { Self::from_builder(B::new()) }
+    fn with_capacity(cap: usize) -> Self { Self::from_builder(B::with_capacity(cap)) }
+
+    #[inline(always)]
+    fn push(&mut self, (key, val, time, diff): (K, V, T, R)) {
+        if let Some(ref mut pending) = self.pending {
+            if pending.0 == key && pending.1 == val && pending.2 == time {
+                pending.3 = pending.3 + diff;
+                return;
+            }
+        }
+        if let Some(pending) = ::std::mem::replace(&mut self.pending, Some((key, val, time, diff))) {
+            if !pending.3.is_zero() {
+                self.builder.push(pending);
+            }
+        }
+    }
+
+    fn done(mut self, lower: &[T], upper: &[T], since: &[T]) -> Output {
+        if let Some(pending) = self.pending.take() {
+            if !pending.3.is_zero() {
+                self.builder.push(pending);
+            }
+        }
+        self.builder.done(lower, upper, since)
+    }
+}
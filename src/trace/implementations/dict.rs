@@ -0,0 +1,355 @@
+//! Trace and batch implementations based on sorted ranges, with values interned in a dictionary.
+//!
+//! The types and type aliases in this module start with `Dict`, and describe collections whose data
+//! have the form `(key, val)` where `key` is ordered. Each batch holds its distinct values once, in a
+//! sorted dictionary, and its value layer holds indices into this dictionary. As the dictionary is
+//! sorted, indices order as their values do, and the value layer navigates exactly as in `OrdVal`.
+//!
+//! This representation is appropriate for collections with many updates but few distinct values.
+//! Merging batches merges their dictionaries, and translates the indices of each input batch.
+
+use std::rc::Rc;
+
+use ::Diff;
+use lattice::Lattice;
+
+use trace::layers::{Trie, TupleBuilder};
+use trace::layers::Builder as TrieBuilder;
+use trace::layers::Cursor as TrieCursor;
+use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor};
+use trace::layers::ordered_leaf::{OrderedLeaf, OrderedLeafBuilder};
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, ReverseCursor};
+use trace::description::Description;
+
+use trace::layers::MergeBuilder;
+
+use super::spine_fueled::Spine;
+use super::merge_batcher::MergeBatcher;
+use super::ord::OrdValBatch;
+
+/// A trace implementation using a spine of ordered lists with dictionary encoded values.
+pub type DictSpine<K, V, T, R> = Spine<K, V, T, R, Rc<DictBatch<K, V, T, R>>>;
+
+
+/// An immutable collection of update tuples, from a contiguous interval of logical times.
+#[derive(Debug, Abomonation)]
+pub struct DictBatch<K: Ord, V: Ord, T: Lattice, R> {
+	/// Where all the dataz is, with values replaced by their indices in `dict`.
+	pub layer: OrderedLayer<K, OrderedLayer<u32, OrderedLeaf<T, R>>>,
+	/// The distinct values of the batch, in sorted order.
+	pub dict: Vec<V>,
+	/// Description of the update times this layer represents.
+	pub desc: Description<T>,
+}
+
+impl<K, V, T, R> BatchReader<K, V, T, R> for DictBatch<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
+	type Cursor = DictCursor<T, R>;
+	fn cursor(&self) -> Self::Cursor { DictCursor { cursor: self.layer.cursor() } }
+	fn len(&self) -> usize { <OrderedLayer<K, OrderedLayer<u32, OrderedLeaf<T, R>>> as Trie>::tuples(&self.layer) }
+	fn description(&self) -> &Description<T> { &self.desc }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() + self.dict.capacity() * ::std::mem::size_of::<V>() }
+}
+
+impl<K, V, T, R> Batch<K, V, T, R> for DictBatch<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	type Batcher = MergeBatcher<K, V, T, R, Self>;
+	type Builder = DictBuilder<K, V, T, R>;
+	type Merger = DictMerger<K, V, T, R>;
+
+	fn begin_merge(&self, other: &Self) -> Self::Merger {
+		DictMerger::new(self, other)
+	}
+
+	fn empty(lower: &[T], upper: &[T]) -> Self {
+		DictBatch {
+			layer: OrderedLayer {
+				keys: Vec::new(),
+				offs: vec![0],
+				vals: OrderedLayer { keys: Vec::new(), offs: vec![0], vals: OrderedLeaf { vals: Vec::new() } },
+			},
+			dict: Vec::new(),
+			desc: Description::new(lower, upper, lower),
+		}
+	}
+}
+
+/// State for an in-progress merge.
+pub struct DictMerger<K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff> {
+	// first batch, and position therein.
+	lower1: usize,
+	upper1: usize,
+	// second batch, and position therein.
+	lower2: usize,
+	upper2: usize,
+	// translations from the indices of each batch to indices in the merged dictionary.
+	remap1: Vec<u32>,
+	remap2: Vec<u32>,
+	// the merged dictionary.
+	dict: Vec<V>,
+	// result that we are currently assembling.
+	result: OrderedBuilder<K, OrderedBuilder<u32, OrderedLeafBuilder<T, R>>>,
+	description: Description<T>,
+	// updates of the input batches merged so far, and in total.
+	consumed: usize,
+	total: usize,
+}
+
+// Appends the values of `layer.keys[key]`, with indices translated by `remap`, and their updates to `result`.
+fn copy_key<K, T, R>(
+	result: &mut OrderedBuilder<K, OrderedBuilder<u32, OrderedLeafBuilder<T, R>>>,
+	layer: &OrderedLayer<K, OrderedLayer<u32, OrderedLeaf<T, R>>>,
+	remap: &[u32],
+	key: usize)
+where K: Ord+Clone, T: Ord+Clone, R: Diff {
+	for index in layer.offs[key] .. layer.offs[key+1] {
+		result.vals.keys.push(remap[layer.vals.keys[index] as usize]);
+		result.vals.vals.copy_range(&layer.vals.vals, layer.vals.offs[index], layer.vals.offs[index+1]);
+		result.vals.offs.push(result.vals.vals.vals.len());
+	}
+	result.keys.push(layer.keys[key].clone());
+	result.offs.push(result.vals.keys.len());
+}
+
+impl<K, V, T, R> DictMerger<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	// Merges the values and updates of the equal keys `source1.layer.keys[key1]` and `source2.layer.keys[key2]`.
+	fn merge_key(&mut self, source1: &DictBatch<K, V, T, R>, key1: usize, source2: &DictBatch<K, V, T, R>, key2: usize) {
+
+		let layer1 = &source1.layer;
+		let layer2 = &source2.layer;
+
+		let (mut lower1, upper1) = (layer1.offs[key1], layer1.offs[key1+1]);
+		let (mut lower2, upper2) = (layer2.offs[key2], layer2.offs[key2+1]);
+
+		let start = self.result.vals.keys.len();
+		while lower1 < upper1 || lower2 < upper2 {
+			let order = if lower1 == upper1 { ::std::cmp::Ordering::Greater }
+						else if lower2 == upper2 { ::std::cmp::Ordering::Less }
+						else { self.remap1[layer1.vals.keys[lower1] as usize].cmp(&self.remap2[layer2.vals.keys[lower2] as usize]) };
+			match order {
+				::std::cmp::Ordering::Less => {
+					self.result.vals.keys.push(self.remap1[layer1.vals.keys[lower1] as usize]);
+					self.result.vals.vals.copy_range(&layer1.vals.vals, layer1.vals.offs[lower1], layer1.vals.offs[lower1+1]);
+					self.result.vals.offs.push(self.result.vals.vals.vals.len());
+					lower1 += 1;
+				},
+				::std::cmp::Ordering::Equal => {
+					let lower = self.result.vals.vals.boundary();
+					let upper = self.result.vals.vals.push_merge(
+						(&layer1.vals.vals, layer1.vals.offs[lower1], layer1.vals.offs[lower1+1]),
+						(&layer2.vals.vals, layer2.vals.offs[lower2], layer2.vals.offs[lower2+1])
+					);
+					if upper > lower {
+						self.result.vals.keys.push(self.remap1[layer1.vals.keys[lower1] as usize]);
+						self.result.vals.offs.push(upper);
+					}
+					lower1 += 1;
+					lower2 += 1;
+				},
+				::std::cmp::Ordering::Greater => {
+					self.result.vals.keys.push(self.remap2[layer2.vals.keys[lower2] as usize]);
+					self.result.vals.vals.copy_range(&layer2.vals.vals, layer2.vals.offs[lower2], layer2.vals.offs[lower2+1]);
+					self.result.vals.offs.push(self.result.vals.vals.vals.len());
+					lower2 += 1;
+				},
+			}
+		}
+
+		if self.result.vals.keys.len() > start {
+			self.result.keys.push(layer1.keys[key1].clone());
+			self.result.offs.push(self.result.vals.keys.len());
+		}
+	}
+}
+
+impl<K, V, T, R> Merger<K, V, T, R, DictBatch<K, V, T, R>> for DictMerger<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	fn new(batch1: &DictBatch<K, V, T, R>, batch2: &DictBatch<K, V, T, R>) -> Self {
+
+		assert!(batch1.upper() == batch2.lower());
+
+		// the merged batch may only be observed from times in advance of both `since` frontiers.
+		let since = batch1.description().merged_since(batch2.description());
+		let description = Description::new(batch1.lower(), batch2.upper(), since);
+
+		// Merge the sorted dictionaries, recording where each value lands.
+		let mut dict = Vec::with_capacity(batch1.dict.len() + batch2.dict.len());
+		let mut remap1 = Vec::with_capacity(batch1.dict.len());
+		let mut remap2 = Vec::with_capacity(batch2.dict.len());
+		let (mut index1, mut index2) = (0, 0);
+		while index1 < batch1.dict.len() || index2 < batch2.dict.len() {
+			let order = if index1 == batch1.dict.len() { ::std::cmp::Ordering::Greater }
+						else if index2 == batch2.dict.len() { ::std::cmp::Ordering::Less }
+						else { batch1.dict[index1].cmp(&batch2.dict[index2]) };
+			let position = dict.len() as u32;
+			match order {
+				::std::cmp::Ordering::Less => {
+					dict.push(batch1.dict[index1].clone());
+					remap1.push(position);
+					index1 += 1;
+				},
+				::std::cmp::Ordering::Equal => {
+					dict.push(batch1.dict[index1].clone());
+					remap1.push(position);
+					remap2.push(position);
+					index1 += 1;
+					index2 += 1;
+				},
+				::std::cmp::Ordering::Greater => {
+					dict.push(batch2.dict[index2].clone());
+					remap2.push(position);
+					index2 += 1;
+				},
+			}
+		}
+
+		DictMerger {
+			lower1: 0,
+			upper1: batch1.layer.keys(),
+			lower2: 0,
+			upper2: batch2.layer.keys(),
+			remap1: remap1,
+			remap2: remap2,
+			dict: dict,
+			result: <OrderedBuilder<K, OrderedBuilder<u32, OrderedLeafBuilder<T, R>>> as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
+			description: description,
+			consumed: 0,
+			total: batch1.len() + batch2.len(),
+		}
+	}
+	fn progress(&self) -> (usize, usize) { (self.consumed, self.total) }
+	fn done(self) -> DictBatch<K, V, T, R> {
+
+		assert!(self.lower1 == self.upper1);
+		assert!(self.lower2 == self.upper2);
+
+		// Values whose updates cancelled remain in the dictionary; they are simply never referenced.
+		DictBatch {
+			layer: self.result.done(),
+			dict: self.dict,
+			desc: self.description,
+		}
+	}
+	fn work(&mut self, source1: &DictBatch<K,V,T,R>, source2: &DictBatch<K,V,T,R>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
+
+		let starting_updates = self.result.vals.vals.vals.len();
+		let mut effort = 0;
+
+		let initial_key_pos = self.result.keys.len();
+
+		// Unlike other mergers, the remaining keys of one batch must still be translated, and are not merely copied.
+		while (self.lower1 < self.upper1 || self.lower2 < self.upper2) && effort < *fuel {
+			let order = if self.lower1 == self.upper1 { ::std::cmp::Ordering::Greater }
+						else if self.lower2 == self.upper2 { ::std::cmp::Ordering::Less }
+						else { source1.layer.keys[self.lower1].cmp(&source2.layer.keys[self.lower2]) };
+			match order {
+				::std::cmp::Ordering::Less => {
+					copy_key(&mut self.result, &source1.layer, &self.remap1[..], self.lower1);
+					self.lower1 += 1;
+				},
+				::std::cmp::Ordering::Equal => {
+					let (lower1, lower2) = (self.lower1, self.lower2);
+					self.merge_key(source1, lower1, source2, lower2);
+					self.lower1 += 1;
+					self.lower2 += 1;
+				},
+				::std::cmp::Ordering::Greater => {
+					copy_key(&mut self.result, &source2.layer, &self.remap2[..], self.lower2);
+					self.lower2 += 1;
+				},
+			}
+			effort = self.result.vals.vals.vals.len() - starting_updates;
+		}
+
+		// if we are supplied a frontier, we should compact.
+		if let Some(frontier) = frontier.as_ref() {
+			OrdValBatch::<K, u32, T, R>::advance_builder_from(&mut self.result, frontier, initial_key_pos)
+		}
+
+		self.consumed = source1.layer.vals.offs[source1.layer.offs[self.lower1]] + source2.layer.vals.offs[source2.layer.offs[self.lower2]];
+
+		if effort >= *fuel { *fuel = 0; }
+		else 			   { *fuel -= effort; }
+	}
+}
+
+/// A cursor for navigating a single layer.
+#[derive(Debug)]
+pub struct DictCursor<T: Lattice+Ord+Clone, R: Diff> {
+	cursor: OrderedCursor<OrderedLayer<u32, OrderedLeaf<T, R>>>,
+}
+
+impl<K, V, T, R> Cursor<K, V, T, R> for DictCursor<T, R>
+where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+
+	type Storage = DictBatch<K, V, T, R>;
+
+	fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { &self.cursor.key(&storage.layer) }
+	fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { &storage.dict[*self.cursor.child.key(&storage.layer.vals) as usize] }
+	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, mut logic: L) {
+		self.cursor.child.child.rewind(&storage.layer.vals.vals);
+		while self.cursor.child.child.valid(&storage.layer.vals.vals) {
+			logic(&self.cursor.child.child.key(&storage.layer.vals.vals).0, self.cursor.child.child.key(&storage.layer.vals.vals).1);
+			self.cursor.child.child.step(&storage.layer.vals.vals);
+		}
+	}
+	fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.valid(&storage.layer) }
+	fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.child.valid(&storage.layer.vals) }
+	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); }
+	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); }
+	fn step_val(&mut self, storage: &Self::Storage) { self.cursor.child.step(&storage.layer.vals); }
+	fn seek_val(&mut self, storage: &Self::Storage, val: &V) {
+		// The first index whose value is greater or equal to `val`, which need not be present.
+		let index = match storage.dict.binary_search(val) { Ok(index) => index, Err(index) => index };
+		self.cursor.child.seek(&storage.layer.vals, &(index as u32));
+	}
+	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); }
+	fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.child.rewind(&storage.layer.vals); }
+}
+
+impl<K, V, T, R> ReverseCursor<K, V, T, R> for DictCursor<T, R>
+where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+	fn step_key_back(&mut self, storage: &Self::Storage) { self.cursor.step_back(&storage.layer); }
+	fn rewind_keys_back(&mut self, storage: &Self::Storage) { self.cursor.rewind_back(&storage.layer); }
+}
+
+
+/// A builder for creating dictionary encoded layers from unsorted update tuples.
+///
+/// The dictionary is only known once all updates are present, so updates are buffered until `done`.
+pub struct DictBuilder<K: Ord, V: Ord, T: Ord+Lattice, R: Diff> {
+	updates: Vec<(K, V, T, R)>,
+}
+
+impl<K, V, T, R> Builder<K, V, T, R, DictBatch<K, V, T, R>> for DictBuilder<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+
+	fn new() -> Self { DictBuilder { updates: Vec::new() } }
+	fn with_capacity(cap: usize) -> Self { DictBuilder { updates: Vec::with_capacity(cap) } }
+
+	#[inline(always)]
+	fn push(&mut self, element: (K, V, T, R)) {
+		self.updates.push(element);
+	}
+
+	#[inline(never)]
+	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> DictBatch<K, V, T, R> {
+
+		let mut dict = self.updates.iter().map(|update| update.1.clone()).collect::<Vec<_>>();
+		dict.sort();
+		dict.dedup();
+
+		let mut builder = <OrderedBuilder<K, OrderedBuilder<u32, OrderedLeafBuilder<T, R>>> as TupleBuilder>::with_capacity(self.updates.len());
+		for (key, val, time, diff) in self.updates {
+			let index = dict.binary_search(&val).expect("value missing from dictionary") as u32;
+			builder.push_tuple((key, (index, (time, diff))));
+		}
+
+		DictBatch {
+			layer: builder.done(),
+			dict: dict,
+			desc: Description::new(lower, upper, since)
+		}
+	}
+}
@@ -54,6 +54,7 @@ where K: Clone+Default+HashOrdered+'static, V: Clone+Ord+'static, T: Lattice+Ord
 	}
 	fn len(&self) -> usize { <HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() }
 }
 
 impl<K, V, T, R> Batch<K, V, T, R> for Rc<HashValBatch<K, V, T, R>>
@@ -176,6 +177,7 @@ where K: Clone+Default+HashOrdered+'static, T: Lattice+Ord+Clone+Default+'static
 	}
 	fn len(&self) -> usize { <HashedLayer<K, OrderedLeaf<T, R>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() }
 }
 
 impl<K, T, R> Batch<K, (), T, R> for Rc<HashKeyBatch<K, T, R>>
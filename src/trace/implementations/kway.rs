@@ -0,0 +1,191 @@
+//! Merging of several contiguous batches in a single pass.
+//!
+//! `Batch::begin_merge` merges two batches, and so merging `k` batches pairwise reads the earliest of their
+//! updates once for each level of the cascade. A `KWayMerger` instead merges all `k` batches at once: a loser
+//! tree over the positions of the batches' cursors identifies the least `(key, val)` among them, and each update
+//! of each batch is read exactly once. As with `Merger::work`, the merge proceeds in measured steps of fuel.
+
+use std::fmt::Debug;
+
+use ::Diff;
+use lattice::Lattice;
+use trace::{Batch, BatchReader, Builder, Cursor, consolidate};
+use trace::description::Description;
+
+/// State for an in-progress merge of several contiguous batches.
+///
+/// The batches must be presented to each call to `work` in the order they were presented to `begin_merge_many`.
+pub struct KWayMerger<K, V, T, R, B: Batch<K, V, T, R>> {
+    cursors: Vec<B::Cursor>,
+    // `tree[0]` is the index of the least cursor, and `tree[1 .. k]` the losers of the matches between cursors.
+    tree: Vec<usize>,
+    builder: B::Builder,
+    description: Description<T>,
+    // re-used allocation for the updates to each `(key, val)`.
+    times: Vec<(T, R)>,
+    // updates of the input batches merged so far, and in total.
+    consumed: usize,
+    total: usize,
+}
+
+/// Initiates the merging of a sequence of contiguous batches.
+///
+/// The upper frontier of each batch must equal the lower frontier of the batch that follows it. The result,
+/// once worked to completion, holds the same updates as merging the batches pairwise with `merge_batches`.
+pub fn begin_merge_many<K, V, T, R, B>(batches: &[&B]) -> KWayMerger<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>,
+{
+    assert!(!batches.is_empty(), "`begin_merge_many`: no batches to merge");
+    for pair in batches.windows(2) {
+        if pair[0].upper() != pair[1].lower() {
+            panic!("`begin_merge_many`: batches are not contiguous, with upper {:?} and lower {:?}", pair[0].upper(), pair[1].lower());
+        }
+    }
+
+    // the merged batch may only be observed from times in advance of every `since` frontier.
+    let mut since = batches[0].since().to_vec();
+    for batch in batches[1 ..].iter() {
+        since = Description::new(batch.lower(), batch.upper(), &since[..]).merged_since(batch.description()).to_vec();
+    }
+    let description = Description::new(batches[0].lower(), batches[batches.len() - 1].upper(), &since[..]);
+
+    let mut cursors = batches.iter().map(|batch| batch.cursor()).collect::<Vec<_>>();
+    for index in 0 .. cursors.len() {
+        settle(&mut cursors[index], batches[index]);
+    }
+    let mut tree = vec![0; batches.len()];
+    tree[0] = build(&mut tree, &cursors, batches, 1);
+
+    KWayMerger {
+        cursors: cursors,
+        tree: tree,
+        builder: B::Builder::with_capacity(batches.iter().map(|batch| batch.len()).sum()),
+        description: description,
+        times: Vec::new(),
+        consumed: 0,
+        total: batches.iter().map(|batch| batch.len()).sum(),
+    }
+}
+
+impl<K, V, T, R, B> KWayMerger<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>,
+{
+    /// Perform some amount of work, decrementing `fuel`.
+    ///
+    /// As for `Merger::work`, if `fuel` is non-zero after the call the merge is complete, and `done` extracts
+    /// the merged batch. If a frontier is supplied, the times of merged updates are advanced by it, and updates
+    /// that then coincide are consolidated.
+    pub fn work(&mut self, batches: &[&B], frontier: &Option<Vec<T>>, fuel: &mut usize) {
+        assert_eq!(batches.len(), self.cursors.len());
+        while *fuel > 0 && valid(&self.cursors[self.tree[0]], batches[self.tree[0]]) {
+
+            let winner = self.tree[0];
+            let key = self.cursors[winner].key(batches[winner]).clone();
+            let val = self.cursors[winner].val(batches[winner]).clone();
+
+            // collect the updates of each cursor at `(key, val)`, which are successive winners.
+            loop {
+                let index = self.tree[0];
+                if !valid(&self.cursors[index], batches[index]) { break; }
+                if self.cursors[index].key(batches[index]) != &key || self.cursors[index].val(batches[index]) != &val { break; }
+                let times = &mut self.times;
+                self.cursors[index].map_times(batches[index], |time, diff| times.push((time.clone(), diff)));
+                self.cursors[index].step_val(batches[index]);
+                settle(&mut self.cursors[index], batches[index]);
+                replay(&mut self.tree, &self.cursors, batches, index);
+            }
+
+            self.consumed += self.times.len();
+            *fuel = fuel.saturating_sub(self.times.len());
+
+            if let Some(frontier) = frontier.as_ref() {
+                for update in self.times.iter_mut() {
+                    update.0 = update.0.advance_by(frontier);
+                }
+            }
+            consolidate(&mut self.times, 0);
+            for (time, diff) in self.times.drain(..) {
+                self.builder.push((key.clone(), val.clone(), time, diff));
+            }
+        }
+    }
+    /// Reports the progress of the merge, as the number of input updates merged and in total.
+    pub fn progress(&self) -> (usize, usize) { (self.consumed, self.total) }
+    /// Extracts the merged batch.
+    ///
+    /// This method should only be called once `work` has left `fuel` non-zero.
+    pub fn done(self) -> B {
+        assert!(self.consumed == self.total, "`KWayMerger::done`: the merge is incomplete");
+        self.builder.done(self.description.lower(), self.description.upper(), self.description.since())
+    }
+}
+
+// Indicates if the cursor is at a valid `(key, val)`.
+fn valid<K, V, T, R, C: Cursor<K, V, T, R>>(cursor: &C, storage: &C::Storage) -> bool {
+    cursor.key_valid(storage) && cursor.val_valid(storage)
+}
+
+// Steps past keys without values, so that the cursor is either at a valid `(key, val)` or exhausted.
+fn settle<K, V, T, R, C: Cursor<K, V, T, R>>(cursor: &mut C, storage: &C::Storage) {
+    while cursor.key_valid(storage) && !cursor.val_valid(storage) {
+        cursor.step_key(storage);
+    }
+}
+
+// Indicates if cursor `index1` precedes cursor `index2`; exhausted cursors follow all others, and ties go to the earlier batch.
+fn precedes<K: Ord, V: Ord, T, R, B: BatchReader<K, V, T, R>>(cursors: &[B::Cursor], batches: &[&B], index1: usize, index2: usize) -> bool {
+    let valid1 = valid(&cursors[index1], batches[index1]);
+    let valid2 = valid(&cursors[index2], batches[index2]);
+    if valid1 && valid2 {
+        let pos1 = (cursors[index1].key(batches[index1]), cursors[index1].val(batches[index1]));
+        let pos2 = (cursors[index2].key(batches[index2]), cursors[index2].val(batches[index2]));
+        pos1 < pos2 || (pos1 == pos2 && index1 < index2)
+    }
+    else {
+        valid1 || (!valid2 && index1 < index2)
+    }
+}
+
+// Plays the matches below `node`, recording losers in `tree`, and returns the winner.
+//
+// Node `n` has children `2n` and `2n+1`, and the cursors are the leaves `k .. 2k` for `k` cursors.
+fn build<K: Ord, V: Ord, T, R, B: BatchReader<K, V, T, R>>(tree: &mut [usize], cursors: &[B::Cursor], batches: &[&B], node: usize) -> usize {
+    if node >= cursors.len() {
+        node - cursors.len()
+    }
+    else {
+        let winner1 = build(tree, cursors, batches, 2 * node);
+        let winner2 = build(tree, cursors, batches, 2 * node + 1);
+        if precedes(cursors, batches, winner1, winner2) {
+            tree[node] = winner2;
+            winner1
+        }
+        else {
+            tree[node] = winner1;
+            winner2
+        }
+    }
+}
+
+// Replays the matches from the leaf of cursor `index` to the root, after the cursor has moved.
+fn replay<K: Ord, V: Ord, T, R, B: BatchReader<K, V, T, R>>(tree: &mut [usize], cursors: &[B::Cursor], batches: &[&B], index: usize) {
+    let mut winner = index;
+    let mut node = (index + cursors.len()) / 2;
+    while node > 0 {
+        if precedes(cursors, batches, tree[node], winner) {
+            ::std::mem::swap(&mut tree[node], &mut winner);
+        }
+        node /= 2;
+    }
+    tree[0] = winner;
+}
@@ -40,6 +40,9 @@
 
 // pub mod spine;
 pub mod spine_fueled;
+pub mod spill;
+pub mod retain;
+pub mod kway;
 
 // mod radix_batcher;
 mod merge_batcher;
@@ -47,5 +50,14 @@ mod merge_batcher;
 // pub use self::radix_batcher::RadixBatcher as Batcher;
 pub use self::merge_batcher::MergeBatcher as Batcher;
 
+pub mod consolidating_builder;
+
 pub mod ord;
+pub mod ord_col;
+pub mod rle;
+pub mod dict;
+#[cfg(feature = "bloom")]
+pub mod bloom;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 // pub mod hash;
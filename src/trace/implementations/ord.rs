@@ -16,9 +16,9 @@ use lattice::Lattice;
 use trace::layers::{Trie, TupleBuilder};
 use trace::layers::Builder as TrieBuilder;
 use trace::layers::Cursor as TrieCursor;
-use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor};
+use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor, advance};
 use trace::layers::ordered_leaf::{OrderedLeaf, OrderedLeafBuilder};
-use trace::{Batch, BatchReader, Builder, Merger, Cursor};
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, ReverseCursor};
 use trace::description::Description;
 
 use trace::layers::MergeBuilder;
@@ -26,6 +26,7 @@ use trace::layers::MergeBuilder;
 // use super::spine::Spine;
 use super::spine_fueled::Spine;
 use super::merge_batcher::MergeBatcher;
+use super::consolidating_builder::ConsolidatingBuilder;
 
 use abomonation::abomonated::Abomonated;
 
@@ -57,6 +58,9 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+'static,
 	fn cursor(&self) -> Self::Cursor { OrdValCursor { cursor: self.layer.cursor() } }
 	fn len(&self) -> usize { <OrderedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn key_bounds(&self) -> Option<(&K, &K)> { self.layer.keys.first().map(|lower| (lower, self.layer.keys.last().unwrap())) }
+	fn distinct_keys_hint(&self) -> usize { self.layer.keys.len() }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() }
 }
 
 impl<K, V, T, R> Batch<K, V, T, R> for OrdValBatch<K, V, T, R>
@@ -68,11 +72,124 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 	fn begin_merge(&self, other: &Self) -> Self::Merger {
 		OrdValMerger::new(self, other)
 	}
+
+	fn empty(lower: &[T], upper: &[T]) -> Self {
+		OrdValBatch {
+			layer: OrderedLayer {
+				keys: Vec::new(),
+				offs: vec![0],
+				vals: OrderedLayer { keys: Vec::new(), offs: vec![0], vals: OrderedLeaf { vals: Vec::new() } },
+			},
+			desc: Description::new(lower, upper, lower),
+		}
+	}
 }
 
 impl<K, V, T, R> OrdValBatch<K, V, T, R>
 where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
-	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>, frontier: &[T], key_pos: usize) {
+	/// Allocates a builder that folds together adjacent pushes with equal `(key, val, time)`.
+	///
+	/// Zero accumulations are discarded, so that sorted input with split differences produces the
+	/// same batch as its consolidated form.
+	pub fn consolidating_builder() -> ConsolidatingBuilder<K, V, T, R, OrdValBuilder<K, V, T, R>> {
+		ConsolidatingBuilder::from_builder(OrdValBuilder::new())
+	}
+
+	/// Forms a batch from updates sorted by `(key, val, time)` and consolidated, moving them into place.
+	///
+	/// Unlike pushing updates into a builder, which compares each update with the one before it to determine
+	/// where keys and values start, this only tests keys and values for equality as it appends them to the
+	/// layers. The input must be sorted with no repeated `(key, val, time)` nor zero differences, which is
+	/// checked in debug builds only.
+	pub fn from_sorted(updates: Vec<(K, V, T, R)>, lower: &[T], upper: &[T], since: &[T]) -> Self {
+
+		debug_assert!(
+			updates.windows(2).all(|x| (&x[0].0, &x[0].1, &x[0].2) < (&x[1].0, &x[1].1, &x[1].2)),
+			"`from_sorted`: updates are not sorted by (key, val, time) without repetition"
+		);
+		debug_assert!(updates.iter().all(|x| !x.3.is_zero()), "`from_sorted`: updates have zero differences");
+
+		let mut batch = Self::from_merge_stream(updates, lower, upper);
+		batch.desc = Description::new(lower, upper, since);
+		batch
+	}
+
+	/// Forms a batch from an iterator of updates sorted by `(key, val, time)` and consolidated, in a single pass.
+	///
+	/// This is intended for ingesting pre-sorted external data, such as the contents of a sorted file or the
+	/// output of an external merge. Unlike `from_sorted`, the updates are not first collected into a `Vec`:
+	/// only the layers of the batch itself are allocated, and each update is moved into place as it is read.
+	/// The batch may be observed from `lower` onwards, as with `empty`.
+	///
+	/// The input must be sorted with no repeated `(key, val, time)` nor zero differences, which is checked
+	/// against the previous update in debug builds only.
+	pub fn from_merge_stream<I: IntoIterator<Item=(K, V, T, R)>>(updates: I, lower: &[T], upper: &[T]) -> Self {
+
+		let updates = updates.into_iter();
+
+		let mut keys: Vec<K> = Vec::new();
+		let mut key_offs = vec![0];
+		let mut vals: Vec<V> = Vec::new();
+		let mut val_offs = vec![0];
+		let mut times: Vec<(T, R)> = Vec::with_capacity(updates.size_hint().0);
+
+		for (key, val, time, diff) in updates {
+			debug_assert!(!diff.is_zero(), "`from_merge_stream`: updates have zero differences");
+			if keys.last() != Some(&key) {
+				debug_assert!(keys.last().map(|last| last < &key).unwrap_or(true), "`from_merge_stream`: updates are not sorted by (key, val, time) without repetition");
+				// close the previous key, and its last value.
+				if keys.len() > 0 {
+					val_offs.push(times.len());
+					key_offs.push(vals.len());
+				}
+				keys.push(key);
+				vals.push(val);
+			}
+			else if vals.last() != Some(&val) {
+				debug_assert!(vals.last().map(|last| last < &val).unwrap_or(true), "`from_merge_stream`: updates are not sorted by (key, val, time) without repetition");
+				val_offs.push(times.len());
+				vals.push(val);
+			}
+			else {
+				debug_assert!(times.last().map(|last| last.0 < time).unwrap_or(true), "`from_merge_stream`: updates are not sorted by (key, val, time) without repetition");
+			}
+			times.push((time, diff));
+		}
+		if keys.len() > 0 {
+			val_offs.push(times.len());
+			key_offs.push(vals.len());
+		}
+
+		OrdValBatch {
+			layer: OrderedLayer {
+				keys: keys,
+				offs: key_offs,
+				vals: OrderedLayer { keys: vals, offs: val_offs, vals: OrderedLeaf { vals: times } },
+			},
+			desc: Description::new(lower, upper, lower),
+		}
+	}
+
+	/// Splits the batch into the updates with keys less than `key`, and those with keys greater or equal to it.
+	///
+	/// Both batches have the description of this batch, as splitting by key leaves the times of updates,
+	/// and so the bounds they lie within, unchanged. The key ranges are copied from the layers directly.
+	pub fn split_at_key(&self, key: &K) -> (Self, Self) {
+		let index = advance(&self.layer.keys[..], |x| x < key);
+		let mut lower = <OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::new();
+		let mut upper = <OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::new();
+		if index > 0 { lower.copy_range(&self.layer, 0, index); }
+		if index < self.layer.keys() { upper.copy_range(&self.layer, index, self.layer.keys()); }
+		(
+			OrdValBatch { layer: lower.done(), desc: self.desc.clone() },
+			OrdValBatch { layer: upper.done(), desc: self.desc.clone() },
+		)
+	}
+
+	/// Advances the times of updates from key position `key_pos` onward by `frontier`, and consolidates them.
+	///
+	/// Values and keys left without updates are removed. This is used to compact the output of merges.
+	pub fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>, frontier: &[T], key_pos: usize) {
 
 		let key_start = key_pos;
 		let val_start = layer.offs[key_pos];
@@ -174,6 +291,91 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 	}
 }
 
+// Merges keys of two layers into `result`, processing at most `fuel` of their updates, and returns the number processed.
+//
+// `position(layer, index)` is the number of updates of `layer` preceding its key `index`. Merging stops when the
+// next step would exceed `fuel`, leaving `lower1` and `lower2` at the keys from which to resume.
+fn merge_fueled<K, L, P>(
+	result: &mut OrderedBuilder<K, L>,
+	(layer1, lower1, upper1): (&OrderedLayer<K, L::Trie>, &mut usize, usize),
+	(layer2, lower2, upper2): (&OrderedLayer<K, L::Trie>, &mut usize, usize),
+	fuel: usize,
+	position: P) -> usize
+where K: Ord+Clone, L: MergeBuilder, P: Fn(&OrderedLayer<K, L::Trie>, usize)->usize {
+
+	let mut effort = 0;
+	loop {
+		if *lower1 < upper1 && *lower2 < upper2 {
+			match layer1.keys[*lower1].cmp(&layer2.keys[*lower2]) {
+				::std::cmp::Ordering::Less => {
+					// copy as many keys less than the other layer's key as fuel permits.
+					let run = *lower1 + advance(&layer1.keys[*lower1 .. upper1], |x| x < &layer2.keys[*lower2]);
+					let bound = fuel_bound(layer1, *lower1, run, fuel - effort, &position);
+					if bound == *lower1 { break; }
+					result.copy_range(layer1, *lower1, bound);
+					effort += position(layer1, bound) - position(layer1, *lower1);
+					*lower1 = bound;
+				},
+				::std::cmp::Ordering::Equal => {
+					let cost = position(layer1, *lower1 + 1) - position(layer1, *lower1) + position(layer2, *lower2 + 1) - position(layer2, *lower2);
+					if effort + cost > fuel { break; }
+					result.merge_step((layer1, &mut *lower1, upper1), (layer2, &mut *lower2, upper2));
+					effort += cost;
+				},
+				::std::cmp::Ordering::Greater => {
+					let run = *lower2 + advance(&layer2.keys[*lower2 .. upper2], |x| x < &layer1.keys[*lower1]);
+					let bound = fuel_bound(layer2, *lower2, run, fuel - effort, &position);
+					if bound == *lower2 { break; }
+					result.copy_range(layer2, *lower2, bound);
+					effort += position(layer2, bound) - position(layer2, *lower2);
+					*lower2 = bound;
+				},
+			}
+		}
+		else if *lower1 < upper1 {
+			// the other layer is exhausted, and the remaining keys are copied as fuel permits.
+			let bound = fuel_bound(layer1, *lower1, upper1, fuel - effort, &position);
+			if bound == *lower1 { break; }
+			result.copy_range(layer1, *lower1, bound);
+			effort += position(layer1, bound) - position(layer1, *lower1);
+			*lower1 = bound;
+		}
+		else if *lower2 < upper2 {
+			let bound = fuel_bound(layer2, *lower2, upper2, fuel - effort, &position);
+			if bound == *lower2 { break; }
+			result.copy_range(layer2, *lower2, bound);
+			effort += position(layer2, bound) - position(layer2, *lower2);
+			*lower2 = bound;
+		}
+		else {
+			break;
+		}
+	}
+	effort
+}
+
+// The greatest `bound` in `[lower, upper]` such that the keys in `[lower, bound)` have at most `fuel` updates.
+fn fuel_bound<L, P: Fn(&L, usize)->usize>(layer: &L, lower: usize, upper: usize, fuel: usize, position: &P) -> usize {
+	let start = position(layer, lower);
+	let (mut lower, mut upper) = (lower, upper);
+	while lower < upper {
+		let middle = lower + (upper - lower + 1) / 2;
+		if position(layer, middle) - start <= fuel { lower = middle; }
+		else { upper = middle - 1; }
+	}
+	lower
+}
+
+// Reports unspent fuel to the caller of `Merger::work` if the merge is complete.
+//
+// A merge that is not complete leaves `fuel` at zero, as `Merger::work` requires, even if the next key has more
+// updates than remained. The unspent fuel is not retained: a caller that wants such a key merged must supply at
+// least its number of updates in a single call, as `Spine` does by reserving the fuel its merges could not use.
+fn settle_fuel(complete: bool, effort: usize, fuel: &mut usize) {
+	if complete { *fuel -= effort; }
+	else { *fuel = 0; }
+}
+
 /// State for an in-progress merge.
 pub struct OrdValMerger<K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff> {
 	// first batch, and position therein.
@@ -185,6 +387,9 @@ pub struct OrdValMerger<K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+O
 	// result that we are currently assembling.
 	result: <OrderedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::MergeBuilder,
 	description: Description<T>,
+	// updates of the input batches merged so far, and in total.
+	consumed: usize,
+	total: usize,
 }
 
 impl<K, V, T, R> Merger<K, V, T, R, OrdValBatch<K, V, T, R>> for OrdValMerger<K, V, T, R>
@@ -193,13 +398,8 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 
 		assert!(batch1.upper() == batch2.lower());
 
-		let since = if batch1.description().since().iter().all(|t1| batch2.description().since().iter().any(|t2| t2.less_equal(t1))) {
-			batch2.description().since()
-		}
-		else {
-			batch1.description().since()
-		};
-
+		// the merged batch may only be observed from times in advance of both `since` frontiers.
+		let since = batch1.description().merged_since(batch2.description());
 		let description = Description::new(batch1.lower(), batch2.upper(), since);
 
 		OrdValMerger {
@@ -209,8 +409,11 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 			upper2: batch2.layer.keys(),
 			result: <<OrderedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::MergeBuilder as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
 			description: description,
+			consumed: 0,
+			total: batch1.len() + batch2.len(),
 		}
 	}
+	fn progress(&self) -> (usize, usize) { (self.consumed, self.total) }
 	fn done(self) -> OrdValBatch<K, V, T, R> {
 
 		assert!(self.lower1 == self.upper1);
@@ -223,32 +426,25 @@ where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fm
 	}
 	fn work(&mut self, source1: &OrdValBatch<K,V,T,R>, source2: &OrdValBatch<K,V,T,R>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
 
-		let starting_updates = self.result.vals.vals.vals.len();
-		let mut effort = 0;
-
 		let initial_key_pos = self.result.keys.len();
 
-		// while both mergees are still active
-		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
-			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
-			effort = self.result.vals.vals.vals.len() - starting_updates;
-		}
-
-		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
-			// these are just copies, so let's bite the bullet and just do them.
-			if self.lower1 < self.upper1 { self.result.copy_range(&source1.layer, self.lower1, self.upper1); self.lower1 = self.upper1; }
-			if self.lower2 < self.upper2 { self.result.copy_range(&source2.layer, self.lower2, self.upper2); self.lower2 = self.upper2; }
-		}
-
-		effort = self.result.vals.vals.vals.len() - starting_updates;
+		// merge no more input updates than we have fuel for.
+		let effort = merge_fueled(
+			&mut self.result,
+			(&source1.layer, &mut self.lower1, self.upper1),
+			(&source2.layer, &mut self.lower2, self.upper2),
+			*fuel,
+			|layer, index| layer.vals.offs[layer.offs[index]],
+		);
 
 		// if we are supplied a frontier, we should compact.
 		if let Some(frontier) = frontier.as_ref() {
 			OrdValBatch::advance_builder_from(&mut self.result, frontier, initial_key_pos)
 		}
 
-		if effort >= *fuel { *fuel = 0; }
-		else 			   { *fuel -= effort; }
+		self.consumed = source1.layer.vals.offs[source1.layer.offs[self.lower1]] + source2.layer.vals.offs[source2.layer.offs[self.lower2]];
+
+		settle_fuel(self.lower1 == self.upper1 && self.lower2 == self.upper2, effort, fuel);
 	}
 }
 
@@ -282,6 +478,12 @@ where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 	fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.child.rewind(&storage.layer.vals); }
 }
 
+impl<K, V, T, R> ReverseCursor<K, V, T, R> for OrdValCursor<V, T, R>
+where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+	fn step_key_back(&mut self, storage: &Self::Storage) { self.cursor.step_back(&storage.layer); }
+	fn rewind_keys_back(&mut self, storage: &Self::Storage) { self.cursor.rewind_back(&storage.layer); }
+}
+
 
 /// A builder for creating layers from unsorted update tuples.
 pub struct OrdValBuilder<K: Ord, V: Ord, T: Ord+Lattice, R: Diff> {
@@ -340,6 +542,9 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
 	}
 	fn len(&self) -> usize { <OrderedLayer<K, OrderedLeaf<T, R>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn key_bounds(&self) -> Option<(&K, &K)> { self.layer.keys.first().map(|lower| (lower, self.layer.keys.last().unwrap())) }
+	fn distinct_keys_hint(&self) -> usize { self.layer.keys.len() }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() }
 }
 
 impl<K, T, R> Batch<K, (), T, R> for OrdKeyBatch<K, T, R>
@@ -351,10 +556,40 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
 	fn begin_merge(&self, other: &Self) -> Self::Merger {
 		OrdKeyMerger::new(self, other)
 	}
+
+	fn empty(lower: &[T], upper: &[T]) -> Self {
+		OrdKeyBatch {
+			layer: OrderedLayer { keys: Vec::new(), offs: vec![0], vals: OrderedLeaf { vals: Vec::new() } },
+			desc: Description::new(lower, upper, lower),
+		}
+	}
 }
 
 impl<K, T, R> OrdKeyBatch<K, T, R>
 where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
+	/// Allocates a builder that folds together adjacent pushes with equal `(key, time)`.
+	///
+	/// Zero accumulations are discarded, so that sorted input with split differences produces the
+	/// same batch as its consolidated form.
+	pub fn consolidating_builder() -> ConsolidatingBuilder<K, (), T, R, OrdKeyBuilder<K, T, R>> {
+		ConsolidatingBuilder::from_builder(OrdKeyBuilder::new())
+	}
+
+	/// Splits the batch into the updates with keys less than `key`, and those with keys greater or equal to it.
+	///
+	/// Both batches have the description of this batch, as `OrdValBatch::split_at_key` does.
+	pub fn split_at_key(&self, key: &K) -> (Self, Self) {
+		let index = advance(&self.layer.keys[..], |x| x < key);
+		let mut lower = <OrderedBuilder<K, OrderedLeafBuilder<T, R>> as TupleBuilder>::new();
+		let mut upper = <OrderedBuilder<K, OrderedLeafBuilder<T, R>> as TupleBuilder>::new();
+		if index > 0 { lower.copy_range(&self.layer, 0, index); }
+		if index < self.layer.keys() { upper.copy_range(&self.layer, index, self.layer.keys()); }
+		(
+			OrdKeyBatch { layer: lower.done(), desc: self.desc.clone() },
+			OrdKeyBatch { layer: upper.done(), desc: self.desc.clone() },
+		)
+	}
+
 	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedLeafBuilder<T, R>>, frontier: &[T], key_pos: usize) {
 
 		let key_start = key_pos;
@@ -438,6 +673,9 @@ pub struct OrdKeyMerger<K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: D
 	// result that we are currently assembling.
 	result: <OrderedLayer<K, OrderedLeaf<T, R>> as Trie>::MergeBuilder,
 	description: Description<T>,
+	// updates of the input batches merged so far, and in total.
+	consumed: usize,
+	total: usize,
 }
 
 impl<K, T, R> Merger<K, (), T, R, OrdKeyBatch<K, T, R>> for OrdKeyMerger<K, T, R>
@@ -446,13 +684,8 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
 
 		assert!(batch1.upper() == batch2.lower());
 
-		let since = if batch1.description().since().iter().all(|t1| batch2.description().since().iter().any(|t2| t2.less_equal(t1))) {
-			batch2.description().since()
-		}
-		else {
-			batch1.description().since()
-		};
-
+		// the merged batch may only be observed from times in advance of both `since` frontiers.
+		let since = batch1.description().merged_since(batch2.description());
 		let description = Description::new(batch1.lower(), batch2.upper(), since);
 
 		OrdKeyMerger {
@@ -462,8 +695,11 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
 			upper2: batch2.layer.keys(),
 			result: <<OrderedLayer<K, OrderedLeaf<T, R>> as Trie>::MergeBuilder as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
 			description: description,
+			consumed: 0,
+			total: batch1.len() + batch2.len(),
 		}
 	}
+	fn progress(&self) -> (usize, usize) { (self.consumed, self.total) }
 	fn done(self) -> OrdKeyBatch<K, T, R> {
 
 		assert!(self.lower1 == self.upper1);
@@ -476,31 +712,24 @@ where K: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
 	}
 	fn work(&mut self, source1: &OrdKeyBatch<K,T,R>, source2: &OrdKeyBatch<K,T,R>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
 
-		let starting_updates = self.result.vals.vals.len();
-		let mut effort = 0;
-
 		let initial_key_pos = self.result.keys.len();
 
-		// while both mergees are still active
-		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
-			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
-			effort = self.result.vals.vals.len() - starting_updates;
-		}
-
-		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
-			// these are just copies, so let's bite the bullet and just do them.
-			if self.lower1 < self.upper1 { self.result.copy_range(&source1.layer, self.lower1, self.upper1); self.lower1 = self.upper1; }
-			if self.lower2 < self.upper2 { self.result.copy_range(&source2.layer, self.lower2, self.upper2); self.lower2 = self.upper2; }
-		}
-
-		effort = self.result.vals.vals.len() - starting_updates;
+		// merge no more input updates than we have fuel for.
+		let effort = merge_fueled(
+			&mut self.result,
+			(&source1.layer, &mut self.lower1, self.upper1),
+			(&source2.layer, &mut self.lower2, self.upper2),
+			*fuel,
+			|layer, index| layer.offs[index],
+		);
 
 		if let Some(frontier) = frontier.as_ref() {
 			OrdKeyBatch::advance_builder_from(&mut self.result, frontier, initial_key_pos);
 		}
 
-		if effort >= *fuel { *fuel = 0; }
-		else 			   { *fuel -= effort; }
+		self.consumed = source1.layer.offs[self.lower1] + source2.layer.offs[self.lower2];
+
+		settle_fuel(self.lower1 == self.upper1 && self.lower2 == self.upper2, effort, fuel);
 	}
 }
 
@@ -536,6 +765,11 @@ impl<K: Ord+Clone, T: Lattice+Ord+Clone, R: Diff> Cursor<K, (), T, R> for OrdKey
 	fn rewind_vals(&mut self, _storage: &Self::Storage) { self.valid = true; }
 }
 
+impl<K: Ord+Clone, T: Lattice+Ord+Clone, R: Diff> ReverseCursor<K, (), T, R> for OrdKeyCursor<T, R> {
+	fn step_key_back(&mut self, storage: &Self::Storage) { self.cursor.step_back(&storage.layer); self.valid = true; }
+	fn rewind_keys_back(&mut self, storage: &Self::Storage) { self.cursor.rewind_back(&storage.layer); self.valid = true; }
+}
+
 
 /// A builder for creating layers from unsorted update tuples.
 pub struct OrdKeyBuilder<K: Ord, T: Ord+Lattice, R: Diff> {
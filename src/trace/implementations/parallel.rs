@@ -0,0 +1,88 @@
+//! Merging of large ordered batches using multiple threads.
+//!
+//! Merging two batches with `Merger::work` proceeds through their keys on a single thread. For very large
+//! batches, `merge_parallel` instead splits the keys of both batches into ranges, merges corresponding ranges
+//! on the `rayon` thread pool, and concatenates the results. As each key is merged and compacted exactly as
+//! it would be by a sequential merge, and the ranges partition the keys, the result is identical to that of
+//! `spine_fueled::merge_batches`.
+
+use std::fmt::Debug;
+
+use rayon::prelude::*;
+
+use ::Diff;
+use lattice::Lattice;
+use trace::BatchReader;
+use trace::description::Description;
+use trace::layers::{Trie, MergeBuilder, TupleBuilder};
+use trace::layers::Builder as TrieBuilder;
+use trace::layers::ordered::OrderedBuilder;
+use trace::layers::ordered_leaf::OrderedLeafBuilder;
+use trace::implementations::ord::OrdValBatch;
+use trace::implementations::spine_fueled::merge_batches;
+
+/// The number of updates in the two batches below which `merge_parallel` merges them sequentially.
+pub const PARALLEL_THRESHOLD: usize = 1 << 16;
+
+/// Merges two contiguous batches, as `merge_batches`, using the `rayon` thread pool if they are large.
+///
+/// Batches with fewer than `PARALLEL_THRESHOLD` updates between them are merged sequentially, as the cost
+/// of coordinating threads would exceed the cost of the merge.
+pub fn merge_parallel<K, V, T, R>(batch1: &OrdValBatch<K, V, T, R>, batch2: &OrdValBatch<K, V, T, R>, compaction: Option<&[T]>) -> OrdValBatch<K, V, T, R>
+where
+    K: Ord+Clone+Send+Sync+'static,
+    V: Ord+Clone+Send+Sync+'static,
+    T: Lattice+Ord+Clone+Debug+Send+Sync+'static,
+    R: Diff+Send+Sync,
+{
+    if batch1.len() + batch2.len() < PARALLEL_THRESHOLD {
+        return merge_batches(batch1, batch2, compaction);
+    }
+    if batch1.upper() != batch2.lower() {
+        panic!("`merge_parallel`: batches are not contiguous, with upper {:?} and lower {:?}", batch1.upper(), batch2.lower());
+    }
+
+    let layer1 = &batch1.layer;
+    let layer2 = &batch2.layer;
+
+    // Split at evenly spaced keys of the batch with more keys, and at the same keys in the other batch.
+    let chunks = ::rayon::current_num_threads();
+    let (larger, smaller) = if layer1.keys() >= layer2.keys() { (&layer1.keys, &layer2.keys) } else { (&layer2.keys, &layer1.keys) };
+    let mut splits = Vec::with_capacity(chunks + 1);
+    splits.push((0, 0));
+    for index in 1 .. chunks {
+        let position = (larger.len() * index) / chunks;
+        let other = match smaller.binary_search(&larger[position]) { Ok(x) => x, Err(x) => x };
+        splits.push((position, other));
+    }
+    splits.push((larger.len(), smaller.len()));
+    if layer1.keys() < layer2.keys() {
+        for split in splits.iter_mut() { *split = (split.1, split.0); }
+    }
+    splits.dedup();
+
+    // Merge and compact each range of keys independently.
+    let ranges = splits.windows(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>();
+    let merged = ranges.par_iter().map(|&((lower1, lower2), (upper1, upper2))| {
+        let mut builder = <OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::new();
+        builder.push_merge((layer1, lower1, upper1), (layer2, lower2, upper2));
+        if let Some(frontier) = compaction {
+            OrdValBatch::advance_builder_from(&mut builder, frontier, 0);
+        }
+        builder.done()
+    }).collect::<Vec<_>>();
+
+    // Concatenate the merged ranges, which are in key order.
+    let mut builder = <OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::new();
+    for layer in merged.iter() {
+        if layer.keys() > 0 {
+            builder.copy_range(layer, 0, layer.keys());
+        }
+    }
+
+    let since = batch1.description().merged_since(batch2.description());
+    OrdValBatch {
+        layer: builder.done(),
+        desc: Description::new(batch1.lower(), batch2.upper(), since),
+    }
+}
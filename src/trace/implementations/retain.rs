@@ -0,0 +1,120 @@
+//! A trace that forgets batches older than a retention frontier.
+//!
+//! The `RetainSpine` wraps a `Spine`, and each time its logical compaction frontier advances it computes a
+//! retention frontier from it, and discards the batches whose updates are all at times not greater or equal
+//! to the retention frontier. This bounds the history the trace holds, for example to a sliding window of
+//! recent times.
+//!
+//! Only whole batches are discarded, and a batch with any update at a time greater or equal to the retention
+//! frontier is retained in its entirety. Consequently, the updates at times in advance of the retention
+//! frontier accumulate exactly as they would in the wrapped spine, whereas the updates of older times may or
+//! may not be presented, depending on how they were batched and merged.
+
+use std::fmt::Debug;
+
+use ::Diff;
+use lattice::Lattice;
+use trace::{Batch, BatchReader, InsertError, Trace, TraceReader};
+use trace::cursor::{Cursor, CursorList};
+use trace::implementations::spine_fueled::Spine;
+
+/// A trace that discards batches older than a retention frontier derived from its compaction frontier.
+///
+/// The retention frontier is determined by applying a function to each element of the frontier supplied to
+/// `advance_by`; for example, with `usize` times, `|t| t.saturating_sub(10)` retains the ten most recent times.
+pub struct RetainSpine<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>> {
+    spine: Spine<K, V, T, R, B>,
+    retention: Box<Fn(&T)->T>,  // Maps elements of the compaction frontier to elements of the retention frontier.
+}
+
+impl<K, V, T, R, B> RetainSpine<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+{
+    /// Allocates a trace whose retention frontier is `retention` applied to each element of the compaction frontier.
+    ///
+    /// Each retention frontier element should be less or equal to the compaction frontier element it derives from.
+    pub fn with_retention<F: Fn(&T)->T+'static>(retention: F) -> Self {
+        RetainSpine {
+            spine: Spine::new(),
+            retention: Box::new(retention),
+        }
+    }
+
+    /// The retention frontier derived from the current compaction frontier.
+    pub fn retention_frontier(&self) -> Vec<T> {
+        self.spine.get_logical_compaction().iter().map(|time| (self.retention)(time)).collect()
+    }
+
+    /// The number of physical batches held by the trace, as reported by `Spine::num_batches`.
+    pub fn num_batches(&self) -> usize { self.spine.num_batches() }
+
+    /// The number of updates held by the trace, as reported by `Spine::len`.
+    pub fn len(&self) -> usize { self.spine.len() }
+
+    /// Indicates that the trace holds no updates, as reported by `Spine::is_empty`.
+    pub fn is_empty(&self) -> bool { self.spine.is_empty() }
+
+    // Discards the batches whose updates are all at times not greater or equal to the retention frontier.
+    fn discard(&mut self) {
+        let retention = self.retention_frontier();
+        // A batch holds no time greater or equal to an element of `retention` if each element is greater or
+        // equal to an element of the batch's upper frontier. Batches in merges are always retained, and if
+        // discarding the others would leave a gap none are discarded; they will be once the merges complete
+        // and the frontier next advances.
+        let _ = self.spine.retain_batches(|batch| {
+            !retention.iter().all(|time| batch.upper().iter().any(|upper| upper.less_equal(time)))
+        });
+    }
+}
+
+impl<K, V, T, R, B> TraceReader<K, V, T, R> for RetainSpine<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+{
+    type Batch = B;
+    type Cursor = CursorList<K, V, T, R, <B as BatchReader<K, V, T, R>>::Cursor>;
+
+    fn cursor_through(&mut self, upper: &[T]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage)> {
+        self.spine.cursor_through(upper)
+    }
+    fn advance_by(&mut self, frontier: &[T]) {
+        self.spine.advance_by(frontier);
+        self.discard();
+    }
+    fn advance_frontier(&mut self) -> &[T] { self.spine.advance_frontier() }
+    fn distinguish_since(&mut self, frontier: &[T]) { self.spine.distinguish_since(frontier) }
+    fn distinguish_frontier(&mut self) -> &[T] { self.spine.distinguish_frontier() }
+
+    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) { self.spine.map_batches(f) }
+
+    fn num_updates_hint(&self) -> Option<usize> { self.spine.num_updates_hint() }
+    fn estimated_bytes_hint(&self) -> Option<usize> { self.spine.estimated_bytes_hint() }
+}
+
+impl<K, V, T, R, B> Trace<K, V, T, R> for RetainSpine<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+{
+    /// Allocates a trace retaining all batches, as its retention frontier is the minimum time.
+    fn new() -> Self {
+        Self::with_retention(|_| T::minimum())
+    }
+    fn insert(&mut self, batch: Self::Batch) { self.spine.insert(batch) }
+    fn insert_batch_checked(&mut self, batch: Self::Batch) -> Result<(), InsertError<T>> {
+        self.spine.insert_batch_checked(batch)
+    }
+    fn close(&mut self) { self.spine.close() }
+}
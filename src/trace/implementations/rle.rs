@@ -0,0 +1,297 @@
+//! Trace and batch implementations based on sorted ranges, with run-length encoded differences.
+//!
+//! The types and type aliases in this module start with `RleDiff`, and describe collections whose
+//! data have the form `(key, val)` where `key` is ordered. They hold the same information as their
+//! `OrdVal` counterparts, but store each run of equal consecutive differences only once. Collections
+//! of mostly constant data, for example those whose differences are all `1`, then store little more
+//! than their keys, values, and times.
+
+use std::rc::Rc;
+
+use ::Diff;
+use lattice::Lattice;
+
+use trace::layers::{Trie, TupleBuilder};
+use trace::layers::Builder as TrieBuilder;
+use trace::layers::Cursor as TrieCursor;
+use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor};
+use trace::layers::rle_leaf::{RleLeaf, RleLeafBuilder};
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, ReverseCursor, consolidate};
+use trace::description::Description;
+
+use trace::layers::MergeBuilder;
+
+use super::spine_fueled::Spine;
+use super::merge_batcher::MergeBatcher;
+
+/// A trace implementation using a spine of ordered lists with run-length encoded differences.
+pub type RleDiffSpine<K, V, T, R> = Spine<K, V, T, R, Rc<RleDiffBatch<K, V, T, R>>>;
+
+
+/// An immutable collection of update tuples, from a contiguous interval of logical times.
+#[derive(Debug, Abomonation)]
+pub struct RleDiffBatch<K: Ord, V: Ord, T: Lattice, R> {
+	/// Where all the dataz is.
+	pub layer: OrderedLayer<K, OrderedLayer<V, RleLeaf<T, R>>>,
+	/// Description of the update times this layer represents.
+	pub desc: Description<T>,
+}
+
+impl<K, V, T, R> BatchReader<K, V, T, R> for RleDiffBatch<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+'static, R: Diff {
+	type Cursor = RleDiffCursor<V, T, R>;
+	fn cursor(&self) -> Self::Cursor { RleDiffCursor { cursor: self.layer.cursor() } }
+	fn len(&self) -> usize { <OrderedLayer<K, OrderedLayer<V, RleLeaf<T, R>>> as Trie>::tuples(&self.layer) }
+	fn description(&self) -> &Description<T> { &self.desc }
+	fn estimated_bytes(&self) -> usize { self.layer.heap_size() }
+}
+
+impl<K, V, T, R> Batch<K, V, T, R> for RleDiffBatch<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	type Batcher = MergeBatcher<K, V, T, R, Self>;
+	type Builder = RleDiffBuilder<K, V, T, R>;
+	type Merger = RleDiffMerger<K, V, T, R>;
+
+	fn begin_merge(&self, other: &Self) -> Self::Merger {
+		RleDiffMerger::new(self, other)
+	}
+
+	fn empty(lower: &[T], upper: &[T]) -> Self {
+		RleDiffBatch {
+			layer: OrderedLayer {
+				keys: Vec::new(),
+				offs: vec![0],
+				vals: OrderedLayer { keys: Vec::new(), offs: vec![0], vals: RleLeaf { keys: Vec::new(), runs: Vec::new() } },
+			},
+			desc: Description::new(lower, upper, lower),
+		}
+	}
+}
+
+impl<K, V, T, R> RleDiffBatch<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, RleLeafBuilder<T, R>>>, frontier: &[T], key_pos: usize) {
+
+		let key_start = key_pos;
+		let val_start = layer.offs[key_pos];
+		let time_start = layer.vals.offs[val_start];
+
+		// 1. Decode the updates from `time_start` onward; they will be re-encoded as they are consolidated.
+		let mut updates = layer.vals.vals.drain_from(time_start);
+		for update in updates.iter_mut() {
+			update.0 = update.0.advance_by(frontier);
+		}
+
+		// 2. For each `(val, off)` pair, sort the range, compact, re-encode, and rewrite `off`.
+		//    This may leave `val` with an empty range; filtering happens in step 3.
+		let mut scratch = Vec::new();
+		for i in val_start .. layer.vals.keys.len() {
+
+			// NB: layer.vals.offs[i+1] will be used next iteration, and should not be changed.
+			let lower = layer.vals.offs[i] - time_start;
+			let upper = layer.vals.offs[i+1] - time_start;
+
+			layer.vals.offs[i] = layer.vals.vals.boundary();
+
+			scratch.extend(updates[lower .. upper].iter().cloned());
+			consolidate(&mut scratch, 0);
+			for update in scratch.drain(..) {
+				layer.vals.vals.push_tuple(update);
+			}
+		}
+		let write_position = layer.vals.vals.boundary();
+		layer.vals.offs[layer.vals.keys.len()] = write_position;
+
+		// 3. For each `(key, off)` pair, (values already sorted), filter vals, and rewrite `off`.
+		//    This may leave `key` with an empty range. Filtering happens in step 4.
+		let mut write_position = val_start;
+		for i in key_start .. layer.keys.len() {
+
+			let lower = layer.offs[i];
+			let upper = layer.offs[i+1];
+
+			layer.offs[i] = write_position;
+
+			// values should already be sorted, but some might now be empty.
+			for index in lower .. upper {
+				let val_lower = layer.vals.offs[index];
+				let val_upper = layer.vals.offs[index+1];
+				if val_lower < val_upper {
+					layer.vals.keys.swap(write_position, index);
+					layer.vals.offs[write_position+1] = layer.vals.offs[index+1];
+					write_position += 1;
+				}
+			}
+		}
+		layer.vals.keys.truncate(write_position);
+		layer.vals.offs.truncate(write_position + 1);
+		layer.offs[layer.keys.len()] = write_position;
+
+		// 4. Remove empty keys.
+		let mut write_position = key_start;
+		for i in key_start .. layer.keys.len() {
+
+			let lower = layer.offs[i];
+			let upper = layer.offs[i+1];
+
+			if lower < upper {
+				layer.keys.swap(write_position, i);
+				write_position += 1;
+			}
+		}
+		layer.offs.dedup();
+		layer.keys.truncate(write_position);
+		layer.offs.truncate(write_position+1);
+	}
+}
+
+/// State for an in-progress merge.
+pub struct RleDiffMerger<K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff> {
+	// first batch, and position therein.
+	lower1: usize,
+	upper1: usize,
+	// second batch, and position therein.
+	lower2: usize,
+	upper2: usize,
+	// result that we are currently assembling.
+	result: <OrderedLayer<K, OrderedLayer<V, RleLeaf<T, R>>> as Trie>::MergeBuilder,
+	description: Description<T>,
+	// updates of the input batches merged so far, and in total.
+	consumed: usize,
+	total: usize,
+}
+
+impl<K, V, T, R> Merger<K, V, T, R, RleDiffBatch<K, V, T, R>> for RleDiffMerger<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+	fn new(batch1: &RleDiffBatch<K, V, T, R>, batch2: &RleDiffBatch<K, V, T, R>) -> Self {
+
+		assert!(batch1.upper() == batch2.lower());
+
+		// the merged batch may only be observed from times in advance of both `since` frontiers.
+		let since = batch1.description().merged_since(batch2.description());
+		let description = Description::new(batch1.lower(), batch2.upper(), since);
+
+		RleDiffMerger {
+			lower1: 0,
+			upper1: batch1.layer.keys(),
+			lower2: 0,
+			upper2: batch2.layer.keys(),
+			result: <<OrderedLayer<K, OrderedLayer<V, RleLeaf<T, R>>> as Trie>::MergeBuilder as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
+			description: description,
+			consumed: 0,
+			total: batch1.len() + batch2.len(),
+		}
+	}
+	fn progress(&self) -> (usize, usize) { (self.consumed, self.total) }
+	fn done(self) -> RleDiffBatch<K, V, T, R> {
+
+		assert!(self.lower1 == self.upper1);
+		assert!(self.lower2 == self.upper2);
+
+		RleDiffBatch {
+			layer: self.result.done(),
+			desc: self.description,
+		}
+	}
+	fn work(&mut self, source1: &RleDiffBatch<K,V,T,R>, source2: &RleDiffBatch<K,V,T,R>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
+
+		let starting_updates = self.result.vals.vals.keys.len();
+		let mut effort = 0;
+
+		let initial_key_pos = self.result.keys.len();
+
+		// while both mergees are still active
+		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
+			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
+			effort = self.result.vals.vals.keys.len() - starting_updates;
+		}
+
+		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
+			// these are just copies, so let's bite the bullet and just do them.
+			if self.lower1 < self.upper1 { self.result.copy_range(&source1.layer, self.lower1, self.upper1); self.lower1 = self.upper1; }
+			if self.lower2 < self.upper2 { self.result.copy_range(&source2.layer, self.lower2, self.upper2); self.lower2 = self.upper2; }
+		}
+
+		effort = self.result.vals.vals.keys.len() - starting_updates;
+
+		// if we are supplied a frontier, we should compact.
+		if let Some(frontier) = frontier.as_ref() {
+			RleDiffBatch::advance_builder_from(&mut self.result, frontier, initial_key_pos)
+		}
+
+		self.consumed = source1.layer.vals.offs[source1.layer.offs[self.lower1]] + source2.layer.vals.offs[source2.layer.offs[self.lower2]];
+
+		if effort >= *fuel { *fuel = 0; }
+		else 			   { *fuel -= effort; }
+	}
+}
+
+/// A cursor for navigating a single layer.
+#[derive(Debug)]
+pub struct RleDiffCursor<V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff> {
+	cursor: OrderedCursor<OrderedLayer<V, RleLeaf<T, R>>>,
+}
+
+impl<K, V, T, R> Cursor<K, V, T, R> for RleDiffCursor<V, T, R>
+where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+
+	type Storage = RleDiffBatch<K, V, T, R>;
+
+	fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { &self.cursor.key(&storage.layer) }
+	fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { &self.cursor.child.key(&storage.layer.vals) }
+	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, mut logic: L) {
+		self.cursor.child.child.rewind(&storage.layer.vals.vals);
+		while self.cursor.child.child.valid(&storage.layer.vals.vals) {
+			logic(self.cursor.child.child.key(&storage.layer.vals.vals), *self.cursor.child.child.diff(&storage.layer.vals.vals));
+			self.cursor.child.child.step(&storage.layer.vals.vals);
+		}
+	}
+	fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.valid(&storage.layer) }
+	fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.child.valid(&storage.layer.vals) }
+	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); }
+	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); }
+	fn step_val(&mut self, storage: &Self::Storage) { self.cursor.child.step(&storage.layer.vals); }
+	fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.child.seek(&storage.layer.vals, val); }
+	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); }
+	fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.child.rewind(&storage.layer.vals); }
+}
+
+impl<K, V, T, R> ReverseCursor<K, V, T, R> for RleDiffCursor<V, T, R>
+where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+	fn step_key_back(&mut self, storage: &Self::Storage) { self.cursor.step_back(&storage.layer); }
+	fn rewind_keys_back(&mut self, storage: &Self::Storage) { self.cursor.rewind_back(&storage.layer); }
+}
+
+
+/// A builder for creating run-length encoded layers from unsorted update tuples.
+pub struct RleDiffBuilder<K: Ord, V: Ord, T: Ord+Lattice, R: Diff> {
+	builder: OrderedBuilder<K, OrderedBuilder<V, RleLeafBuilder<T, R>>>,
+}
+
+impl<K, V, T, R> Builder<K, V, T, R, RleDiffBatch<K, V, T, R>> for RleDiffBuilder<K, V, T, R>
+where K: Ord+Clone+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+::std::fmt::Debug+'static, R: Diff {
+
+	fn new() -> Self {
+		RleDiffBuilder {
+			builder: OrderedBuilder::<K, OrderedBuilder<V, RleLeafBuilder<T, R>>>::new()
+		}
+	}
+	fn with_capacity(cap: usize) -> Self {
+		RleDiffBuilder {
+			builder: <OrderedBuilder<K, OrderedBuilder<V, RleLeafBuilder<T, R>>> as TupleBuilder>::with_capacity(cap)
+		}
+	}
+
+	#[inline(always)]
+	fn push(&mut self, (key, val, time, diff): (K, V, T, R)) {
+		self.builder.push_tuple((key, (val, (time, diff))));
+	}
+
+	#[inline(never)]
+	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> RleDiffBatch<K, V, T, R> {
+		RleDiffBatch {
+			layer: self.builder.done(),
+			desc: Description::new(lower, upper, since)
+		}
+	}
+}
@@ -0,0 +1,231 @@
+//! A trace that spills cold batches to files.
+//!
+//! The `SpillSpine` wraps a `Spine`, and whenever the spine holds more than a configured number of
+//! batches it serializes the oldest complete batches to files in a directory. These batches are no
+//! longer merged or compacted, and are reloaded from their files when a cursor is requested. A reloaded
+//! batch is shared with later cursors for as long as some cursor's storage still holds it.
+
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ::Diff;
+use lattice::Lattice;
+use trace::{Batch, BatchReader, InsertError, Trace, TraceReader};
+use trace::cursor::{Cursor, CursorList};
+use trace::rc_blanket_impls::RcBatchCursor;
+use trace::implementations::spine_fueled::Spine;
+
+// Distinguishes the default directories of spines within one process.
+static SPILL_DIRECTORIES: AtomicUsize = AtomicUsize::new(0);
+
+/// Converts batches to and from bytes, so that they may be written to and read from files.
+pub trait BatchSerializer<B> {
+    /// Appends a representation of `batch` to `bytes`.
+    fn serialize(&mut self, batch: &B, bytes: &mut Vec<u8>);
+    /// Reconstructs a batch from bytes produced by `serialize`.
+    fn deserialize(&mut self, bytes: &[u8]) -> B;
+}
+
+/// A trace that keeps recent batches in memory, and spills older batches to files.
+///
+/// Spilled batches are the oldest batches of the trace, and are presented first by its cursors.
+/// They are deleted when dropped from the trace with `advance_by(&[])`, or when the trace is dropped,
+/// which also removes the directory if the trace created it.
+/// A batch whose file cannot be written is kept in memory instead, in its place among the spilled
+/// batches. Acquiring a cursor or mapping across the batches panics if a spill file can no longer be
+/// read, as the updates of its batch would otherwise be lost.
+pub struct SpillSpine<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>, S: BatchSerializer<B>> {
+    spine: Spine<K, V, T, R, B>,
+    serializer: S,
+    directory: PathBuf,
+    created: bool,              // Whether the trace created `directory`, and should remove it when dropped.
+    spilled: Vec<Spilled<B>>,   // Batches removed from the spine, from oldest to newest.
+    resident: usize,            // The number of batches the in-memory spine may hold before spilling.
+}
+
+// A batch removed from the spine, held in a file or, if the file could not be written, in memory.
+// A file's batch is shared with any cursor storage that still holds it since it was last reloaded.
+enum Spilled<B> {
+    File(PathBuf, Weak<B>),
+    Resident(Rc<B>),
+}
+
+impl<K, V, T, R, B, S> SpillSpine<K, V, T, R, B, S>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+    S: BatchSerializer<B>,
+{
+    /// Allocates a trace spilling to files in `directory`, retaining at most `resident` batches in memory.
+    ///
+    /// The directory will be created if it does not exist, and this method panics if it cannot be.
+    /// If the trace creates the directory, it removes the directory again when dropped.
+    pub fn new_in(directory: PathBuf, serializer: S, resident: usize) -> Self {
+        let created = !directory.exists();
+        fs::create_dir_all(&directory).expect("failed to create spill directory");
+        SpillSpine {
+            spine: Spine::new(),
+            serializer: serializer,
+            directory: directory,
+            created: created,
+            spilled: Vec::new(),
+            resident: resident,
+        }
+    }
+
+    /// The number of batches currently spilled to files.
+    ///
+    /// Batches kept in memory because their files could not be written are not counted.
+    pub fn num_spilled(&self) -> usize {
+        self.spilled.iter().filter(|x| match **x { Spilled::File(_, _) => true, Spilled::Resident(_) => false }).count()
+    }
+
+    // Spills the oldest complete batches of the spine until it holds at most `resident` batches.
+    fn spill(&mut self) {
+        while self.spine.num_batches() > self.resident {
+            if let Some(batch) = self.spine.take_oldest() {
+                let mut bytes = Vec::new();
+                self.serializer.serialize(&batch, &mut bytes);
+                let path = self.directory.join(format!("batch-{}-{}.spill", self.spilled.len(), batch.len()));
+                match File::create(&path).and_then(|mut file| file.write_all(&bytes[..])) {
+                    Ok(()) => self.spilled.push(Spilled::File(path, Weak::new())),
+                    Err(_) => {
+                        // Remove any partial file; the batch is retained in memory, in order.
+                        let _ = fs::remove_file(&path);
+                        self.spilled.push(Spilled::Resident(Rc::new(batch)));
+                    },
+                }
+            }
+            else {
+                // The oldest batch is being merged, or is still pending.
+                break;
+            }
+        }
+    }
+
+    // Recovers all spilled batches, from oldest to newest, reading only those no cursor's storage still holds.
+    fn reload(&mut self) -> Vec<Rc<B>> {
+        let mut batches = Vec::with_capacity(self.spilled.len());
+        for spilled in self.spilled.iter_mut() {
+            match *spilled {
+                Spilled::File(ref path, ref mut handle) => {
+                    if let Some(batch) = handle.upgrade() {
+                        batches.push(batch);
+                    }
+                    else {
+                        let mut bytes = Vec::new();
+                        let mut file = File::open(path).expect("failed to open spill file");
+                        file.read_to_end(&mut bytes).expect("failed to read spill file");
+                        let batch = Rc::new(self.serializer.deserialize(&bytes[..]));
+                        *handle = Rc::downgrade(&batch);
+                        batches.push(batch);
+                    }
+                },
+                Spilled::Resident(ref batch) => batches.push(batch.clone()),
+            }
+        }
+        batches
+    }
+}
+
+impl<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>, S: BatchSerializer<B>> SpillSpine<K, V, T, R, B, S> {
+    // Deletes all spill files, and discards batches retained in their place.
+    fn delete_spilled(&mut self) {
+        for spilled in self.spilled.drain(..) {
+            if let Spilled::File(path, _) = spilled {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+impl<K, V, T, R, B, S> TraceReader<K, V, T, R> for SpillSpine<K, V, T, R, B, S>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+    S: BatchSerializer<B>,
+{
+    type Batch = B;
+    type Cursor = CursorList<K, V, T, R, RcBatchCursor<K, V, T, R, B>>;
+
+    fn cursor_through(&mut self, upper: &[T]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage)> {
+        if let Some((_, resident)) = self.spine.cursor_through(upper) {
+            // Spilled batches are older than any resident batch, and go first.
+            let mut storage = self.reload();
+            storage.extend(resident.into_iter().map(Rc::new));
+            let cursors = storage.iter().map(|batch| batch.cursor()).collect::<Vec<_>>();
+            Some((CursorList::new(cursors, &storage), storage))
+        }
+        else {
+            None
+        }
+    }
+    fn advance_by(&mut self, frontier: &[T]) {
+        self.spine.advance_by(frontier);
+        if frontier.len() == 0 {
+            self.delete_spilled();
+        }
+    }
+    fn advance_frontier(&mut self) -> &[T] { self.spine.advance_frontier() }
+    fn distinguish_since(&mut self, frontier: &[T]) {
+        self.spine.distinguish_since(frontier);
+        self.spill();
+    }
+    fn distinguish_frontier(&mut self) -> &[T] { self.spine.distinguish_frontier() }
+
+    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) {
+        for batch in self.reload().iter() {
+            f(&**batch);
+        }
+        self.spine.map_batches(f);
+    }
+}
+
+impl<K, V, T, R, B, S> Trace<K, V, T, R> for SpillSpine<K, V, T, R, B, S>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+    S: BatchSerializer<B>+Default,
+{
+    /// Allocates a trace spilling to a fresh directory in `std::env::temp_dir()`, retaining 8 batches in memory.
+    fn new() -> Self {
+        let index = SPILL_DIRECTORIES.fetch_add(1, Ordering::SeqCst);
+        let name = format!("differential-spill-{}-{}", ::std::process::id(), index);
+        Self::new_in(::std::env::temp_dir().join(name), S::default(), 8)
+    }
+    fn insert(&mut self, batch: Self::Batch) {
+        self.spine.insert(batch);
+        self.spill();
+    }
+    fn insert_batch_checked(&mut self, batch: Self::Batch) -> Result<(), InsertError<T>> {
+        self.spine.insert_batch_checked(batch)?;
+        self.spill();
+        Ok(())
+    }
+    fn close(&mut self) {
+        self.spine.close();
+        self.spill();
+    }
+}
+
+impl<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>, S: BatchSerializer<B>> Drop for SpillSpine<K, V, T, R, B, S> {
+    fn drop(&mut self) {
+        self.delete_spilled();
+        if self.created {
+            let _ = fs::remove_dir(&self.directory);
+        }
+    }
+}
@@ -5,29 +5,177 @@
 //! instantiated for any implementor of `trace::Batch`.
 
 use std::fmt::Debug;
+use std::collections::VecDeque;
 
 use ::Diff;
 use lattice::Lattice;
-use trace::{Batch, BatchReader, Trace, TraceReader};
+use trace::{Batch, BatchReader, CursorError, InsertError, Trace, TraceReader};
 // use trace::cursor::cursor_list::CursorList;
 use trace::cursor::{Cursor, CursorList};
+use trace::cursor::cursor_range::CursorRange;
 use trace::Merger;
+use trace::description::Description;
 
+// Pending batches with at most this many updates are coalesced when released together.
+const COALESCE_THRESHOLD: usize = 64;
+// At most this many pending batches are coalesced at once, as coalescing visits every batch for each key.
+const COALESCE_LIMIT: usize = 64;
+
+/// A report of a completed merge, delivered to a spine's merge logger.
+///
+/// Pending batches coalesced into one batch are reported as a single merge, whose first input comprises all
+/// but the newest of the coalesced batches.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MergeEvent {
+    /// The number of updates in the first (older) input batch.
+    pub length1: usize,
+    /// The number of updates in the second (newer) input batch.
+    pub length2: usize,
+    /// The number of updates in the merged output batch.
+    pub length: usize,
+    /// The total fuel consumed by the merge.
+    pub fuel: usize,
+}
+
+/// Cumulative counts of the merges a spine has completed, as reported by `Spine::compact_stats`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompactStats {
+    /// The number of merges completed.
+    pub merges: usize,
+    /// The number of updates in the input batches of the completed merges.
+    pub tuples: usize,
+    /// The total fuel consumed by the completed merges.
+    pub fuel: usize,
+}
+
+// Records completed merges, and reports them to a callback if one is installed.
+struct MergeLogger {
+    callback: Option<Box<FnMut(MergeEvent)>>,
+    stats: CompactStats,
+}
+
+/// Policies for when a spine merges its batches, selected by `Spine::set_merge_policy`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MergePolicy {
+    /// Merges batches progressively, as batches of similar sizes contend for a slot.
+    Geometric,
+    /// Merges as `Geometric`, but also merges the adjacent pair of batches with the fewest updates while more
+    /// than this many batches are merging or merged, completing merges in progress first.
+    ///
+    /// This bounds the number of resident batches, not counting pending batches, at the expense of merging
+    /// batches of dissimilar sizes, which may repeatedly copy the updates of large batches.
+    BoundedCount(usize),
+}
+
+/// The reasons `Spine::retain_batches` may refuse to remove batches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RetainError<T> {
+    /// Removing the batches would leave a gap between the upper frontier of one retained batch, or of the
+    /// trace itself, and the lower frontier of the next retained batch.
+    Discontinuity {
+        /// The upper frontier of the older retained batch, or of the trace.
+        expected: Vec<T>,
+        /// The lower frontier of the newer retained batch, or the upper frontier of the newest retained batch.
+        found: Vec<T>,
+    },
+}
+
+/// Where a batch resides in a spine, as reported by `Spine::map_batches_classified`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BatchKind {
+    /// The batch is merged or merging with other batches.
+    Merging,
+    /// The batch is in advance of the physical compaction frontier, and awaits merging.
+    Pending,
+}
+
+/// The length and bounds of a batch, as reported by `Spine::describe`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchLayout<T> {
+    /// The number of updates in the batch.
+    pub len: usize,
+    /// The lower frontier of the batch.
+    pub lower: Vec<T>,
+    /// The upper frontier of the batch.
+    pub upper: Vec<T>,
+}
+
+impl<T: Clone> BatchLayout<T> {
+    fn of<K, V, R, B: BatchReader<K, V, T, R>>(batch: &B) -> Self {
+        BatchLayout {
+            len: batch.len(),
+            lower: batch.lower().to_vec(),
+            upper: batch.upper().to_vec(),
+        }
+    }
+}
+
+/// A snapshot of the batches and frontiers of a `Spine`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpineLayout<T> {
+    /// Batches that have been released to merging, from oldest to newest.
+    ///
+    /// A merge in progress is reported as its two input batches.
+    pub merging: Vec<BatchLayout<T>>,
+    /// Batches not yet released to merging, as they extend beyond the physical compaction frontier.
+    pub pending: Vec<BatchLayout<T>>,
+    /// The logical compaction frontier, as set by `advance_by`.
+    pub advance_frontier: Vec<T>,
+    /// The physical compaction frontier, as set by `distinguish_since`.
+    pub through_frontier: Vec<T>,
+}
+
+/// The batches and frontiers of a `Spine`, from which an equivalent spine may be restored.
+///
+/// The batches may be serialized individually, for example with `abomonation`, to checkpoint a trace.
+#[derive(Clone, Debug)]
+pub struct SpineSnapshot<T, B> {
+    /// Batches that have been released to merging, from oldest to newest.
+    ///
+    /// A merge in progress is captured as its two input batches.
+    pub merging: Vec<B>,
+    /// Batches not yet released to merging, from oldest to newest.
+    pub pending: Vec<B>,
+    /// The lower frontier of the oldest batch, or the upper frontier of the trace if it holds no batches.
+    pub lower: Vec<T>,
+    /// The upper frontier of the trace, which the next inserted batch must have as its lower frontier.
+    pub upper: Vec<T>,
+    /// The logical compaction frontier, as set by `advance_by`.
+    pub advance_frontier: Vec<T>,
+    /// The physical compaction frontier, as set by `distinguish_since`.
+    pub through_frontier: Vec<T>,
+}
+
+// A merge in progress keeps, next to its merger, the work it has performed and the fuel it has reserved: fuel
+// supplied to calls that could not use all of it, because the next key has more updates than the call supplied.
 enum MergeState<K, V, T, R, B: Batch<K, V, T, R>> {
-    Merging(B, B, Option<Vec<T>>, <B as Batch<K,V,T,R>>::Merger),
+    Merging(B, B, Option<Vec<T>>, <B as Batch<K,V,T,R>>::Merger, (usize, usize), Option<(K, K)>),
     Complete(B),
 }
 
-impl<K, V, T: Eq, R, B: Batch<K, V, T, R>> MergeState<K, V, T, R, B> {
-    fn complete(mut self) -> B {
-        if let MergeState::Merging(ref source1, ref source2, ref frontier, ref mut in_progress) = self {
+impl<K, V, T, R, B> MergeState<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone,
+    R: Diff,
+    B: Batch<K, V, T, R>,
+{
+    fn complete(mut self, logger: &mut MergeLogger) -> B {
+        if let MergeState::Merging(ref source1, ref source2, ref frontier, ref mut in_progress, (ref mut consumed, _), ref range) = self {
             let mut fuel = usize::max_value();
-            in_progress.work(source1, source2, frontier, &mut fuel);
+            let before = in_progress.progress().0;
+            in_progress.work(source1, source2, if range.is_some() { &None } else { frontier }, &mut fuel);
             assert!(fuel > 0);
+            *consumed += in_progress.progress().0 - before;
         }
         match self {
             // ALLOC: Here is where we may de-allocate batches.
-            MergeState::Merging(_, _, _, finished) => finished.done(),
+            MergeState::Merging(source1, source2, frontier, finished, (consumed, _), range) => {
+                let batch = finish_merge(finished, frontier, range);
+                log_merge(logger, &source1, &source2, &batch, consumed);
+                batch
+            },
             MergeState::Complete(x) => x,
         }
     }
@@ -38,19 +186,39 @@ impl<K, V, T: Eq, R, B: Batch<K, V, T, R>> MergeState<K, V, T, R, B> {
             _ => false,
         }
     }
-    fn begin_merge(batch1: B, batch2: B, frontier: Option<Vec<T>>) -> Self {
+    fn begin_merge(batch1: B, batch2: B, frontier: Option<Vec<T>>, range: Option<(K, K)>) -> Self {
         assert!(batch1.upper() == batch2.lower());
         let begin_merge = <B as Batch<K, V, T, R>>::begin_merge(&batch1, &batch2);
-        MergeState::Merging(batch1, batch2, frontier, begin_merge)
+        MergeState::Merging(batch1, batch2, frontier, begin_merge, (0, 0), range)
     }
-    fn work(mut self, fuel: &mut usize) -> Self {
-        if let MergeState::Merging(ref source1, ref source2, ref frontier, ref mut in_progress) = self {
-            in_progress.work(source1, source2, frontier, fuel);
+    // Performs work with `fuel` and any fuel reserved by earlier calls.
+    //
+    // Work is counted by the merger's progress, not by the fuel it was given. A merge that completes returns what
+    // it did not spend of `fuel`; one that does not complete spends all of `fuel` and reserves what it could not use.
+    fn work(mut self, fuel: &mut usize, logger: &mut MergeLogger) -> Self {
+        if let MergeState::Merging(ref source1, ref source2, ref frontier, ref mut in_progress, (ref mut consumed, ref mut reserve), ref range) = self {
+            let supplied = *fuel;
+            let mut available = supplied.saturating_add(*reserve);
+            let before = in_progress.progress().0;
+            in_progress.work(source1, source2, if range.is_some() { &None } else { frontier }, &mut available);
+            let spent = in_progress.progress().0 - before;
+            *consumed += spent;
+            if available > 0 {
+                *fuel = ::std::cmp::min(available, supplied);
+            }
+            else {
+                *reserve = supplied.saturating_add(*reserve).saturating_sub(spent);
+                *fuel = 0;
+            }
         }
         if *fuel > 0 {
             match self {
                 // ALLOC: Here is where we may de-allocate batches.
-                MergeState::Merging(_, _, _, finished) => MergeState::Complete(finished.done()),
+                MergeState::Merging(source1, source2, frontier, finished, (consumed, _), range) => {
+                    let batch = finish_merge(finished, frontier, range);
+                    log_merge(logger, &source1, &source2, &batch, consumed);
+                    MergeState::Complete(batch)
+                },
                 MergeState::Complete(x) => MergeState::Complete(x),
             }
         }
@@ -58,12 +226,139 @@ impl<K, V, T: Eq, R, B: Batch<K, V, T, R>> MergeState<K, V, T, R, B> {
     }
     fn len(&self) -> usize {
         match *self {
-            MergeState::Merging(ref batch1, ref batch2, _, _) => batch1.len() + batch2.len(),
+            MergeState::Merging(ref batch1, ref batch2, ..) => batch1.len() + batch2.len(),
             MergeState::Complete(ref batch) => batch.len(),
         }
     }
 }
 
+// Completes a merge, and advances the times of keys in `range` when the merge is restricted to a key range.
+//
+// Merges restricted to a key range are performed without a frontier, and only afterwards are the times of
+// the in-range keys advanced, by rebuilding the merged batch.
+fn finish_merge<K, V, T, R, B>(merger: B::Merger, frontier: Option<Vec<T>>, range: Option<(K, K)>) -> B
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone,
+    R: Diff,
+    B: Batch<K, V, T, R>,
+{
+    let batch = merger.done();
+    match (frontier, range) {
+        (Some(frontier), Some(range)) => rebuild(vec![batch], &frontier[..], &Some(range)),
+        _ => batch,
+    }
+}
+
+// Forms a single batch from a sequence of contiguous batches, advancing and consolidating their updates.
+//
+// If `range` is supplied, only the times of keys in `[lower, upper)` are advanced by `frontier`.
+fn rebuild<K, V, T, R, B>(batches: Vec<B>, frontier: &[T], range: &Option<(K, K)>) -> B
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone,
+    R: Diff,
+    B: Batch<K, V, T, R>,
+{
+    use trace::{Builder, consolidate};
+
+    let lower = batches[0].lower().to_vec();
+    let upper = batches[batches.len()-1].upper().to_vec();
+
+    let since = batches[1..].iter().fold(batches[0].since().to_vec(), |since, batch| {
+        Description::new(batch.lower(), batch.upper(), &since[..]).merged_since(batch.description()).to_vec()
+    });
+
+    // When the times of all keys are advanced by `frontier`, the batch may only be observed in advance of it too.
+    let since = if range.is_none() { join_frontiers(&since[..], frontier) } else { since };
+
+    let capacity = batches.iter().map(|b| b.len()).sum();
+    let mut builder = <B as Batch<K, V, T, R>>::Builder::with_capacity(capacity);
+
+    let cursors = batches.iter().map(|b| b.cursor()).collect::<Vec<_>>();
+    let mut cursor = CursorList::new(cursors, &batches);
+    let mut updates = Vec::new();
+    while cursor.key_valid(&batches) {
+        let advance = match *range {
+            Some((ref lower, ref upper)) => lower <= cursor.key(&batches) && cursor.key(&batches) < upper,
+            None => true,
+        };
+        while cursor.val_valid(&batches) {
+            if advance {
+                cursor.map_times(&batches, |time, diff| updates.push((time.advance_by(frontier), diff)));
+            }
+            else {
+                cursor.map_times(&batches, |time, diff| updates.push((time.clone(), diff)));
+            }
+            consolidate(&mut updates, 0);
+            for (time, diff) in updates.drain(..) {
+                builder.push((cursor.key(&batches).clone(), cursor.val(&batches).clone(), time, diff));
+            }
+            cursor.step_val(&batches);
+        }
+        cursor.step_key(&batches);
+    }
+
+    builder.done(&lower[..], &upper[..], &since[..])
+}
+
+// The join of two frontiers, whose elements are the minimal joins of an element of each.
+fn join_frontiers<T: Lattice+Clone>(frontier1: &[T], frontier2: &[T]) -> Vec<T> {
+    let mut joined: Vec<T> = Vec::new();
+    for time1 in frontier1.iter() {
+        for time2 in frontier2.iter() {
+            let time = time1.join(time2);
+            if !joined.iter().any(|t| t.less_equal(&time)) {
+                joined.retain(|t| !time.less_equal(t));
+                joined.push(time);
+            }
+        }
+    }
+    joined
+}
+
+// Records a completed merge in `logger`, and reports it to the callback, if one is installed.
+fn log_merge<K, V, T, R, B: Batch<K, V, T, R>>(logger: &mut MergeLogger, source1: &B, source2: &B, result: &B, fuel: usize) {
+    log_event(logger, source1.len(), source2.len(), result.len(), fuel);
+}
+
+fn log_event(logger: &mut MergeLogger, length1: usize, length2: usize, length: usize, fuel: usize) {
+    logger.stats.merges += 1;
+    logger.stats.tuples += length1 + length2;
+    logger.stats.fuel += fuel;
+    if let Some(ref mut callback) = logger.callback {
+        callback(MergeEvent {
+            length1: length1,
+            length2: length2,
+            length: length,
+            fuel: fuel,
+        });
+    }
+}
+
+/// Merges two contiguous batches at once, as a spine merges its batches, but outside of any spine.
+///
+/// The updates of `batch1` must immediately precede those of `batch2`, in that the upper frontier of `batch1`
+/// must equal the lower frontier of `batch2`. If `compaction` is supplied, the times of the merged updates are
+/// advanced by it, and updates that then coincide are consolidated.
+pub fn merge_batches<K, V, T, R, B>(batch1: &B, batch2: &B, compaction: Option<&[T]>) -> B
+where
+    T: Eq+Clone+Debug,
+    B: Batch<K, V, T, R>,
+{
+    if batch1.upper() != batch2.lower() {
+        panic!("`merge_batches`: batches are not contiguous, with upper {:?} and lower {:?}", batch1.upper(), batch2.lower());
+    }
+    let frontier = compaction.map(|frontier| frontier.to_vec());
+    let mut merger = batch1.begin_merge(batch2);
+    let mut fuel = usize::max_value();
+    merger.work(batch1, batch2, &frontier, &mut fuel);
+    assert!(fuel > 0);
+    merger.done()
+}
+
 /// An append-only collection of update tuples.
 ///
 /// A spine maintains a small number of immutable collections of update tuples, merging the collections when
@@ -74,9 +369,19 @@ pub struct Spine<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>> {
     advance_frontier: Vec<T>,            // Times after which the trace must accumulate correctly.
     through_frontier: Vec<T>,            // Times after which the trace must be able to subset its inputs.
     merging: Vec<Option<MergeState<K,V,T,R,B>>>, // Several possibly shared collections of updates.
-    pending: Vec<B>,                     // Batches at times in advance of `frontier`.
+    pending: VecDeque<B>,                // Batches at times in advance of `frontier`.
     upper: Vec<T>,
+    closed: bool,                        // Set by `close`, after which no further batches may be inserted.
     effort: usize,
+    merge_ratio: f64,
+    compaction_range: Option<(K, K)>,   // Keys whose times may be advanced when merging, if not all keys.
+    merge_logger: MergeLogger,
+    merge_policy: MergePolicy,
+    degenerate_inserts: usize,           // Batches inserted with equal lower and upper frontiers, and ignored.
+    strict: bool,                        // Whether `insert` panics on invalid batches, rather than refusing them.
+    refused_inserts: usize,              // Invalid batches refused by `insert` when not strict.
+    history_capacity: usize,             // The number of frontier snapshots to retain, if any.
+    frontier_history: Vec<(Vec<T>, Vec<T>)>, // The most recent `(advance, through)` frontiers, oldest first.
 }
 
 impl<K, V, T, R, B> TraceReader<K, V, T, R> for Spine<K, V, T, R, B>
@@ -91,80 +396,36 @@ where
     type Cursor = CursorList<K, V, T, R, <B as BatchReader<K, V, T, R>>::Cursor>;
 
     fn cursor_through(&mut self, upper: &[T]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage)> {
-
-        // we shouldn't grab a cursor into a closed trace, right?
-        assert!(self.advance_frontier.len() > 0);
-
-        // Check that `upper` is greater or equal to `self.through_frontier`.
-        // Otherwise, the cut could be in `self.merging` and it is user error anyhow.
-        if upper.iter().all(|t1| self.through_frontier.iter().any(|t2| t2.less_equal(t1))) {
-
-            let mut cursors = Vec::new();
-            let mut storage = Vec::new();
-
-            for merge_state in self.merging.iter().rev() {
-                match *merge_state {
-                    Some(MergeState::Merging(ref batch1, ref batch2, _, _)) => {
-                        cursors.push(batch1.cursor());
-                        storage.push(batch1.clone());
-                        cursors.push(batch2.cursor());
-                        storage.push(batch2.clone());
-                    },
-                    Some(MergeState::Complete(ref batch)) => {
-                        cursors.push(batch.cursor());
-                        storage.push(batch.clone());
-                    },
-                    None => { }
-                }
-            }
-
-            for batch in &self.pending {
-                let include_lower = upper.iter().all(|t1| batch.lower().iter().any(|t2| t2.less_equal(t1)));
-                let include_upper = upper.iter().all(|t1| batch.upper().iter().any(|t2| t2.less_equal(t1)));
-
-                if include_lower != include_upper && upper != batch.lower() {
-                    panic!("`cursor_through`: `upper` straddles batch");
-                    // return None;
-                }
-
-                // include pending batches
-                if include_upper {
-                    cursors.push(batch.cursor());
-                    storage.push(batch.clone());
-                }
-            }
-            Some((CursorList::new(cursors, &storage), storage))
-        }
-        else {
-            None
-        }
+        self.try_cursor_through(upper).unwrap_or(None)
     }
     fn advance_by(&mut self, frontier: &[T]) {
+        debug_assert!(
+            frontier.iter().all(|t1| self.advance_frontier.iter().any(|t2| t2.less_equal(t1))),
+            "`advance_by`: frontier {:?} regresses from {:?}", frontier, self.advance_frontier
+        );
         self.advance_frontier = frontier.to_vec();
         if self.advance_frontier.len() == 0 {
+            // An empty frontier discards all updates, including merges in progress and their inputs.
             self.pending.clear();
             self.merging.clear();
         }
+        self.record_frontiers();
     }
-    fn advance_frontier(&mut self) -> &[T] { &self.advance_frontier[..] }
+    fn advance_frontier(&mut self) -> &[T] { self.get_logical_compaction() }
     fn distinguish_since(&mut self, frontier: &[T]) {
         self.through_frontier = frontier.to_vec();
+        self.compaction_range = None;
+        self.record_frontiers();
         self.consider_merges();
     }
-    fn distinguish_frontier(&mut self) -> &[T] { &self.through_frontier[..] }
+    fn distinguish_frontier(&mut self) -> &[T] { self.get_physical_compaction() }
 
     fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) {
-        for batch in self.merging.iter().rev() {
-            match *batch {
-                Some(MergeState::Merging(ref batch1, ref batch2, _, _)) => { f(batch1); f(batch2); },
-                Some(MergeState::Complete(ref batch)) => { f(batch); },
-                None => { },
-            }
-        }
-        for batch in self.pending.iter() {
-            f(batch);
-        }
+        self.map_batches_classified(|_, batch| f(batch));
     }
+
+    fn num_updates_hint(&self) -> Option<usize> { Some(self.len()) }
+    fn estimated_bytes_hint(&self) -> Option<usize> { Some(self.estimated_bytes()) }
 }
 
 // A trace implementation for any key type that can be borrowed from or converted into `Key`.
@@ -186,27 +447,88 @@ where
     // merging the batch. This means it is a good time to perform amortized work proportional
     // to the size of batch.
     fn insert(&mut self, batch: Self::Batch) {
+        if self.closed && batch.lower() != batch.upper() {
+            panic!("`insert`: trace is closed, but received a batch with lower {:?}", batch.lower());
+        }
+        if let Err(error) = self.insert_batch_checked(batch) {
+            if self.strict {
+                panic!("`insert`: {:?}", error);
+            }
+            self.refused_inserts += 1;
+        }
+    }
+
+    fn insert_batch_checked(&mut self, batch: Self::Batch) -> Result<(), InsertError<T>> {
 
-        // we can ignore degenerate batches (TODO: learn where they come from; suppress them?)
+        // degenerate batches are ignored, but counted to help learn where they come from.
         if batch.lower() != batch.upper() {
-            assert_eq!(batch.lower(), &self.upper[..]);
+            // a closed trace has an empty upper frontier, which no batch continues.
+            if self.closed || batch.lower() != &self.upper[..] {
+                return Err(InsertError::Discontinuity { expected: self.upper.clone(), found: batch.lower().to_vec() });
+            }
             self.upper = batch.upper().to_vec();
-            self.pending.push(batch);
-            self.consider_merges();
+            if self.pending.is_empty() && self.is_releasable(&batch) {
+                // Nothing precedes the batch, and it may be merged immediately.
+                if let Err(batch) = self.introduce_batch(batch) {
+                    self.push_pending(batch);
+                }
+                #[cfg(debug_assertions)]
+                self.check_invariants();
+            }
+            else {
+                self.push_pending(batch);
+                self.consider_merges();
+            }
         }
         else {
             // degenerate batches had best be empty.
-            assert!(batch.len() == 0);
+            if batch.len() > 0 {
+                return Err(InsertError::NonEmptyDegenerate { bounds: batch.lower().to_vec(), len: batch.len() });
+            }
+            self.degenerate_inserts += 1;
         }
+        Ok(())
     }
 
     fn close(&mut self) {
-        if self.upper != Vec::new() {
-            use trace::Builder;
-            let builder = B::Builder::new();
-            let batch = builder.done(&self.upper[..], &[], &self.upper[..]);
-            self.insert(batch);
+        if !self.closed {
+            if self.upper != Vec::new() {
+                let batch = B::empty(&self.upper[..], &[]);
+                self.insert(batch);
+            }
+            self.closed = true;
+        }
+    }
+}
+
+// Inserts batches in order, as `insert` would, but considers merges only once all batches are pending.
+impl<K, V, T, R, B> Extend<B> for Spine<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+{
+    fn extend<I: IntoIterator<Item=B>>(&mut self, batches: I) {
+        for batch in batches {
+            if batch.lower() != batch.upper() {
+                if self.closed { panic!("`extend`: trace is closed, but received a batch with lower {:?}", batch.lower()); }
+                if batch.lower() != &self.upper[..] {
+                    panic!("`extend`: {:?}", InsertError::Discontinuity { expected: self.upper.clone(), found: batch.lower().to_vec() });
+                }
+                self.upper = batch.upper().to_vec();
+                self.push_pending(batch);
+            }
+            else {
+                // degenerate batches had best be empty.
+                if batch.len() > 0 {
+                    panic!("`extend`: {:?}", InsertError::NonEmptyDegenerate::<T> { bounds: batch.lower().to_vec(), len: batch.len() });
+                }
+                self.degenerate_inserts += 1;
+            }
         }
+        self.consider_merges();
     }
 }
 
@@ -223,19 +545,461 @@ where
     /// This trace will merge batches progressively, with each inserted batch applying a multiple
     /// of the batch's length in effort to each merge. The `effort` parameter is that multiplier.
     /// This value should be at least one for the merging to happen; a value of zero is not helpful.
-    pub fn with_effort(mut effort: usize) -> Self {
+    /// A batch that would require a merge in progress to complete at once is held back as pending
+    /// until that merge has been given enough fuel by later inserts.
+    pub fn with_effort(effort: usize) -> Self {
+        Self::with_effort_from(effort, vec![<T as Lattice>::minimum()])
+    }
+
+    /// Allocates a `Spine` as `new` does, whose frontiers all start at `initial_frontier`.
+    ///
+    /// The first inserted batch must have `initial_frontier` as its lower bound, and `cursor_through` and
+    /// compaction behave as if all updates at times not greater or equal to the frontier had already been
+    /// compacted away. This is useful when resuming a trace, for example from a checkpoint, at a later time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `initial_frontier` is empty, as a trace with an empty frontier could hold no updates.
+    pub fn new_from(initial_frontier: Vec<T>) -> Self {
+        assert!(!initial_frontier.is_empty(), "`new_from`: initial frontier must be non-empty");
+        Self::with_effort_from(4, initial_frontier)
+    }
+
+    // Allocates a `Spine` with the specified effort multiplier, whose frontiers all start at `frontier`.
+    fn with_effort_from(mut effort: usize, frontier: Vec<T>) -> Self {
 
         // Zero effort is .. not smart.
         if effort == 0 { effort = 1; }
 
         Spine {
             phantom: ::std::marker::PhantomData,
-            advance_frontier: vec![<T as Lattice>::minimum()],
-            through_frontier: vec![<T as Lattice>::minimum()],
+            advance_frontier: frontier.clone(),
+            through_frontier: frontier.clone(),
             merging: Vec::new(),
-            pending: Vec::new(),
-            upper: vec![<T as Lattice>::minimum()],
+            pending: VecDeque::new(),
+            upper: frontier,
+            closed: false,
             effort,
+            merge_ratio: 2.0,
+            compaction_range: None,
+            merge_logger: MergeLogger { callback: None, stats: CompactStats::default() },
+            merge_policy: MergePolicy::Geometric,
+            degenerate_inserts: 0,
+            strict: true,
+            refused_inserts: 0,
+            history_capacity: 0,
+            frontier_history: Vec::new(),
+        }
+    }
+
+    /// Allocates a `Spine` as `new` does, with space for at least `batches` merging and pending batches.
+    ///
+    /// The trace otherwise behaves exactly as one created by `new`, but avoids reallocating its lists of
+    /// batches as they grow to the expected number of batches.
+    pub fn with_capacity(batches: usize) -> Self {
+        let mut spine = Self::with_effort(4);
+        spine.merging.reserve(batches);
+        spine.pending.reserve(batches);
+        spine
+    }
+
+    /// Advances the frontier that may be used in `cursor_through`, compacting only keys in `[lower, upper)`.
+    ///
+    /// This behaves as `distinguish_since`, except that merges only advance the times of updates whose keys
+    /// lie in `[lower, upper)` by the advance frontier. Updates with keys outside this range retain their
+    /// original times, so that other readers may continue to distinguish them. A subsequent call to
+    /// `distinguish_since` restores compaction of all keys.
+    pub fn distinguish_since_range(&mut self, frontier: &[T], lower: &K, upper: &K) {
+        self.through_frontier = frontier.to_vec();
+        self.compaction_range = Some((lower.clone(), upper.clone()));
+        self.record_frontiers();
+        self.consider_merges();
+    }
+
+    /// Sets the ratio between the capacities of adjacent slots of the spine.
+    ///
+    /// Batches are assigned to slots by their length, with capacities growing geometrically by this ratio, and
+    /// batches merge when they contend for a slot. Smaller ratios merge more eagerly, spending more effort on
+    /// merging to keep fewer resident batches; larger ratios merge lazily, retaining more batches (and memory,
+    /// as updates are consolidated less often) but performing less merge work. The default ratio is `2.0`.
+    ///
+    /// This method panics if `ratio` is not greater than `1.0`.
+    pub fn set_merge_ratio(&mut self, ratio: f64) {
+        assert!(ratio > 1.0, "merge ratio must be greater than 1.0; found {}", ratio);
+        self.merge_ratio = ratio;
+        // batches exceeding the capacities of their slots move up, without doing any merge work.
+        self.apply_fuel(0, 0);
+    }
+
+    /// Sets the policy for when batches are merged. The default policy is `MergePolicy::Geometric`.
+    ///
+    /// Switching to `BoundedCount` merges batches at once until the budget is met, and switching to `Geometric`
+    /// moves any batches exceeding the capacities of their slots up, starting merges where they meet others.
+    pub fn set_merge_policy(&mut self, policy: MergePolicy) {
+        self.merge_policy = policy;
+        match policy {
+            MergePolicy::Geometric => self.apply_fuel(0, 0),
+            MergePolicy::BoundedCount(budget) => self.bound_batch_count(budget),
+        }
+    }
+
+    /// Reports the frontier through which times must accumulate correctly, as set by `advance_by`.
+    ///
+    /// Unlike `advance_frontier`, this method only requires a shared reference.
+    pub fn get_logical_compaction(&self) -> &[T] { &self.advance_frontier[..] }
+
+    /// Reports the frontier from which the trace may be subsetted, as set by `distinguish_since`.
+    ///
+    /// Unlike `distinguish_frontier`, this method only requires a shared reference.
+    pub fn get_physical_compaction(&self) -> &[T] { &self.through_frontier[..] }
+
+    /// Reports the upper frontier of the inserted batches, before which all updates have been inserted.
+    ///
+    /// A cursor through this frontier, from `cursor_through`, presents the same updates as `cursor`.
+    pub fn upper(&self) -> &[T] { &self.upper[..] }
+
+    /// Installs a callback invoked with a `MergeEvent` each time a merge completes.
+    ///
+    /// Spines without a logger do no additional work when merges complete.
+    pub fn set_merge_logger<F: FnMut(MergeEvent)+'static>(&mut self, logger: F) {
+        self.merge_logger.callback = Some(Box::new(logger));
+    }
+
+    /// Reports cumulative counts of the merges the spine has completed, whether or not a merge logger is installed.
+    ///
+    /// The counts agree with the sums over the `MergeEvent`s a logger installed at creation would receive.
+    pub fn compact_stats(&self) -> CompactStats { self.merge_logger.stats }
+
+    /// The number of degenerate batches, with equal lower and upper frontiers, that have been inserted.
+    ///
+    /// Degenerate batches contain no updates and are otherwise ignored by the trace.
+    pub fn degenerate_inserts(&self) -> usize { self.degenerate_inserts }
+
+    /// Sets whether `insert` panics when presented with a batch `insert_batch_checked` would report as invalid.
+    ///
+    /// Spines are strict by default. A spine that is not strict instead discards the batch, leaving the trace
+    /// unchanged; the number of batches discarded this way is reported by `refused_inserts`, and callers that
+    /// want the errors themselves should use `insert_batch_checked`.
+    /// Batches inserted after `close` cause a panic in either case.
+    pub fn set_strict(&mut self, strict: bool) { self.strict = strict; }
+
+    /// Indicates whether `insert` panics on invalid batches, as set by `set_strict`.
+    pub fn is_strict(&self) -> bool { self.strict }
+
+    /// The number of invalid batches that `insert` has discarded while the spine was not strict.
+    pub fn refused_inserts(&self) -> usize { self.refused_inserts }
+
+    /// Records the `(advance_frontier, through_frontier)` pair after each compaction call, retaining the most recent `capacity`.
+    ///
+    /// Snapshots are taken by `advance_by`, `distinguish_since`, and `distinguish_since_range`, and are reported
+    /// by `frontier_history`. Reducing the capacity discards the oldest snapshots, and a capacity of zero stops
+    /// recording and discards them all.
+    pub fn enable_frontier_history(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        if self.frontier_history.len() > capacity {
+            let excess = self.frontier_history.len() - capacity;
+            self.frontier_history.drain(.. excess);
+        }
+    }
+
+    /// The recorded `(advance_frontier, through_frontier)` snapshots, from oldest to most recent.
+    ///
+    /// The history is empty unless enabled by `enable_frontier_history`.
+    pub fn frontier_history(&self) -> &[(Vec<T>, Vec<T>)] { &self.frontier_history[..] }
+
+    // Snapshots the current frontiers, if history is enabled, discarding the oldest snapshot if at capacity.
+    fn record_frontiers(&mut self) {
+        if self.history_capacity > 0 {
+            if self.frontier_history.len() == self.history_capacity {
+                self.frontier_history.remove(0);
+            }
+            self.frontier_history.push((self.advance_frontier.clone(), self.through_frontier.clone()));
+        }
+    }
+
+    /// Indicates that `close` has been called, after which no further batches may be inserted.
+    pub fn is_closed(&self) -> bool { self.closed }
+
+    /// The number of updates held by the trace, including those in pending batches.
+    ///
+    /// A merge in progress contributes the lengths of both of its input batches.
+    pub fn len(&self) -> usize {
+        let merging = self.merging.iter().map(|x| x.as_ref().map(|y| y.len()).unwrap_or(0)).sum::<usize>();
+        let pending = self.pending.iter().map(|x| x.len()).sum::<usize>();
+        merging + pending
+    }
+
+    /// Indicates that the trace holds no updates, as `len` would report zero.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// The number of physical batches held by the trace, including pending batches.
+    ///
+    /// A merge in progress contributes both of its input batches.
+    pub fn num_batches(&self) -> usize {
+        let merging = self.merging.iter().map(|x| match *x {
+            Some(MergeState::Merging(..)) => 2,
+            Some(MergeState::Complete(_)) => 1,
+            None => 0,
+        }).sum::<usize>();
+        merging + self.pending.len()
+    }
+
+    /// The least and greatest keys of the batches of the trace, or `None` if no batch has keys.
+    ///
+    /// Keys whose updates accumulate to zero across batches are still reported.
+    pub fn key_bounds(&self) -> Option<(&K, &K)> {
+        let mut bounds: Option<(&K, &K)> = None;
+        for (lower, upper) in self.batches().into_iter().filter_map(|batch| batch.key_bounds()) {
+            bounds = Some(match bounds {
+                Some((min, max)) => (::std::cmp::min(min, lower), ::std::cmp::max(max, upper)),
+                None => (lower, upper),
+            });
+        }
+        bounds
+    }
+
+    /// The sum of the distinct key estimates of the batches of the trace.
+    ///
+    /// As a key may be present in several batches, this may exceed the number of distinct keys in the trace.
+    pub fn distinct_keys_hint(&self) -> usize {
+        self.batches().into_iter().map(|batch| batch.distinct_keys_hint()).sum()
+    }
+
+    /// An estimate of the heap memory used by the batches of the trace, in bytes.
+    ///
+    /// This sums `BatchReader::estimated_bytes` over merging and pending batches, and so excludes the
+    /// partial results of merges in progress, as well as any memory shared with batches held elsewhere.
+    pub fn estimated_bytes(&self) -> usize {
+        self.batches().into_iter().map(|batch| batch.estimated_bytes()).sum()
+    }
+
+    /// Panics if the spine violates one of the invariants its merging logic maintains.
+    ///
+    /// The invariants are that the frontiers of the trace are antichains, that the bounds of its batches are
+    /// contiguous from oldest to newest, that the upper frontier of the newest batch is that of the trace, and
+    /// that merging batches are ordered by size as the merge policy requires. Under the `Geometric` policy a
+    /// complete batch exceeds the capacity of its slot only while it waits for a merge in progress in the next
+    /// slot; compaction and cancellation may leave batches smaller than their slots call for, and the inputs of
+    /// merges in progress are not checked. Under the `BoundedCount` policy the number of resident batches, counting
+    /// both inputs of each merge in progress, is within the budget.
+    ///
+    /// The check is called after each insert and each consideration of merges in debug builds, and is only
+    /// available in them.
+    #[cfg(debug_assertions)]
+    pub fn check_invariants(&self) {
+
+        for &(name, frontier) in [("advance", &self.advance_frontier), ("through", &self.through_frontier), ("upper", &self.upper)].iter() {
+            for (index1, time1) in frontier.iter().enumerate() {
+                for (index2, time2) in frontier.iter().enumerate() {
+                    if index1 != index2 && time1.less_equal(time2) {
+                        panic!("`check_invariants`: {} frontier {:?} is not an antichain", name, frontier);
+                    }
+                }
+            }
+        }
+
+        match self.merge_policy {
+            MergePolicy::Geometric => {
+                for index in 0 .. self.merging.len() {
+                    if let Some(MergeState::Complete(ref batch)) = self.merging[index] {
+                        let waiting = self.merging.get(index + 1).map(|x| x.as_ref().map(|x| !x.is_complete()).unwrap_or(false)) == Some(true);
+                        if batch.len() > self.slot_capacity(index) && !waiting {
+                            panic!("`check_invariants`: batch of {:?} updates exceeds the capacity {:?} of slot {:?}", batch.len(), self.slot_capacity(index), index);
+                        }
+                    }
+                }
+            },
+            MergePolicy::BoundedCount(budget) => {
+                let count = self.merging.iter().map(|x| match *x {
+                    Some(MergeState::Merging(..)) => 2,
+                    Some(MergeState::Complete(_)) => 1,
+                    None => 0,
+                }).sum::<usize>();
+                if count > ::std::cmp::max(budget, 1) {
+                    panic!("`check_invariants`: {:?} batches are merging or merged, exceeding the budget of {:?}", count, budget);
+                }
+            },
+        }
+
+        let batches = self.batches();
+        for pair in batches.windows(2) {
+            if pair[0].upper() != pair[1].lower() {
+                panic!("`check_invariants`: batches are not contiguous, with upper {:?} and lower {:?}", pair[0].upper(), pair[1].lower());
+            }
+        }
+        if let Some(newest) = batches.last() {
+            if newest.upper() != &self.upper[..] {
+                panic!("`check_invariants`: newest batch has upper {:?}, but the trace has upper {:?}", newest.upper(), self.upper);
+            }
+        }
+    }
+
+    // The batches of the trace, merging and pending, from oldest to newest.
+    fn batches(&self) -> Vec<&B> {
+        let mut batches = Vec::new();
+        for batch in self.merging.iter().rev() {
+            match *batch {
+                Some(MergeState::Merging(ref batch1, ref batch2, ..)) => { batches.push(batch1); batches.push(batch2); },
+                Some(MergeState::Complete(ref batch)) => { batches.push(batch); },
+                None => { },
+            }
+        }
+        batches.extend(self.pending.iter());
+        batches
+    }
+
+    /// The number of merging and of pending batches the trace can hold without reallocating, whichever is smaller.
+    pub fn capacity(&self) -> usize {
+        ::std::cmp::min(self.merging.capacity(), self.pending.capacity())
+    }
+
+    /// Reports the aggregate progress of the merges in progress, as reported by `Merger::progress`.
+    ///
+    /// The result is the sum of the work done and the sum of the total work estimates, and is `(0, 0)`
+    /// when no merges are in progress.
+    pub fn merge_progress(&self) -> (usize, usize) {
+        let mut progress = (0, 0);
+        for merge_state in self.merging.iter() {
+            if let Some(MergeState::Merging(_, _, _, ref merger, _, _)) = *merge_state {
+                let (done, total) = merger.progress();
+                progress.0 += done;
+                progress.1 += total;
+            }
+        }
+        progress
+    }
+
+    /// Maps some logic across the batches of the trace, as `map_batches`, indicating where each batch resides.
+    ///
+    /// Batches are presented from oldest to newest, and so all merging batches precede all pending batches.
+    pub fn map_batches_classified<F: FnMut(BatchKind, &B)>(&self, mut f: F) {
+        for batch in self.merging.iter().rev() {
+            match *batch {
+                Some(MergeState::Merging(ref batch1, ref batch2, ..)) => { f(BatchKind::Merging, batch1); f(BatchKind::Merging, batch2); },
+                Some(MergeState::Complete(ref batch)) => { f(BatchKind::Merging, batch); },
+                None => { },
+            }
+        }
+        for batch in self.pending.iter() {
+            f(BatchKind::Pending, batch);
+        }
+    }
+
+    /// Reports the lengths and bounds of the batches of the trace, and its frontiers.
+    pub fn describe(&self) -> SpineLayout<T> {
+        let mut merging = Vec::new();
+        for merge_state in self.merging.iter().rev() {
+            match *merge_state {
+                Some(MergeState::Merging(ref batch1, ref batch2, ..)) => {
+                    merging.push(BatchLayout::of(batch1));
+                    merging.push(BatchLayout::of(batch2));
+                },
+                Some(MergeState::Complete(ref batch)) => merging.push(BatchLayout::of(batch)),
+                None => { },
+            }
+        }
+        SpineLayout {
+            merging: merging,
+            pending: self.pending.iter().map(|batch| BatchLayout::of(batch)).collect(),
+            advance_frontier: self.advance_frontier.clone(),
+            through_frontier: self.through_frontier.clone(),
+        }
+    }
+
+    /// Removes and returns the oldest batch of the trace, if it is not involved in a merge.
+    ///
+    /// The caller becomes responsible for the updates of the batch, which the trace will no longer
+    /// present through its cursors nor compact. This is intended for wrappers that relocate cold
+    /// batches, such as `SpillSpine`.
+    pub fn take_oldest(&mut self) -> Option<B> {
+        let oldest = match self.merging.last() {
+            Some(&Some(MergeState::Complete(_))) => self.merging.pop(),
+            _ => None,
+        };
+        while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+        match oldest {
+            Some(Some(MergeState::Complete(batch))) => Some(batch),
+            _ => None,
+        }
+    }
+
+    /// Removes the batches, merged or pending, for which `predicate` returns false.
+    ///
+    /// This is intended for testing and debugging, for example to drop empty batches. The batches of merges
+    /// in progress are always retained. Batches are only removed if the retained batches remain contiguous,
+    /// so that their bounds still cover the trace from the lower frontier of the oldest retained batch to the
+    /// upper frontier of the trace; otherwise no batches are removed and an error describes the first gap.
+    pub fn retain_batches<F: Fn(&B)->bool>(&mut self, predicate: F) -> Result<(), RetainError<T>> {
+
+        // The bounds of retained batches, from oldest to newest.
+        let mut merging_keep = vec![true; self.merging.len()];
+        let mut bounds = Vec::new();
+        for index in (0 .. self.merging.len()).rev() {
+            match self.merging[index] {
+                Some(MergeState::Merging(ref batch1, ref batch2, ..)) => bounds.push((batch1.lower(), batch2.upper())),
+                Some(MergeState::Complete(ref batch)) => {
+                    merging_keep[index] = predicate(batch);
+                    if merging_keep[index] { bounds.push((batch.lower(), batch.upper())); }
+                },
+                None => { },
+            }
+        }
+        let pending_keep = self.pending.iter().map(|batch| predicate(batch)).collect::<Vec<_>>();
+        for (batch, keep) in self.pending.iter().zip(pending_keep.iter()) {
+            if *keep { bounds.push((batch.lower(), batch.upper())); }
+        }
+
+        for pair in bounds.windows(2) {
+            if pair[0].1 != pair[1].0 {
+                return Err(RetainError::Discontinuity { expected: pair[0].1.to_vec(), found: pair[1].0.to_vec() });
+            }
+        }
+        if let Some(&(_, upper)) = bounds.last() {
+            if upper != &self.upper[..] {
+                return Err(RetainError::Discontinuity { expected: self.upper.clone(), found: upper.to_vec() });
+            }
+        }
+
+        for (index, keep) in merging_keep.into_iter().enumerate() {
+            if !keep { self.merging[index] = None; }
+        }
+        while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+        let pending = ::std::mem::replace(&mut self.pending, VecDeque::new());
+        self.pending = pending.into_iter().zip(pending_keep.into_iter()).filter(|x| x.1).map(|x| x.0).collect();
+
+        Ok(())
+    }
+
+    /// Merges all batches into at most one batch, completing any merges in progress.
+    ///
+    /// Unlike the progressive merging performed as batches are inserted, which only merges batches of similar
+    /// sizes, this method merges everything at once with unbounded fuel, and advances times by the advance
+    /// frontier in the final merge. Pending batches in advance of the physical compaction frontier are left
+    /// pending, as merging them would prevent `cursor_through` from distinguishing them.
+    pub fn merge_all(&mut self) {
+
+        // Collect complete batches, from oldest to newest.
+        let states = self.merging.drain(..).rev().collect::<Vec<_>>();
+        let mut batches = Vec::new();
+        for state in states.into_iter() {
+            if let Some(state) = state {
+                batches.push(state.complete(&mut self.merge_logger));
+            }
+        }
+        while self.pending.len() > 0 && self.is_releasable(&self.pending[0]) {
+            batches.push(self.pending.pop_front().expect("pending batch"));
+        }
+
+        let count = batches.len();
+        let mut batches = batches.into_iter();
+        if let Some(mut merged) = batches.next() {
+            for (index, batch) in batches.enumerate() {
+                let frontier = if index + 2 == count { Some(self.advance_frontier.clone()) } else { None };
+                merged = MergeState::begin_merge(merged, batch, frontier, self.compaction_range.clone()).complete(&mut self.merge_logger);
+            }
+            let index = self.slot_for(merged.len());
+            while self.merging.len() <= index { self.merging.push(None); }
+            self.merging[index] = Some(MergeState::Complete(merged));
         }
     }
 
@@ -246,7 +1010,8 @@ where
         // We have a new design here, in an attempt to rationalize progressive merging of batches.
         //
         // Batches arrive with a number of records, and are assigned a power-of-two "size", which is this
-        // number rounded up to the next power of two. Batches are placed in a slot based on their size,
+        // number rounded up to the next power of two (more generally, to the next power of the merge ratio,
+        // see `set_merge_ratio`). Batches are placed in a slot based on their size,
         // and each slot can be one of:
         //
         //  i. empty,
@@ -268,7 +1033,8 @@ where
         // overtake any merges in progress.
         //
         // Our main technical issue is that we need to ensure that merges complete before other batches want
-        // to initiate a merge with their resulting batch.
+        // to initiate a merge with their resulting batch. Should a merge not have completed in time, rather
+        // than force it to completion the batch waits at the front of `pending` while the merge receives fuel.
         //
         // To this end, as batches are introduced, we perform an amount of work on all existing merges that
         // is proportional to the size of the introduced batch. The intent is that once a merge is initiated,
@@ -284,83 +1050,279 @@ where
         //     1. merging[i].lower == merging[i+1].upper (unless either is None).
         //     2. large batches never have small indices.
 
-        while self.pending.len() > 0 &&
-              self.through_frontier.iter().all(|t1| self.pending[0].upper().iter().any(|t2| t2.less_equal(t1)))
-        {
-            // this could be a VecDeque, if we ever notice this.
-            let batch = self.pending.remove(0);
-
-            // Step 0: Determine batch size and target slot.
-            let batch_size = batch.len().next_power_of_two();
-            let batch_index = batch_size.trailing_zeros() as usize;
-            while self.merging.len() <= batch_index { self.merging.push(None); }
-
-            if self.merging.len() > 32 { eprintln!("large progressive merge; len: {:?}", self.merging.len()); }
-
-            // Step 1: Forcibly merge batches in lower slots.
-            for position in 0 .. batch_index {
-                if let Some(batch) = self.merging[position].take() {
-                    let batch = batch.complete();
-                    if let Some(batch2) = self.merging[position+1].take() {
-                        let batch2 = batch2.complete();
-                        self.merging[position+1] = Some(MergeState::begin_merge(batch2, batch, None));
+        while self.pending.len() > 0 && self.is_releasable(&self.pending[0]) {
+
+            // Bursts of small batches are coalesced into one batch, rather than cascading pairwise merges.
+            let mut count = 0;
+            while count < self.pending.len() && count < COALESCE_LIMIT &&
+                  self.pending[count].len() <= COALESCE_THRESHOLD &&
+                  self.is_releasable(&self.pending[count])
+            {
+                count += 1;
+            }
+
+            let batch = if count > 1 {
+                let batches = self.pending.drain(.. count).collect::<Vec<_>>();
+                // Reported as a merge of the older batches with the newest one, reading each update once.
+                let length2 = batches[count-1].len();
+                let length1 = batches.iter().map(|b| b.len()).sum::<usize>() - length2;
+                let batch = rebuild(batches, &self.advance_frontier[..], &self.compaction_range);
+                log_event(&mut self.merge_logger, length1, length2, batch.len(), length1 + length2);
+                batch
+            }
+            else {
+                self.pending.pop_front().expect("pending batch")
+            };
+
+            if let Err(batch) = self.introduce_batch(batch) {
+                // Merges in progress must complete first; the batch and those after it wait in `pending`.
+                self.pending.push_front(batch);
+                break;
+            }
+        }
+
+        self.prune_empty();
+
+        #[cfg(debug_assertions)]
+        self.check_invariants();
+    }
+
+    /// Removes batches with no updates, where doing so keeps the bounds of the remaining batches contiguous.
+    ///
+    /// Consecutive empty batches, among those merged and among those pending, are replaced by a single empty
+    /// batch spanning their bounds, and empty batches preceding all other batches are discarded. Batches in
+    /// merges in progress are retained. This method is called whenever the trace considers merges.
+    pub fn prune_empty(&mut self) {
+
+        // Coalesce consecutive complete empty batches, from oldest to newest, and discard leading ones.
+        let mut leading = true;
+        let mut previous: Option<usize> = None;
+        for index in (0 .. self.merging.len()).rev() {
+            let empty = match self.merging[index] {
+                Some(MergeState::Complete(ref batch)) => batch.len() == 0,
+                Some(MergeState::Merging(..)) => false,
+                None => continue,
+            };
+            if empty && leading {
+                self.merging[index] = None;
+            }
+            else if empty {
+                if let Some(prev) = previous {
+                    if let (Some(MergeState::Complete(batch1)), Some(MergeState::Complete(batch2))) = (self.merging[prev].take(), self.merging[index].take()) {
+                        self.merging[index] = Some(MergeState::Complete(B::empty(batch1.lower(), batch2.upper())));
                     }
-                    else {
-                        self.merging[position+1] = Some(MergeState::Complete(batch));
-                    };
                 }
+                previous = Some(index);
+            }
+            else {
+                leading = false;
+                previous = None;
             }
+        }
+        while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+
+        // Likewise for pending batches, which are only leading if no batches are merged. Batches are only added
+        // to and removed from the ends of `pending`, and `push_pending` coalesces consecutive empty batches as they
+        // are added, so only the batches at the front remain to be considered.
+        while leading && self.pending.front().map(|b| b.len() == 0) == Some(true) {
+            self.pending.pop_front();
+        }
+        while self.pending.len() > 1 && self.pending[0].len() == 0 && self.pending[1].len() == 0 {
+            let batch1 = self.pending.pop_front().expect("pending batch");
+            let batch2 = self.pending.pop_front().expect("pending batch");
+            self.pending.push_front(B::empty(batch1.lower(), batch2.upper()));
+        }
+    }
+
+    // Adds a batch to the back of `pending`, coalescing it with a preceding empty batch if it is empty too.
+    fn push_pending(&mut self, batch: B) {
+        if batch.len() == 0 && self.pending.back().map(|b| b.len() == 0) == Some(true) {
+            let prev = self.pending.pop_back().expect("pending batch");
+            self.pending.push_back(B::empty(prev.lower(), batch.upper()));
+        }
+        else {
+            self.pending.push_back(batch);
+        }
+    }
+
+    // Merges batches in `merging` until at most `budget` remain, first completing merges in progress and then
+    // merging the adjacent pair of batches with the fewest updates.
+    fn bound_batch_count(&mut self, budget: usize) {
+        let mut count = self.merging.iter().map(|x| match *x {
+            Some(MergeState::Merging(..)) => 2,
+            Some(MergeState::Complete(_)) => 1,
+            None => 0,
+        }).sum::<usize>();
 
-            // Step 2: Insert new batch at target position
-            if let Some(batch2) = self.merging[batch_index].take() {
-                let batch2 = batch2.complete();
-                let frontier = if batch_index == self.merging.len()-1 { Some(self.advance_frontier.clone()) } else { None };
-                self.merging[batch_index] = Some(MergeState::begin_merge(batch2, batch, frontier));
+        for position in 0 .. self.merging.len() {
+            if count <= budget { return; }
+            if self.merging[position].as_ref().map(|x| !x.is_complete()).unwrap_or(false) {
+                let batch = self.merging[position].take().expect("merge in progress").complete(&mut self.merge_logger);
+                self.merging[position] = Some(MergeState::Complete(batch));
+                count -= 1;
             }
-            else {
-                self.merging[batch_index] = Some(MergeState::Complete(batch));
-            }
-
-            // Step 3: Perform `size` work on each in-progress merge, from large to small.
-            //         For non-merges, accumulate fuel, as we may need to apply it to merges
-            //         that result at us.
-            let mut fuel = 0;
-            for position in (batch_index .. self.merging.len()).rev() {
-
-                // We add fuel for any merge that may lead to this location.
-                fuel += (2 * batch_size).saturating_mul(self.effort);
-
-                // We now move to the right, merging until we stop merging or run out of fuel.
-                let mut new_position = position;
-                while self.merging[new_position].as_ref().map(|x| !x.is_complete()).unwrap_or(false) && fuel > 0 {
-                    if let Some(mut batch) = self.merging[new_position].take() {
-
-                        // Apply work with accumulated fuel.
-                        batch = batch.work(&mut fuel);
-
-                        // If we have a complete batch, and it wants to be in the next slot ...
-                        if batch.is_complete() && batch.len() >= (1 << new_position) {//.next_power_of_two().trailing_zeros() as usize > new_position {
-
-                            new_position += 1;
-                            if self.merging.len() <= new_position { self.merging.push(None); }
-
-                            // If the next slot is actually occupied, must start a merge.
-                            if let Some(mut batch2) = self.merging[new_position].take() {
-                                if !batch2.is_complete() {
-                                    eprintln!("batch[{}] not complete (size: {:?})", new_position, batch2.len());
-                                    eprintln!("sizes: {:?}", self.merging.iter().map(|x| x.as_ref().map(|y|y.len()).unwrap_or(0)).collect::<Vec<_>>());
-                                    let mut temp_fuel = usize::max_value();
-                                    batch2 = batch2.work(&mut temp_fuel);
-                                    eprintln!("fuel shortage: {:?}", usize::max_value() - temp_fuel);
-                                }
-                                let batch1 = batch.complete();
-                                let batch2 = batch2.complete();
+        }
+
+        while count > budget && count > 1 {
+            let occupied = (0 .. self.merging.len()).filter(|&i| self.merging[i].is_some()).collect::<Vec<_>>();
+            let pair = (1 .. occupied.len())
+                .min_by_key(|&i| self.merging[occupied[i-1]].as_ref().map(|x| x.len()).unwrap_or(0) + self.merging[occupied[i]].as_ref().map(|x| x.len()).unwrap_or(0))
+                .expect("adjacent batches");
+
+            // Newer batches reside at smaller indices; the merged batch takes the older batch's slot.
+            let (newer, older) = (occupied[pair-1], occupied[pair]);
+            let batch1 = self.merging[older].take().expect("older batch").complete(&mut self.merge_logger);
+            let batch2 = self.merging[newer].take().expect("newer batch").complete(&mut self.merge_logger);
+            let frontier = if older == self.merging.len()-1 { Some(self.advance_frontier.clone()) } else { None };
+            let merged = MergeState::begin_merge(batch1, batch2, frontier, self.compaction_range.clone()).complete(&mut self.merge_logger);
+            self.merging[older] = Some(MergeState::Complete(merged));
+            count -= 1;
+        }
+    }
+
+    // The number of updates a batch may have and still reside in slot `index`.
+    fn slot_capacity(&self, index: usize) -> usize {
+        let mut capacity = 1usize;
+        for _ in 0 .. index {
+            let scaled = (capacity as f64 * self.merge_ratio).ceil() as usize;
+            capacity = ::std::cmp::max(capacity.saturating_add(1), scaled);
+        }
+        capacity
+    }
+
+    // Indicates that `state` is a complete batch with more updates than slot `index` may hold.
+    fn exceeds_slot(&self, index: usize, state: &MergeState<K, V, T, R, B>) -> bool {
+        state.is_complete() && state.len() > self.slot_capacity(index)
+    }
+
+    // The first slot with capacity for a batch of `len` updates.
+    fn slot_for(&self, len: usize) -> usize {
+        let mut index = 0;
+        while self.slot_capacity(index) < len { index += 1; }
+        index
+    }
+
+    // Indicates that `batch` is not in advance of `through_frontier`, and may be migrated into `merging`.
+    fn is_releasable(&self, batch: &B) -> bool {
+        self.through_frontier.iter().all(|t1| batch.upper().iter().any(|t2| t2.less_equal(t1)))
+    }
+
+    // Introduces a batch into `merging`, and performs work proportional to its size.
+    //
+    // The batch is returned if merges in progress in its target slot or lower slots do not complete with the
+    // work its size allows, as completing them would take unbounded work. The caller should retry the batch,
+    // and the batches that follow it, once more work has been done.
+    fn introduce_batch(&mut self, batch: B) -> Result<(), B> {
+
+        // Step 0: Determine batch size and target slot.
+        let batch_index = self.slot_for(batch.len());
+        let batch_size = self.slot_capacity(batch_index);
+        while self.merging.len() <= batch_index { self.merging.push(None); }
+
+        if self.merging.len() > 32 { eprintln!("large progressive merge; len: {:?}", self.merging.len()); }
+
+        // Merges in progress in these slots must complete first; give them fuel, rather than forcing them.
+        if self.blocked_through(batch_index) {
+            self.apply_fuel(0, batch_size);
+            if self.blocked_through(batch_index) {
+                while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+                return Err(batch);
+            }
+        }
+
+        // Step 1: Forcibly merge batches in lower slots.
+        for position in 0 .. batch_index {
+            if let Some(batch) = self.merging[position].take() {
+                let batch = batch.complete(&mut self.merge_logger);
+                if let Some(batch2) = self.merging[position+1].take() {
+                    let batch2 = batch2.complete(&mut self.merge_logger);
+                    self.merging[position+1] = Some(MergeState::begin_merge(batch2, batch, None, None));
+                }
+                else {
+                    self.merging[position+1] = Some(MergeState::Complete(batch));
+                };
+            }
+        }
+
+        // Step 2: Insert new batch at target position
+        if let Some(batch2) = self.merging[batch_index].take() {
+            let batch2 = batch2.complete(&mut self.merge_logger);
+            let frontier = if batch_index == self.merging.len()-1 { Some(self.advance_frontier.clone()) } else { None };
+            self.merging[batch_index] = Some(MergeState::begin_merge(batch2, batch, frontier, self.compaction_range.clone()));
+        }
+        else {
+            self.merging[batch_index] = Some(MergeState::Complete(batch));
+        }
+
+        // Step 3: Perform `size` work on each in-progress merge, from large to small.
+        self.apply_fuel(batch_index, batch_size);
+
+        // Step 4: Consider migrating complete batches to lower bins, if appropriate.
+        for index in (1 .. self.merging.len()).rev() {
+            let capacity = self.slot_capacity(index-1);
+            if self.merging[index].as_ref().map(|x| x.is_complete() && x.len() < capacity).unwrap_or(false) {
+                if self.merging[index-1].is_none() {
+                    self.merging[index-1] = self.merging[index].take();
+                }
+            }
+        }
+        while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+
+        // Step 5: Bound the number of batches, if the merge policy calls for it.
+        if let MergePolicy::BoundedCount(budget) = self.merge_policy {
+            self.bound_batch_count(budget);
+        }
+
+        Ok(())
+    }
+
+    // Indicates that a merge is in progress in a slot at or below `index`.
+    fn blocked_through(&self, index: usize) -> bool {
+        self.merging.iter().take(index + 1).any(|x| x.as_ref().map(|x| !x.is_complete()).unwrap_or(false))
+    }
+
+    // Performs work proportional to `batch_size` on each in-progress merge from slot `lowest` up, from large to small.
+    //
+    // For non-merges, accumulate fuel, as we may need to apply it to merges that result at us. A complete batch that
+    // wants to move into a slot whose merge does not complete with the remaining fuel waits in its slot, and moves
+    // on a later call once it exceeds the capacity of its slot and the merge has completed.
+    fn apply_fuel(&mut self, lowest: usize, batch_size: usize) {
+        let mut fuel = 0;
+        for position in (lowest .. self.merging.len()).rev() {
+
+            // We add fuel for any merge that may lead to this location.
+            fuel += (self.merge_ratio.ceil() as usize).saturating_mul(batch_size).saturating_mul(self.effort);
+
+            // We now move to the right, merging until we stop merging or run out of fuel.
+            let mut new_position = position;
+            while self.merging[new_position].as_ref().map(|x| (!x.is_complete() && fuel > 0) || self.exceeds_slot(new_position, x)).unwrap_or(false) {
+                if let Some(mut batch) = self.merging[new_position].take() {
+
+                    // Apply work with accumulated fuel.
+                    batch = batch.work(&mut fuel, &mut self.merge_logger);
+
+                    // If we have a complete batch, and it wants to be in the next slot ...
+                    if batch.is_complete() && batch.len() >= self.slot_capacity(new_position) {//.next_power_of_two().trailing_zeros() as usize > new_position {
+
+                        new_position += 1;
+                        if self.merging.len() <= new_position { self.merging.push(None); }
+
+                        // If the next slot is actually occupied, must start a merge.
+                        if let Some(mut batch2) = self.merging[new_position].take() {
+                            if !batch2.is_complete() {
+                                batch2 = batch2.work(&mut fuel, &mut self.merge_logger);
+                            }
+                            if batch2.is_complete() {
+                                let batch1 = batch.complete(&mut self.merge_logger);
+                                let batch2 = batch2.complete(&mut self.merge_logger);
                                 // if this is the last position, engage compaction.
                                 let frontier = if new_position+1 == self.merging.len() { Some(self.advance_frontier.clone()) } else { None };
-                                self.merging[new_position] = Some(MergeState::begin_merge(batch2, batch1, frontier));
+                                self.merging[new_position] = Some(MergeState::begin_merge(batch2, batch1, frontier, self.compaction_range.clone()));
                             }
                             else {
-                                self.merging[new_position] = Some(batch);
+                                // The merge in the next slot is out of fuel; the batch waits for it to complete.
+                                self.merging[new_position-1] = Some(batch);
+                                self.merging[new_position] = Some(batch2);
                             }
                         }
                         else {
@@ -368,20 +1330,204 @@ where
                         }
                     }
                     else {
-                        // We can't be here. The while condition ensures that an entry exists.
+                        self.merging[new_position] = Some(batch);
                     }
                 }
+                else {
+                    // We can't be here. The while condition ensures that an entry exists.
+                }
             }
+        }
+    }
+}
+impl<K, V, T, R, B> Spine<K, V, T, R, B>
+where
+    K: Ord+Clone,
+    V: Ord+Clone,
+    T: Lattice+Ord+Clone+Debug,
+    R: Diff,
+    B: Batch<K, V, T, R>+Clone+'static,
+{
+    /// Compacts the trace both logically and physically to `frontier`.
+    ///
+    /// This is the common case of calling `advance_by` and then `distinguish_since` with the same frontier, for
+    /// readers that neither need to distinguish times nor subset the trace before `frontier`. Advancing first
+    /// ensures that the merges prompted by `distinguish_since` already advance times to `frontier`.
+    ///
+    /// This method panics if `frontier` is empty, or if it is not in advance of the current advance frontier.
+    pub fn compact_to(&mut self, frontier: &[T]) {
+        assert!(!frontier.is_empty(), "`compact_to`: frontier must be non-empty");
+        assert!(
+            frontier.iter().all(|t1| self.advance_frontier.iter().any(|t2| t2.less_equal(t1))),
+            "`compact_to`: frontier {:?} is not in advance of {:?}", frontier, self.advance_frontier
+        );
+        self.advance_by(frontier);
+        self.distinguish_since(frontier);
+    }
 
-            // Step 4: Consider migrating complete batches to lower bins, if appropriate.
-            for index in (1 .. self.merging.len()).rev() {
-                if self.merging[index].as_ref().map(|x| x.is_complete() && x.len() < (1 << (index-1))).unwrap_or(false) {
-                    if self.merging[index-1].is_none() {
-                        self.merging[index-1] = self.merging[index].take();
-                    }
+    /// Acquires a cursor through `upper`, as `cursor_through`, restricted to keys in `[lower, upper_key)`.
+    ///
+    /// Each batch cursor is seeked to `lower` before it is merged with the others, and stops at `upper_key`, so
+    /// that keys outside the range are neither visited nor compared when the cursors are merged.
+    pub fn cursor_through_range(&mut self, upper: &[T], lower: &K, upper_key: &K) -> Option<(CursorList<K, V, T, R, CursorRange<K, V, T, R, B::Cursor>>, Vec<B>)> {
+        self.batches_through(upper).unwrap_or(None).map(|storage| {
+            let cursors = storage.iter().map(|batch| CursorRange::new(batch.cursor(), batch, lower.clone(), upper_key.clone())).collect::<Vec<_>>();
+            (CursorList::new(cursors, &storage), storage)
+        })
+    }
+
+    /// Acquires a cursor through `upper`, as `cursor_through`, reporting why no cursor could be provided.
+    ///
+    /// The result is an error if `upper` is not greater or equal to the physical compaction frontier, as
+    /// the updates through `upper` may have been merged with later updates. The result is `Ok(None)` if
+    /// `upper` straddles a pending batch, as there is then no clean cut to offer.
+    pub fn try_cursor_through(&mut self, upper: &[T]) -> Result<Option<(CursorList<K, V, T, R, B::Cursor>, Vec<B>)>, CursorError<T>> {
+        self.batches_through(upper).map(|batches| batches.map(|storage| {
+            let cursors = storage.iter().map(|batch| batch.cursor()).collect::<Vec<_>>();
+            (CursorList::new(cursors, &storage), storage)
+        }))
+    }
+
+    // Collects the batches whose updates are at times not greater or equal to an element of `upper`, if there is a clean cut.
+    fn batches_through(&self, upper: &[T]) -> Result<Option<Vec<B>>, CursorError<T>> {
+
+        // we shouldn't grab a cursor into a closed trace, right?
+        assert!(self.advance_frontier.len() > 0);
+
+        // Check that `upper` is greater or equal to `self.through_frontier`.
+        // Otherwise, the cut could be in `self.merging` and it is user error anyhow.
+        if upper.iter().all(|t1| self.through_frontier.iter().any(|t2| t2.less_equal(t1))) {
+
+            let mut storage = Vec::new();
+
+            for merge_state in self.merging.iter().rev() {
+                match *merge_state {
+                    Some(MergeState::Merging(ref batch1, ref batch2, ..)) => {
+                        storage.push(batch1.clone());
+                        storage.push(batch2.clone());
+                    },
+                    Some(MergeState::Complete(ref batch)) => {
+                        storage.push(batch.clone());
+                    },
+                    None => { }
+                }
+            }
+
+            for batch in &self.pending {
+                let description = batch.description();
+                let include_lower = upper.iter().all(|t1| description.lower().iter().any(|t2| t2.less_equal(t1)));
+                let include_upper = upper.iter().all(|t1| description.upper().iter().any(|t2| t2.less_equal(t1)));
+
+                // `upper` straddles the batch; there is no clean cut to offer, unless the batch has no updates to cut.
+                if include_lower != include_upper && upper != description.lower() && batch.len() > 0 {
+                    return Ok(None);
+                }
+
+                // include pending batches
+                if include_upper {
+                    storage.push(batch.clone());
                 }
             }
-            while self.merging.last().map(|x| x.is_none()) == Some(true) { self.merging.pop(); }
+            Ok(Some(storage))
+        }
+        else {
+            Err(CursorError::UpperTooLow { through_frontier: self.through_frontier.clone() })
         }
     }
-}
\ No newline at end of file
+
+    /// Collects all updates in the trace, with times advanced by the advance frontier.
+    ///
+    /// The updates are consolidated and sorted by key, value, and time. This is intended for testing and
+    /// debugging, as it copies the entire contents of the trace. A trace whose advance frontier is empty
+    /// has discarded its updates, and produces an empty vector.
+    pub fn updates(&mut self) -> Vec<(K, V, T, R)> {
+
+        use trace::consolidate;
+
+        let mut updates = Vec::new();
+        if self.advance_frontier.len() > 0 {
+            let (mut cursor, storage) = self.cursor();
+            let frontier = &self.advance_frontier[..];
+            while cursor.key_valid(&storage) {
+                while cursor.val_valid(&storage) {
+                    let key = cursor.key(&storage);
+                    let val = cursor.val(&storage);
+                    cursor.map_times(&storage, |time, diff| {
+                        updates.push(((key.clone(), val.clone(), time.advance_by(frontier)), diff));
+                    });
+                    cursor.step_val(&storage);
+                }
+                cursor.step_key(&storage);
+            }
+        }
+
+        consolidate(&mut updates, 0);
+        updates.into_iter().map(|((key, val, time), diff)| (key, val, time, diff)).collect()
+    }
+
+    /// Captures the batches and frontiers of the trace.
+    ///
+    /// The batches are shared with the trace rather than copied when `B` is reference counted.
+    pub fn snapshot(&self) -> SpineSnapshot<T, B> {
+        let mut merging = Vec::new();
+        for merge_state in self.merging.iter().rev() {
+            match *merge_state {
+                Some(MergeState::Merging(ref batch1, ref batch2, ..)) => {
+                    merging.push(batch1.clone());
+                    merging.push(batch2.clone());
+                },
+                Some(MergeState::Complete(ref batch)) => merging.push(batch.clone()),
+                None => { },
+            }
+        }
+        let pending: Vec<B> = self.pending.iter().cloned().collect();
+        let lower = match merging.first().or(pending.first()) {
+            Some(batch) => batch.lower().to_vec(),
+            None => self.upper.clone(),
+        };
+        SpineSnapshot {
+            merging: merging,
+            pending: pending,
+            lower: lower,
+            upper: self.upper.clone(),
+            advance_frontier: self.advance_frontier.clone(),
+            through_frontier: self.through_frontier.clone(),
+        }
+    }
+
+    /// Reconstructs a trace from a snapshot.
+    ///
+    /// The restored trace presents the same updates as the trace the snapshot was taken from, starts from
+    /// its lower frontier as if created by `new_from`, and accepts batches continuing from its upper frontier.
+    /// Merges in progress are restarted, and the effort, merge ratio, and merge logger take their default
+    /// values. A snapshot with an empty upper frontier restores a closed trace.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the batches of the snapshot are not contiguous from its lower frontier.
+    pub fn restore(snapshot: SpineSnapshot<T, B>) -> Self {
+        let mut spine = if snapshot.lower.is_empty() { Self::with_effort(4) } else { Self::new_from(snapshot.lower) };
+        spine.advance_frontier = snapshot.advance_frontier;
+        spine.through_frontier = snapshot.through_frontier;
+        for batch in snapshot.merging {
+            assert_eq!(batch.lower(), &spine.upper[..]);
+            spine.upper = batch.upper().to_vec();
+            if spine.pending.is_empty() {
+                if let Err(batch) = spine.introduce_batch(batch) {
+                    spine.push_pending(batch);
+                }
+            }
+            else {
+                spine.push_pending(batch);
+            }
+        }
+        for batch in snapshot.pending {
+            assert_eq!(batch.lower(), &spine.upper[..]);
+            spine.upper = batch.upper().to_vec();
+            spine.push_pending(batch);
+        }
+        spine.upper = snapshot.upper;
+        spine.closed = spine.upper.len() == 0;
+        spine
+    }
+}
@@ -0,0 +1,160 @@
+//! Implementation storing keys and differences in separate columns.
+
+use difference::Diff;
+
+use super::{Trie, Cursor, Builder, MergeBuilder, TupleBuilder};
+use super::ordered_leaf::advance;
+
+/// A layer of ordered keys, each with a difference, stored as separate columns.
+///
+/// This layer holds the same information as an `OrderedLeaf`, but stores keys and differences in
+/// distinct vectors, so that scans of either column need not touch the other.
+#[derive(Debug, Eq, PartialEq, Clone, Abomonation)]
+pub struct ColumnarLeaf<K, R> {
+    /// Ordered keys.
+    pub keys: Vec<K>,
+    /// Differences, with `diffs[i]` associated with `keys[i]`.
+    pub diffs: Vec<R>,
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> Trie for ColumnarLeaf<K, R> {
+    type Item = (K, R);
+    type Cursor = ColumnarLeafCursor;
+    type MergeBuilder = ColumnarLeafBuilder<K, R>;
+    type TupleBuilder = ColumnarLeafBuilder<K, R>;
+    fn keys(&self) -> usize { self.keys.len() }
+    fn tuples(&self) -> usize { <ColumnarLeaf<K, R> as Trie>::keys(&self) }
+    fn heap_size(&self) -> usize {
+        self.keys.capacity() * ::std::mem::size_of::<K>() + self.diffs.capacity() * ::std::mem::size_of::<R>()
+    }
+    fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor {
+        ColumnarLeafCursor {
+            bounds: (lower, upper),
+            pos: lower,
+        }
+    }
+}
+
+/// A builder for columnar leaves.
+pub struct ColumnarLeafBuilder<K, R> {
+    /// Ordered keys.
+    pub keys: Vec<K>,
+    /// Differences, with `diffs[i]` associated with `keys[i]`.
+    pub diffs: Vec<R>,
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> Builder for ColumnarLeafBuilder<K, R> {
+    type Trie = ColumnarLeaf<K, R>;
+    fn boundary(&mut self) -> usize { self.keys.len() }
+    fn done(self) -> Self::Trie { ColumnarLeaf { keys: self.keys, diffs: self.diffs } }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> MergeBuilder for ColumnarLeafBuilder<K, R> {
+    fn with_capacity(other1: &Self::Trie, other2: &Self::Trie) -> Self {
+        let capacity = <ColumnarLeaf<K, R> as Trie>::keys(other1) + <ColumnarLeaf<K, R> as Trie>::keys(other2);
+        ColumnarLeafBuilder {
+            keys: Vec::with_capacity(capacity),
+            diffs: Vec::with_capacity(capacity),
+        }
+    }
+    #[inline(always)]
+    fn copy_range(&mut self, other: &Self::Trie, lower: usize, upper: usize) {
+        self.keys.extend_from_slice(&other.keys[lower .. upper]);
+        self.diffs.extend_from_slice(&other.diffs[lower .. upper]);
+    }
+    fn push_merge(&mut self, other1: (&Self::Trie, usize, usize), other2: (&Self::Trie, usize, usize)) -> usize {
+
+        let (trie1, mut lower1, upper1) = other1;
+        let (trie2, mut lower2, upper2) = other2;
+
+        self.keys.reserve((upper1 - lower1) + (upper2 - lower2));
+        self.diffs.reserve((upper1 - lower1) + (upper2 - lower2));
+
+        // while both mergees are still active
+        while lower1 < upper1 && lower2 < upper2 {
+
+            match trie1.keys[lower1].cmp(&trie2.keys[lower2]) {
+                ::std::cmp::Ordering::Less => {
+                    // determine how far we can advance lower1 until we reach/pass lower2
+                    let step = 1 + advance(&trie1.keys[(1+lower1)..upper1], |x| x < &trie2.keys[lower2]);
+                    <ColumnarLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie1, lower1, lower1 + step);
+                    lower1 += step;
+                }
+                ::std::cmp::Ordering::Equal => {
+
+                    let sum = trie1.diffs[lower1] + trie2.diffs[lower2];
+                    if !sum.is_zero() {
+                        self.keys.push(trie1.keys[lower1].clone());
+                        self.diffs.push(sum);
+                    }
+
+                    lower1 += 1;
+                    lower2 += 1;
+                }
+                ::std::cmp::Ordering::Greater => {
+                    // determine how far we can advance lower2 until we reach/pass lower1
+                    let step = 1 + advance(&trie2.keys[(1+lower2)..upper2], |x| x < &trie1.keys[lower1]);
+                    <ColumnarLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie2, lower2, lower2 + step);
+                    lower2 += step;
+                }
+            }
+        }
+
+        if lower1 < upper1 { <ColumnarLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie1, lower1, upper1); }
+        if lower2 < upper2 { <ColumnarLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie2, lower2, upper2); }
+
+        self.keys.len()
+    }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> TupleBuilder for ColumnarLeafBuilder<K, R> {
+    type Item = (K, R);
+    fn new() -> Self { ColumnarLeafBuilder { keys: Vec::new(), diffs: Vec::new() } }
+    fn with_capacity(cap: usize) -> Self {
+        ColumnarLeafBuilder {
+            keys: Vec::with_capacity(cap),
+            diffs: Vec::with_capacity(cap),
+        }
+    }
+    #[inline(always)]
+    fn push_tuple(&mut self, (key, diff): (K, R)) {
+        self.keys.push(key);
+        self.diffs.push(diff);
+    }
+}
+
+/// A cursor for walking through a columnar leaf.
+///
+/// The cursor reveals keys through `Cursor::key`, and their differences through `diff`.
+#[derive(Debug)]
+pub struct ColumnarLeafCursor {
+    pos: usize,
+    bounds: (usize, usize),
+}
+
+impl ColumnarLeafCursor {
+    /// Reveals the difference associated with the current key.
+    pub fn diff<'a, K, R>(&self, storage: &'a ColumnarLeaf<K, R>) -> &'a R { &storage.diffs[self.pos] }
+}
+
+impl<K: Ord, R> Cursor<ColumnarLeaf<K, R>> for ColumnarLeafCursor {
+    type Key = K;
+    fn key<'a>(&self, storage: &'a ColumnarLeaf<K, R>) -> &'a Self::Key { &storage.keys[self.pos] }
+    fn step(&mut self, storage: &ColumnarLeaf<K, R>) {
+        self.pos += 1;
+        if !self.valid(storage) {
+            self.pos = self.bounds.1;
+        }
+    }
+    fn seek(&mut self, storage: &ColumnarLeaf<K, R>, key: &Self::Key) {
+        self.pos += advance(&storage.keys[self.pos .. self.bounds.1], |k| k.lt(key));
+    }
+    fn valid(&self, _storage: &ColumnarLeaf<K, R>) -> bool { self.pos < self.bounds.1 }
+    fn rewind(&mut self, _storage: &ColumnarLeaf<K, R>) {
+        self.pos = self.bounds.0;
+    }
+    fn reposition(&mut self, _storage: &ColumnarLeaf<K, R>, lower: usize, upper: usize) {
+        self.pos = lower;
+        self.bounds = (lower, upper);
+    }
+}
@@ -54,6 +54,7 @@ impl<K: Clone+HashOrdered+Default, L: Trie> Trie for HashedLayer<K, L> {
 
 	fn keys(&self) -> usize { self.keys.len() }
 	fn tuples(&self) -> usize { self.vals.tuples() }
+	fn heap_size(&self) -> usize { self.keys.capacity() * ::std::mem::size_of::<Entry<K>>() + self.vals.heap_size() }
 	fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor {
 
 		if lower < upper {
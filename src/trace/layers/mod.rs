@@ -6,6 +6,8 @@
 
 pub mod ordered;
 pub mod ordered_leaf;
+pub mod columnar_leaf;
+pub mod rle_leaf;
 pub mod hashed;
 // pub mod weighted;
 // pub mod unordered;
@@ -29,6 +31,14 @@ pub trait Trie  : ::std::marker::Sized {
 	fn keys(&self) -> usize;
 	/// The total number of tuples in the collection.
 	fn tuples(&self) -> usize;
+	/// An estimate of the heap memory used by the collection, in bytes.
+	///
+	/// The estimate sums the allocated capacities of the collection's vectors, and does not account for
+	/// memory owned by the tuples themselves, for example by `String` keys.
+	///
+	/// The default implementation reports zero, and so layers that do not override it are left out of the
+	/// estimates of the layers and batches containing them.
+	fn heap_size(&self) -> usize { 0 }
 	/// Returns a cursor capable of navigating the collection.
 	fn cursor(&self) -> Self::Cursor { self.cursor_from(0, self.keys()) }
 	/// Returns a cursor over a range of data, commonly used by others to restrict navigation to 
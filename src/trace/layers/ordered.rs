@@ -26,6 +26,11 @@ impl<K: Ord+Clone, L: Trie> Trie for OrderedLayer<K, L> {
 
 	fn keys(&self) -> usize { self.keys.len() }
 	fn tuples(&self) -> usize { self.vals.tuples() }
+	fn heap_size(&self) -> usize {
+		self.keys.capacity() * ::std::mem::size_of::<K>() +
+		self.offs.capacity() * ::std::mem::size_of::<usize>() +
+		self.vals.heap_size()
+	}
 	fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor {
 
 		if lower < upper {
@@ -232,6 +237,28 @@ impl<K: Ord, L: Trie> Cursor<OrderedLayer<K, L>> for OrderedCursor<L> {
 	}
 }
 
+impl<L: Trie> OrderedCursor<L> {
+	/// Retreats the cursor by one element, becoming invalid if it was at its first element.
+	pub fn step_back<K: Ord>(&mut self, storage: &OrderedLayer<K, L>) {
+		if self.pos < self.bounds.1 {
+			if self.pos > self.bounds.0 {
+				self.pos -= 1;
+				self.child.reposition(&storage.vals, storage.offs[self.pos], storage.offs[self.pos + 1]);
+			}
+			else {
+				self.pos = self.bounds.1;
+			}
+		}
+	}
+	/// Rewinds the cursor to its last element.
+	pub fn rewind_back<K: Ord>(&mut self, storage: &OrderedLayer<K, L>) {
+		if self.bounds.0 < self.bounds.1 {
+			self.pos = self.bounds.1 - 1;
+			self.child.reposition(&storage.vals, storage.offs[self.pos], storage.offs[self.pos + 1]);
+		}
+	}
+}
+
 /// Reports the number of elements satisfing the predicate.
 ///
 /// This methods *relies strongly* on the assumption that the predicate
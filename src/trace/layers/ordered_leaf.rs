@@ -18,6 +18,7 @@ impl<K: Ord+Clone, R: Diff+Clone> Trie for OrderedLeaf<K, R> {
     type TupleBuilder = OrderedLeafBuilder<K, R>;
     fn keys(&self) -> usize { self.vals.len() }
     fn tuples(&self) -> usize { <OrderedLeaf<K, R> as Trie>::keys(&self) }
+    fn heap_size(&self) -> usize { self.vals.capacity() * ::std::mem::size_of::<(K, R)>() }
     fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor { 
         OrderedLeafCursor {
             bounds: (lower, upper),
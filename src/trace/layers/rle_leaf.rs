@@ -0,0 +1,219 @@
+//! Implementation storing keys alongside run-length encoded differences.
+
+use difference::Diff;
+
+use super::{Trie, Cursor, Builder, MergeBuilder, TupleBuilder};
+use super::ordered_leaf::advance;
+
+/// A layer of ordered keys, whose differences are stored as runs of equal values.
+///
+/// Each key remains individually addressable, but consecutive equal differences are stored once,
+/// so that a layer whose differences are all `1` stores a single difference however many keys it has.
+#[derive(Debug, Eq, PartialEq, Clone, Abomonation)]
+pub struct RleLeaf<K, R> {
+    /// Ordered keys.
+    pub keys: Vec<K>,
+    /// Runs of differences, each with the exclusive upper bound of the keys it applies to.
+    pub runs: Vec<(R, usize)>,
+}
+
+impl<K, R> RleLeaf<K, R> {
+    /// The index of the run containing the difference for `keys[index]`.
+    #[inline(always)]
+    pub fn run_of(&self, index: usize) -> usize {
+        advance(&self.runs[..], |run| run.1 <= index)
+    }
+    /// The difference associated with `keys[index]`.
+    pub fn diff_at(&self, index: usize) -> &R { &self.runs[self.run_of(index)].0 }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> Trie for RleLeaf<K, R> {
+    type Item = (K, R);
+    type Cursor = RleLeafCursor;
+    type MergeBuilder = RleLeafBuilder<K, R>;
+    type TupleBuilder = RleLeafBuilder<K, R>;
+    fn keys(&self) -> usize { self.keys.len() }
+    fn tuples(&self) -> usize { <RleLeaf<K, R> as Trie>::keys(&self) }
+    fn heap_size(&self) -> usize {
+        self.keys.capacity() * ::std::mem::size_of::<K>() + self.runs.capacity() * ::std::mem::size_of::<(R, usize)>()
+    }
+    fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor {
+        RleLeafCursor {
+            bounds: (lower, upper),
+            pos: lower,
+            run: self.run_of(lower),
+        }
+    }
+}
+
+/// A builder for run-length encoded leaves.
+pub struct RleLeafBuilder<K, R> {
+    /// Ordered keys.
+    pub keys: Vec<K>,
+    /// Runs of differences, each with the exclusive upper bound of the keys it applies to.
+    pub runs: Vec<(R, usize)>,
+}
+
+impl<K, R: Diff> RleLeafBuilder<K, R> {
+    // Extends the runs to cover all keys pushed so far, the most recent of which have difference `diff`.
+    #[inline(always)]
+    fn extend_runs(&mut self, diff: R) {
+        let upper = self.keys.len();
+        if let Some(last) = self.runs.last_mut() {
+            if (last.0 - diff).is_zero() {
+                last.1 = upper;
+                return;
+            }
+        }
+        self.runs.push((diff, upper));
+    }
+    /// Removes and decodes all `(key, diff)` pairs from `index` onward.
+    pub fn drain_from(&mut self, index: usize) -> Vec<(K, R)> {
+        let mut run = advance(&self.runs[..], |run| run.1 <= index);
+        let first = run;
+        let mut result = Vec::with_capacity(self.keys.len() - index);
+        for (position, key) in self.keys.drain(index ..).enumerate() {
+            while index + position >= self.runs[run].1 { run += 1; }
+            result.push((key, self.runs[run].0));
+        }
+        // Retain the runs ending at or before `index`, and cut short a run straddling it.
+        let start = if first > 0 { self.runs[first - 1].1 } else { 0 };
+        if start < index {
+            self.runs.truncate(first + 1);
+            self.runs[first].1 = index;
+        }
+        else {
+            self.runs.truncate(first);
+        }
+        result
+    }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> Builder for RleLeafBuilder<K, R> {
+    type Trie = RleLeaf<K, R>;
+    fn boundary(&mut self) -> usize { self.keys.len() }
+    fn done(self) -> Self::Trie { RleLeaf { keys: self.keys, runs: self.runs } }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> MergeBuilder for RleLeafBuilder<K, R> {
+    fn with_capacity(other1: &Self::Trie, other2: &Self::Trie) -> Self {
+        RleLeafBuilder {
+            keys: Vec::with_capacity(<RleLeaf<K, R> as Trie>::keys(other1) + <RleLeaf<K, R> as Trie>::keys(other2)),
+            runs: Vec::with_capacity(other1.runs.len() + other2.runs.len()),
+        }
+    }
+    #[inline(always)]
+    fn copy_range(&mut self, other: &Self::Trie, lower: usize, upper: usize) {
+        // Copy runs rather than individual differences, clipping them to `lower .. upper`.
+        let mut run = other.run_of(lower);
+        let mut position = lower;
+        while position < upper {
+            let run_upper = ::std::cmp::min(other.runs[run].1, upper);
+            self.keys.extend_from_slice(&other.keys[position .. run_upper]);
+            self.extend_runs(other.runs[run].0);
+            position = run_upper;
+            run += 1;
+        }
+    }
+    fn push_merge(&mut self, other1: (&Self::Trie, usize, usize), other2: (&Self::Trie, usize, usize)) -> usize {
+
+        let (trie1, mut lower1, upper1) = other1;
+        let (trie2, mut lower2, upper2) = other2;
+
+        self.keys.reserve((upper1 - lower1) + (upper2 - lower2));
+
+        // while both mergees are still active
+        while lower1 < upper1 && lower2 < upper2 {
+
+            match trie1.keys[lower1].cmp(&trie2.keys[lower2]) {
+                ::std::cmp::Ordering::Less => {
+                    // determine how far we can advance lower1 until we reach/pass lower2
+                    let step = 1 + advance(&trie1.keys[(1+lower1)..upper1], |x| x < &trie2.keys[lower2]);
+                    <RleLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie1, lower1, lower1 + step);
+                    lower1 += step;
+                }
+                ::std::cmp::Ordering::Equal => {
+
+                    let sum = *trie1.diff_at(lower1) + *trie2.diff_at(lower2);
+                    if !sum.is_zero() {
+                        self.keys.push(trie1.keys[lower1].clone());
+                        self.extend_runs(sum);
+                    }
+
+                    lower1 += 1;
+                    lower2 += 1;
+                }
+                ::std::cmp::Ordering::Greater => {
+                    // determine how far we can advance lower2 until we reach/pass lower1
+                    let step = 1 + advance(&trie2.keys[(1+lower2)..upper2], |x| x < &trie1.keys[lower1]);
+                    <RleLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie2, lower2, lower2 + step);
+                    lower2 += step;
+                }
+            }
+        }
+
+        if lower1 < upper1 { <RleLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie1, lower1, upper1); }
+        if lower2 < upper2 { <RleLeafBuilder<K, R> as MergeBuilder>::copy_range(self, trie2, lower2, upper2); }
+
+        self.keys.len()
+    }
+}
+
+impl<K: Ord+Clone, R: Diff+Clone> TupleBuilder for RleLeafBuilder<K, R> {
+    type Item = (K, R);
+    fn new() -> Self { RleLeafBuilder { keys: Vec::new(), runs: Vec::new() } }
+    fn with_capacity(cap: usize) -> Self {
+        RleLeafBuilder {
+            keys: Vec::with_capacity(cap),
+            runs: Vec::new(),
+        }
+    }
+    #[inline(always)]
+    fn push_tuple(&mut self, (key, diff): (K, R)) {
+        self.keys.push(key);
+        self.extend_runs(diff);
+    }
+}
+
+/// A cursor for walking through a run-length encoded leaf.
+///
+/// The cursor reveals keys through `Cursor::key`, and their differences through `diff`.
+#[derive(Debug)]
+pub struct RleLeafCursor {
+    pos: usize,
+    run: usize,
+    bounds: (usize, usize),
+}
+
+impl RleLeafCursor {
+    /// Reveals the difference associated with the current key.
+    pub fn diff<'a, K, R>(&self, storage: &'a RleLeaf<K, R>) -> &'a R { &storage.runs[self.run].0 }
+}
+
+impl<K: Ord, R> Cursor<RleLeaf<K, R>> for RleLeafCursor {
+    type Key = K;
+    fn key<'a>(&self, storage: &'a RleLeaf<K, R>) -> &'a Self::Key { &storage.keys[self.pos] }
+    fn step(&mut self, storage: &RleLeaf<K, R>) {
+        self.pos += 1;
+        if self.valid(storage) {
+            if self.pos >= storage.runs[self.run].1 { self.run += 1; }
+        }
+        else {
+            self.pos = self.bounds.1;
+        }
+    }
+    fn seek(&mut self, storage: &RleLeaf<K, R>, key: &Self::Key) {
+        self.pos += advance(&storage.keys[self.pos .. self.bounds.1], |k| k.lt(key));
+        self.run = storage.run_of(self.pos);
+    }
+    fn valid(&self, _storage: &RleLeaf<K, R>) -> bool { self.pos < self.bounds.1 }
+    fn rewind(&mut self, storage: &RleLeaf<K, R>) {
+        self.pos = self.bounds.0;
+        self.run = storage.run_of(self.pos);
+    }
+    fn reposition(&mut self, storage: &RleLeaf<K, R>, lower: usize, upper: usize) {
+        self.pos = lower;
+        self.run = storage.run_of(lower);
+        self.bounds = (lower, upper);
+    }
+}
@@ -13,8 +13,10 @@ pub mod implementations;
 pub mod layers;
 pub mod wrappers;
 
+use timely::order::PartialOrder;
+
 use ::Diff;
-pub use self::cursor::Cursor;
+pub use self::cursor::{Cursor, ReverseCursor};
 pub use self::description::Description;
 
 // 	The traces and batch and cursors want the flexibility to appear as if they manage certain types of keys and
@@ -62,8 +64,21 @@ pub trait TraceReader<Key, Val, Time, R> {
 	/// the trace, and (ii) the trace has not been advanced beyond `upper`. Practically, the implementation should
 	/// be expected to look for a "clean cut" using `upper`, and if it finds such a cut can return a cursor. This
 	/// should allow `upper` such as `&[]` as used by `self.cursor()`, though it is difficult to imagine other uses.
+	///
+	/// If no clean cut exists, for example because `upper` straddles a batch, the method returns `None`. This can
+	/// happen transiently when frontiers disagree, and callers should retry once the frontier has advanced.
 	fn cursor_through(&mut self, upper: &[Time]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<Key, Val, Time, R>>::Storage)>;
 
+	/// Acquires a cursor as `cursor_through`, together with the advance frontier of the trace at that moment.
+	///
+	/// The times the cursor presents may have been advanced by the returned frontier, and accumulate correctly
+	/// only for times greater or equal to one of its elements. Consumers that advance times themselves should
+	/// advance them by at least this frontier, so that their results do not depend on how the trace compacted.
+	fn cursor_through_since(&mut self, upper: &[Time]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<Key, Val, Time, R>>::Storage, Vec<Time>)> where Time: Clone {
+		let since = self.advance_frontier().to_vec();
+		self.cursor_through(upper).map(|(cursor, storage)| (cursor, storage, since))
+	}
+
 	/// Advances the frontier of times the collection must be correctly accumulable through.
 	///
 	/// Practically, this allows the trace to advance times in updates it maintains as long as the advanced times
@@ -79,6 +94,24 @@ pub trait TraceReader<Key, Val, Time, R> {
 	/// be accurate from the frontier onwards.
 	fn advance_frontier(&mut self) -> &[Time];
 
+	/// Advances the frontier of times the collection must be correctly accumulable through, as `advance_by`,
+	/// reporting rather than applying a frontier that does not advance the current frontier.
+	///
+	/// Each element of a non-empty `frontier` must be greater or equal to an element of the current advance
+	/// frontier, as otherwise the trace would claim to accurately accumulate times it may already have lost
+	/// the ability to distinguish. The empty frontier, which indicates that no further accumulation is needed,
+	/// is always permitted.
+	fn set_logical_compaction(&mut self, frontier: &[Time]) -> Result<(), CompactionError<Time>> where Time: PartialOrder+Clone {
+		if frontier.len() > 0 {
+			let current = self.advance_frontier();
+			if !frontier.iter().all(|t1| current.iter().any(|t2| t2.less_equal(t1))) {
+				return Err(CompactionError::Regression { current: current.to_vec(), proposed: frontier.to_vec() });
+			}
+		}
+		self.advance_by(frontier);
+		Ok(())
+	}
+
 	/// Advances the frontier that may be used in `cursor_through`.
 	///
 	/// Practically, this allows the trace to merge batches whose upper frontier comes before `frontier`. The trace
@@ -104,6 +137,17 @@ pub trait TraceReader<Key, Val, Time, R> {
 	/// cursor methods, as they (by default) just move through batches accumulating cursors into a cursor list.
 	fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F);
 
+	/// Reports an estimate of the number of updates in the trace, if one is cheaply available.
+	///
+	/// The estimate is intended for planning, for example to choose join orders, and need not be exact: updates
+	/// that would consolidate may be counted separately. Implementations should not enumerate updates to produce
+	/// the estimate, and the default implementation reports `None`.
+	fn num_updates_hint(&self) -> Option<usize> { None }
+	/// Reports an estimate of the heap memory used by the trace, in bytes, if one is cheaply available.
+	///
+	/// As with `num_updates_hint`, the estimate is intended for monitoring and planning, for example to throttle
+	/// inputs when an arrangement grows, and the default implementation reports `None`.
+	fn estimated_bytes_hint(&self) -> Option<usize> { None }
 }
 
 /// An append-only collection of `(key, val, time, diff)` tuples.
@@ -129,6 +173,19 @@ where <Self as TraceReader<Key, Val, Time, R>>::Batch: Batch<Key, Val, Time, R>
 	/// commute. For now, the trace should complain, to the extent that it cares about contiguous intervals.
 	fn insert(&mut self, batch: Self::Batch);
 
+	/// Introduces a batch of updates to the trace, reporting rather than panicking on a discontinuity.
+	///
+	/// If the lower bound of `batch` does not equal the upper bound of the most recent addition, or if `batch`
+	/// has equal lower and upper bounds but contains updates, the trace is left unchanged and an `InsertError`
+	/// describing the problem is returned. A closed trace refuses all batches that are not degenerate.
+	///
+	/// The default implementation calls `insert` and reports no errors; traces able to detect invalid batches
+	/// should override it.
+	fn insert_batch_checked(&mut self, batch: Self::Batch) -> Result<(), InsertError<Time>> {
+		self.insert(batch);
+		Ok(())
+	}
+
 	/// Introduces an empty batch concluding the trace.
 	///
 	/// This method should be logically equivalent to introducing an empty batch whose lower frontier equals
@@ -136,6 +193,47 @@ where <Self as TraceReader<Key, Val, Time, R>>::Batch: Batch<Key, Val, Time, R>
 	fn close(&mut self);
 }
 
+/// The reasons a batch may be refused by `Trace::insert_batch_checked`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InsertError<T> {
+	/// The lower bound of the batch does not equal the upper bound of the trace.
+	Discontinuity {
+		/// The upper bound of the trace, which the batch should have started from.
+		expected: Vec<T>,
+		/// The lower bound of the batch.
+		found: Vec<T>,
+	},
+	/// The batch has equal lower and upper bounds, which admit no times, but contains updates.
+	NonEmptyDegenerate {
+		/// The lower and upper bounds of the batch.
+		bounds: Vec<T>,
+		/// The number of updates in the batch.
+		len: usize,
+	},
+}
+
+/// The reasons a trace may be unable to provide a cursor through a frontier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CursorError<T> {
+	/// The requested upper frontier is not greater or equal to the frontier the trace distinguishes.
+	UpperTooLow {
+		/// The frontier from which the trace may be subsetted, as set by `distinguish_since`.
+		through_frontier: Vec<T>,
+	},
+}
+
+/// The reasons a trace may refuse to advance its logical compaction frontier, in `set_logical_compaction`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompactionError<T> {
+	/// The proposed frontier has an element not greater or equal to any element of the current frontier.
+	Regression {
+		/// The current advance frontier of the trace.
+		current: Vec<T>,
+		/// The frontier supplied to `set_logical_compaction`.
+		proposed: Vec<T>,
+	},
+}
+
 /// A batch of updates whose contents may be read.
 ///
 /// This is a restricted interface to batches of updates, which support the reading of the batch's contents,
@@ -157,6 +255,44 @@ pub trait BatchReader<K, V, T, R> where Self: ::std::marker::Sized
 	fn lower(&self) -> &[T] { self.description().lower() }
 	/// All times in the batch are not greater or equal to any element of `upper`.
 	fn upper(&self) -> &[T] { self.description().upper() }
+	/// Times in the batch may have been advanced by the elements of `since`.
+	fn since(&self) -> &[T] { self.description().since() }
+
+	/// The least and greatest keys of the batch, or `None` if the batch has no keys.
+	///
+	/// The default implementation steps a cursor through all keys of the batch.
+	fn key_bounds(&self) -> Option<(&K, &K)> {
+		let mut cursor = self.cursor();
+		if cursor.key_valid(self) {
+			let lower = cursor.key(self);
+			let mut upper = lower;
+			while cursor.key_valid(self) {
+				upper = cursor.key(self);
+				cursor.step_key(self);
+			}
+			Some((lower, upper))
+		}
+		else {
+			None
+		}
+	}
+	/// An estimate of the number of distinct keys in the batch.
+	///
+	/// The default implementation counts the keys presented by a cursor, and is exact.
+	fn distinct_keys_hint(&self) -> usize {
+		let mut cursor = self.cursor();
+		let mut count = 0;
+		while cursor.key_valid(self) {
+			count += 1;
+			cursor.step_key(self);
+		}
+		count
+	}
+	/// An estimate of the heap memory used by the batch, in bytes.
+	///
+	/// The default implementation assumes each update occupies a `(K, V, T, R)` tuple, which overestimates
+	/// batches that share keys and values among updates.
+	fn estimated_bytes(&self) -> usize { self.len() * ::std::mem::size_of::<(K, V, T, R)>() }
 }
 
 /// An immutable collection of updates.
@@ -176,6 +312,13 @@ pub trait Batch<K, V, T, R> : BatchReader<K, V, T, R> where Self: ::std::marker:
 	fn begin_merge(&self, other: &Self) -> Self::Merger {
 		Self::Merger::new(self, other)
 	}
+
+	/// Creates an empty batch with the supplied bounds.
+	///
+	/// The default implementation uses the batch's builder, and takes `lower` as the batch's `since`.
+	fn empty(lower: &[T], upper: &[T]) -> Self {
+		<Self::Builder as Builder<K, V, T, R, Self>>::new().done(lower, upper, lower)
+	}
 }
 
 /// Functionality for collecting and batching updates.
@@ -213,8 +356,19 @@ pub trait Merger<K, V, T, R, Output: Batch<K, V, T, R>> {
 	/// Perform some amount of work, decrementing `fuel`.
 	///
 	/// If `fuel` is non-zero after the call, the merging is complete and
-	/// one should call `done` to extract the merged results.
+	/// one should call `done` to extract the merged results. Implementations
+	/// should not perform more work than the fuel supplied to the call, and
+	/// may leave fuel unspent if the next unit of work needs more; callers
+	/// that want such a unit completed must supply enough fuel in one call.
 	fn work(&mut self, source1: &Output, source2: &Output, frontier: &Option<Vec<T>>, fuel: &mut usize);
+	/// Reports the progress of the merge, as the work done and an estimate of the total work.
+	///
+	/// The work done should not decrease as `work` is called, and should equal the total once the
+	/// merge is complete. Implementations may measure work in any unit, for example input updates.
+	///
+	/// The default implementation reports `(0, 0)`, as if no work were ever done. Fueled traces count work by
+	/// progress, and so offer such a merger, in each call, all the fuel supplied to it since the merge began.
+	fn progress(&self) -> (usize, usize) { (0, 0) }
 	/// Extracts merged results.
 	///
 	/// This method should only be called after `work` has been called and
@@ -225,11 +379,19 @@ pub trait Merger<K, V, T, R, Output: Batch<K, V, T, R>> {
 
 
 /// Blanket implementations for reference counted batches.
+///
+/// A trace of `Rc<B>` batches clones batches by incrementing a reference count, rather than copying their
+/// updates, which makes `cursor_through` and other methods that clone batches into cursor storage cheap.
+/// As `Rc` is neither `Send` nor `Sync`, shared batches may only be used within the thread that built them;
+/// batches exchanged between workers should be serialized, for example with `Abomonated`.
 pub mod rc_blanket_impls {
 
 	use std::rc::Rc;
 
-	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description};
+	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, ReverseCursor, Description};
+
+	/// A batch shared by reference counting, whose clones share the same underlying storage.
+	pub type Shared<B> = Rc<B>;
 
 	impl<K, V, T, R, B: BatchReader<K,V,T,R>> BatchReader<K,V,T,R> for Rc<B> {
 
@@ -244,6 +406,13 @@ pub mod rc_blanket_impls {
 		fn len(&self) -> usize { (&**self).len() }
 		/// Describes the times of the updates in the batch.
 		fn description(&self) -> &Description<T> { (&**self).description() }
+
+		/// The least and greatest keys of the batch.
+		fn key_bounds(&self) -> Option<(&K, &K)> { (&**self).key_bounds() }
+		/// An estimate of the number of distinct keys in the batch.
+		fn distinct_keys_hint(&self) -> usize { (&**self).distinct_keys_hint() }
+		/// An estimate of the heap memory used by the batch, in bytes.
+		fn estimated_bytes(&self) -> usize { (&**self).estimated_bytes() }
 	}
 
 	/// Wrapper to provide cursor to nested scope.
@@ -284,6 +453,14 @@ pub mod rc_blanket_impls {
 
 	    #[inline(always)] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(storage) }
 	    #[inline(always)] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage) }
+
+	    #[inline(always)] fn lacks_key(&self, storage: &Self::Storage, key: &K) -> bool { self.cursor.lacks_key(storage, key) }
+	}
+
+	impl<K, V, T, R, B: BatchReader<K, V, T, R>> ReverseCursor<K, V, T, R> for RcBatchCursor<K, V, T, R, B>
+	where B::Cursor: ReverseCursor<K, V, T, R> {
+	    #[inline(always)] fn step_key_back(&mut self, storage: &Self::Storage) { self.cursor.step_key_back(storage) }
+	    #[inline(always)] fn rewind_keys_back(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys_back(storage) }
 	}
 
 	/// An immutable collection of updates.
@@ -291,6 +468,8 @@ pub mod rc_blanket_impls {
 		type Batcher = RcBatcher<K, V, T, R, B>;
 		type Builder = RcBuilder<K, V, T, R, B>;
 		type Merger = RcMerger<K, V, T, R, B>;
+
+		fn empty(lower: &[T], upper: &[T]) -> Self { Rc::new(B::empty(lower, upper)) }
 	}
 
 	/// Wrapper type for batching reference counted batches.
@@ -322,6 +501,7 @@ pub mod rc_blanket_impls {
 	impl<K,V,T,R,B:Batch<K,V,T,R>> Merger<K, V, T, R, Rc<B>> for RcMerger<K,V,T,R,B> {
 		fn new(source1: &Rc<B>, source2: &Rc<B>) -> Self { RcMerger { merger: B::begin_merge(source1, source2) } }
 		fn work(&mut self, source1: &Rc<B>, source2: &Rc<B>, frontier: &Option<Vec<T>>, fuel: &mut usize) { self.merger.work(source1, source2, frontier, fuel) }
+		fn progress(&self) -> (usize, usize) { self.merger.progress() }
 		fn done(self) -> Rc<B> { Rc::new(self.merger.done()) }
 	}
 }
@@ -350,6 +530,13 @@ pub mod abomonated_blanket_impls {
 		fn len(&self) -> usize { (&**self).len() }
 		/// Describes the times of the updates in the batch.
 		fn description(&self) -> &Description<T> { (&**self).description() }
+
+		/// The least and greatest keys of the batch.
+		fn key_bounds(&self) -> Option<(&K, &K)> { (&**self).key_bounds() }
+		/// An estimate of the number of distinct keys in the batch.
+		fn distinct_keys_hint(&self) -> usize { (&**self).distinct_keys_hint() }
+		/// An estimate of the heap memory used by the batch, in bytes.
+		fn estimated_bytes(&self) -> usize { (&**self).estimated_bytes() }
 	}
 
 	/// Wrapper to provide cursor to nested scope.
@@ -390,6 +577,8 @@ pub mod abomonated_blanket_impls {
 
 	    #[inline(always)] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(storage) }
 	    #[inline(always)] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage) }
+
+	    #[inline(always)] fn lacks_key(&self, storage: &Self::Storage, key: &K) -> bool { self.cursor.lacks_key(storage, key) }
 	}
 
 	/// An immutable collection of updates.
@@ -442,6 +631,7 @@ pub mod abomonated_blanket_impls {
 		fn work(&mut self, source1: &Abomonated<B,Vec<u8>>, source2: &Abomonated<B,Vec<u8>>, frontier: &Option<Vec<T>>, fuel: &mut usize) {
 			self.merger.work(source1, source2, frontier, fuel)
 		}
+		fn progress(&self) -> (usize, usize) { self.merger.progress() }
 		fn done(self) -> Abomonated<B, Vec<u8>> {
 			let batch = self.merger.done();
 			let mut bytes = Vec::with_capacity(measure(&batch));
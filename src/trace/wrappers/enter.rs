@@ -81,6 +81,8 @@ where
         }
         self.trace.cursor_through(&self.stash1[..]).map(|(x,y)| (CursorEnter::new(x), y))
     }
+    fn num_updates_hint(&self) -> Option<usize> { self.trace.num_updates_hint() }
+    fn estimated_bytes_hint(&self) -> Option<usize> { self.trace.estimated_bytes_hint() }
 }
 
 impl<K, V, T, R, Tr, TInner> TraceEnter<K, V, T, R, Tr, TInner>
@@ -103,6 +103,10 @@ where
     fn distinguish_since(&mut self, frontier: &[T]) { self.trace.distinguish_since(frontier) }
     fn distinguish_frontier(&mut self) -> &[T] { self.trace.distinguish_frontier() }
 
+    // Frozen updates may be dropped, so the hint of the wrapped trace is an overestimate.
+    fn num_updates_hint(&self) -> Option<usize> { self.trace.num_updates_hint() }
+    fn estimated_bytes_hint(&self) -> Option<usize> { self.trace.estimated_bytes_hint() }
+
     fn cursor_through(&mut self, upper: &[T]) -> Option<(Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage)> {
         let func = &self.func;
         self.trace.cursor_through(upper)
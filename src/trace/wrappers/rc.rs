@@ -34,6 +34,8 @@ where T: Lattice+Ord+Clone+'static, Tr: TraceReader<K,V,T,R> {
     pub through_frontiers: MutableAntichain<T>,
     /// The wrapped trace.
     pub trace: Tr,
+    /// Set once a reader has requested the contents of the trace.
+    pub demanded: bool,
 }
 
 impl<K,V,T,R,Tr> TraceBox<K,V,T,R,Tr>
@@ -61,6 +63,7 @@ where T: Lattice+Ord+Clone+'static, Tr: TraceReader<K,V,T,R> {
             advance_frontiers: advance,
             through_frontiers: through,
             trace: trace,
+            demanded: false,
         }
     }
     /// Replaces elements of `lower` with those of `upper`.
@@ -122,6 +125,9 @@ impl<K,V,T,R,Tr> TraceReader<K, V, T, R> for TraceRc<K,V,T,R,Tr> where T: Lattic
     fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) {
         ::std::cell::RefCell::borrow_mut(&self.wrapper).trace.map_batches(f)
     }
+
+    fn num_updates_hint(&self) -> Option<usize> { self.wrapper.borrow().trace.num_updates_hint() }
+    fn estimated_bytes_hint(&self) -> Option<usize> { self.wrapper.borrow().trace.estimated_bytes_hint() }
 }
 
 impl<K,V,T,R,Tr> TraceRc<K,V,T,R,Tr> where T: Lattice+Ord+Clone+'static, Tr: TraceReader<K,V,T,R> {
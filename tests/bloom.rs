@@ -0,0 +1,127 @@
+#![cfg(feature = "bloom")]
+
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::bloom::BloomBatch;
+use differential_dataflow::trace::implementations::spine_fueled::Spine;
+use differential_dataflow::trace::{Trace, TraceReader, Batch, BatchReader, Batcher};
+use differential_dataflow::trace::cursor::Cursor;
+
+thread_local!(static COMPARISONS: Cell<usize> = Cell::new(0));
+
+// A key that counts how often it is compared.
+#[derive(Clone, Debug)]
+struct CountedKey(u64);
+
+impl Hash for CountedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+impl PartialEq for CountedKey {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+impl Eq for CountedKey { }
+impl PartialOrd for CountedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for CountedKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+type PlainBatch = Rc<OrdValBatch<CountedKey, u64, usize, i64>>;
+type BloomedBatch = Rc<BloomBatch<OrdValBatch<CountedKey, u64, usize, i64>>>;
+
+// Loads even keys into ten pending batches, each key into one batch, and reports the key a cursor seeking `key`
+// arrives at, the number of key comparisons the seek and the inspection of the cursor performed, and the key
+// the cursor then steps to.
+fn seek_position<B: Batch<CountedKey, u64, usize, i64>+Clone+'static>(key: u64) -> (Option<u64>, usize, Option<u64>) {
+    let mut trace = Spine::<CountedKey, u64, usize, i64, B>::new();
+    let mut batcher = B::Batcher::new();
+    for i in 0 .. 10u64 {
+        batcher.push_batch(&mut (0 .. 1000).map(|j| ((CountedKey(2 * (10 * j + i)), i), i as usize, 1)).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    assert_eq!(trace.num_batches(), 10);
+
+    let (mut cursor, storage) = trace.cursor_through(&[10]).unwrap();
+    COMPARISONS.with(|c| c.set(0));
+    cursor.seek_key(&storage, &CountedKey(key));
+    let position = cursor.get_key(&storage).map(|key| key.0);
+    let comparisons = COMPARISONS.with(|c| c.get());
+    if position.is_some() { cursor.step_key(&storage); }
+    (position, comparisons, cursor.get_key(&storage).map(|key| key.0))
+}
+
+#[test]
+fn test_bloom_absent_key() {
+    // a seek for an absent key arrives at the next greater key, as without the filter.
+    assert_eq!(seek_position::<PlainBatch>(1001).0, Some(1002));
+    assert_eq!(seek_position::<BloomedBatch>(1001).0, Some(1002));
+    assert_eq!(seek_position::<BloomedBatch>(1000000).0, None);
+}
+
+#[test]
+fn test_bloom_present_key() {
+    let (plain, plain_comparisons, plain_next) = seek_position::<PlainBatch>(1000);
+    let (bloomed, bloomed_comparisons, bloomed_next) = seek_position::<BloomedBatch>(1000);
+    assert_eq!(plain, Some(1000));
+    assert_eq!(bloomed, Some(1000));
+    // the batches that certainly lack the key are neither searched nor inspected by the trace's cursor.
+    assert!(bloomed_comparisons * 2 < plain_comparisons);
+    // stepping off the key considers those batches again.
+    assert_eq!(plain_next, Some(1002));
+    assert_eq!(bloomed_next, Some(1002));
+}
+
+#[test]
+fn test_bloom_deferred_seek() {
+    let mut batcher = <BloomedBatch as Batch<CountedKey, u64, usize, i64>>::Batcher::new();
+    batcher.push_batch(&mut (0 .. 1000).map(|j| ((CountedKey(2 * j), j), 0, 1)).collect());
+    let batch = batcher.seal(&[1]);
+    let absent = (0 .. 1000).map(|j| 2 * j + 1).find(|&k| !batch.filter.may_contain(&CountedKey(k))).unwrap();
+
+    // seeking a key the filter excludes searches nothing until the cursor is inspected.
+    let mut cursor = batch.cursor();
+    COMPARISONS.with(|c| c.set(0));
+    cursor.seek_key(&batch, &CountedKey(absent));
+    assert_eq!(COMPARISONS.with(|c| c.get()), 0);
+    assert!(cursor.key_valid(&batch));
+    assert_eq!(cursor.key(&batch).0, absent + 1);
+    assert!(COMPARISONS.with(|c| c.get()) > 0);
+
+    // a deferred seek followed by another seeks once, and never moves the cursor backwards.
+    let mut cursor = batch.cursor();
+    cursor.seek_key(&batch, &CountedKey(absent + 100));
+    cursor.seek_key(&batch, &CountedKey(absent + 1));
+    assert_eq!(cursor.key(&batch).0, absent + 101);
+
+    // rewinding discards a deferred seek.
+    cursor.seek_key(&batch, &CountedKey(absent));
+    cursor.rewind_keys(&batch);
+    assert_eq!(cursor.key(&batch).0, 0);
+}
+
+#[test]
+fn test_bloom_contents() {
+    let mut batcher = <BloomedBatch as Batch<CountedKey, u64, usize, i64>>::Batcher::new();
+    batcher.push_batch(&mut (0 .. 100).map(|j| ((CountedKey(3 * j), j), 0, 1)).collect());
+    let batch = batcher.seal(&[1]);
+
+    assert!((0 .. 100).all(|j| batch.filter.may_contain(&CountedKey(3 * j))));
+    let false_positives = (0 .. 100).filter(|j| batch.filter.may_contain(&CountedKey(3 * j + 1))).count();
+    assert!(false_positives < 10);
+
+    // stepping through a bloom filtered batch visits every key.
+    let mut cursor = batch.cursor();
+    let mut count = 0;
+    while cursor.key_valid(&batch) { count += 1; cursor.step_key(&batch); }
+    assert_eq!(count, 100);
+}
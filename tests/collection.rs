@@ -0,0 +1,384 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::collection::concatenate;
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Consolidate, Join};
+
+#[test]
+fn explode_multiplies_diffs() {
+
+    let data = timely::example(|scope| {
+        vec![(0, Default::default(), 1isize)]
+            .into_iter()
+            .to_stream(scope)
+            .as_collection()
+            .explode(|x| vec![((x, 'a'), 2isize), ((x, 'b'), 3isize)])
+            .inner
+            .capture()
+    });
+
+    let mut extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    extracted[0].1.sort();
+    assert_eq!(extracted[0].1, vec![((0, 'a'), Default::default(), 2), ((0, 'b'), Default::default(), 3)]);
+}
+
+#[test]
+fn explode_retractions() {
+
+    // a retraction of the input retracts each produced record by its weight.
+    let data = timely::example(|scope| {
+        vec![(0, Default::default(), -1isize)]
+            .into_iter()
+            .to_stream(scope)
+            .as_collection()
+            .explode(|x| vec![((x, 'a'), 2isize), ((x, 'b'), 3isize)])
+            .inner
+            .capture()
+    });
+
+    let mut extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    extracted[0].1.sort();
+    assert_eq!(extracted[0].1, vec![((0, 'a'), Default::default(), -2), ((0, 'b'), Default::default(), -3)]);
+}
+
+#[test]
+fn filter_map_matches_filter_and_map() {
+
+    let data = timely::example(|scope| {
+        let input = vec![(1, Default::default(), 1isize), (2, Default::default(), 2), (3, Default::default(), -3), (4, Default::default(), 4)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let x1 = input.filter_map(|x| if x % 2 == 1 { Some(x * 10) } else { None });
+        let x2 = input.filter(|x| x % 2 == 1).map(|x| x * 10);
+
+        (x1.inner.capture(), x2.inner.capture())
+    });
+
+    let (fused, unfused) = data;
+    let fused = fused.extract();
+    assert_eq!(fused, unfused.extract());
+    assert_eq!(fused.len(), 1);
+    assert_eq!(fused[0].1, vec![(10, Default::default(), 1), (30, Default::default(), -3)]);
+}
+
+#[test]
+fn map_in_place_matches_map() {
+
+    let data = timely::example(|scope| {
+        let input = vec![((1, 1), Default::default(), 1isize), ((2, 5), Default::default(), -2), ((3, 0), Default::default(), 3)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let x1 = input.map_in_place(|x| x.1 += x.0);
+        let x2 = input.map(|(x, y)| (x, x + y));
+
+        (x1.inner.capture(), x2.inner.capture())
+    });
+
+    let (in_place, mapped) = data;
+    let in_place = in_place.extract();
+    assert_eq!(in_place, mapped.extract());
+    assert_eq!(in_place.len(), 1);
+    assert_eq!(in_place[0].1, vec![((1, 2), Default::default(), 1), ((2, 7), Default::default(), -2), ((3, 3), Default::default(), 3)]);
+}
+
+#[test]
+fn concatenate_matches_concat() {
+
+    let data = timely::example(|scope| {
+        let inputs = (0 .. 5isize)
+            .map(|i| vec![(i, Default::default(), i + 1), (10, Default::default(), 1)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection())
+            .collect::<Vec<_>>();
+
+        let folded = inputs[1..].iter().fold(inputs[0].clone(), |acc, x| acc.concat(x));
+        let unioned = concatenate(scope, inputs);
+        let empty = concatenate::<_, isize, isize, _>(scope, Vec::new());
+
+        (unioned.consolidate().inner.capture(), folded.consolidate().inner.capture(), empty.inner.capture())
+    });
+
+    let (unioned, folded, empty) = data;
+    let unioned = unioned.extract();
+    assert_eq!(unioned, folded.extract());
+    assert_eq!(unioned.len(), 1);
+    assert_eq!(unioned[0].1, vec![
+        (0, Default::default(), 1),
+        (1, Default::default(), 2),
+        (2, Default::default(), 3),
+        (3, Default::default(), 4),
+        (4, Default::default(), 5),
+        (10, Default::default(), 5),
+    ]);
+    assert!(empty.extract().is_empty());
+}
+
+#[test]
+fn negate_cancels() {
+
+    let data = timely::example(|scope| {
+        let input = vec![(1, Default::default(), 1isize), (2, Default::default(), -2), (3, Default::default(), 3)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let negated = input.negate();
+        let cancelled = input.concat(&negated).consolidate();
+
+        (negated.inner.capture(), cancelled.inner.capture())
+    });
+
+    let (negated, cancelled) = data;
+    let negated = negated.extract();
+    assert_eq!(negated.len(), 1);
+    assert_eq!(negated[0].1, vec![(1, Default::default(), -1), (2, Default::default(), 2), (3, Default::default(), -3)]);
+    assert!(cancelled.extract().iter().all(|x| x.1.is_empty()));
+}
+
+#[test]
+fn inspect_batch_sees_every_record() {
+
+    let seen = Arc::new(Mutex::new((0, 0)));
+    let seen2 = seen.clone();
+
+    let data = timely::example(move |scope| {
+        let seen2 = seen2.clone();
+        (0 .. 100)
+            .map(|x| (x, Default::default(), 1isize))
+            .to_stream(scope)
+            .as_collection()
+            .inspect_batch(move |_time, batch| {
+                let mut seen = seen2.lock().unwrap();
+                seen.0 += 1;
+                seen.1 += batch.len();
+            })
+            .inner
+            .capture()
+    });
+
+    let extracted = data.extract();
+    let records = extracted.iter().map(|x| x.1.len()).sum::<usize>();
+    assert_eq!(records, 100);
+
+    let (batches, total) = *seen.lock().unwrap();
+    assert!(batches > 0);
+    assert_eq!(total, records);
+}
+
+#[test]
+fn delay_shifts_times() {
+
+    let data = timely::example(|scope| {
+        vec![(1, RootTimestamp::new(0), 1isize), (2, RootTimestamp::new(0), -1), (3, RootTimestamp::new(0), 2)]
+            .into_iter()
+            .to_stream(scope)
+            .as_collection()
+            .delay(|t| RootTimestamp::new(t.inner + 1))
+            .inner
+            .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![(1, RootTimestamp::new(1), 1), (2, RootTimestamp::new(1), -1), (3, RootTimestamp::new(1), 2)]);
+}
+
+#[test]
+fn assert_eq_equal_collections() {
+    timely::example(|scope| {
+        let data = (0 .. 10).map(|x| (x, Default::default(), 1isize)).to_stream(scope).as_collection();
+        let odds = data.filter(|x| x % 2 == 1);
+        let evens = data.filter(|x| x % 2 == 0);
+        evens.concat(&odds).assert_eq(&data);
+        data.map(|x| x + 1).map(|x| x - 1).assert_eq(&data);
+    });
+}
+
+#[test]
+#[should_panic(expected = "collection is non-empty, with update (9, ")]
+fn assert_eq_unequal_records() {
+    timely::example(|scope| {
+        let data = (0 .. 10).map(|x| (x, Default::default(), 1isize)).to_stream(scope).as_collection();
+        data.filter(|x| *x != 9).assert_eq(&data);
+    });
+}
+
+#[test]
+#[should_panic(expected = "collection is non-empty, with update (3, ")]
+fn assert_eq_unequal_multiplicities() {
+    timely::example(|scope| {
+        let data = (0 .. 10).map(|x| (x, Default::default(), 1isize)).to_stream(scope).as_collection();
+        let extra = vec![(3, Default::default(), 1isize)].into_iter().to_stream(scope).as_collection();
+        data.concat(&extra).assert_eq(&data);
+    });
+}
+
+#[test]
+fn split_partitions_updates() {
+
+    let data = timely::example(|scope| {
+        let input = vec![(1, Default::default(), 1isize), (2, Default::default(), -2), (3, Default::default(), 3), (4, Default::default(), 4)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        let (odds, evens) = input.split(|x| x % 2 == 1);
+        let union = odds.concat(&evens).consolidate();
+
+        (odds.inner.capture(), evens.inner.capture(), union.inner.capture(), input.consolidate().inner.capture())
+    });
+
+    let (odds, evens, union, input) = data;
+    let odds = odds.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    let evens = evens.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    assert_eq!(odds, vec![(1, Default::default(), 1), (3, Default::default(), 3)]);
+    assert_eq!(evens, vec![(2, Default::default(), -2), (4, Default::default(), 4)]);
+    assert_eq!(union.extract(), input.extract());
+}
+
+#[test]
+fn hashed_partition_preserves_updates() {
+
+    let data = timely::example(|scope| {
+        let input = (0 .. 20u64).map(|x| (x, Default::default(), 1isize)).to_stream(scope).as_collection();
+        input.hashed_partition(3, |x| x * 7).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, (0 .. 20u64).map(|x| (x, Default::default(), 1isize)).collect::<Vec<_>>());
+}
+
+#[test]
+fn hashed_partition_routes_by_hash() {
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = seen.clone();
+
+    timely::execute(Configuration::Process(2), move |worker| {
+        let index = worker.index();
+        let seen = seen2.clone();
+        let mut input = worker.dataflow(|scope| {
+            let (input, data) = scope.new_collection();
+            data.hashed_partition(4, |x: &u64| x / 10)
+                .inspect(move |&(x, _, _)| seen.lock().unwrap().push((x, index)));
+            input
+        });
+        if index == 0 {
+            for x in 0 .. 80u64 { input.insert(x); }
+        }
+    }).unwrap();
+
+    // records with hash `h` form partition `h % 4`, which is held by worker `(h % 4) % 2`.
+    let mut seen = seen.lock().unwrap().clone();
+    seen.sort();
+    assert_eq!(seen, (0 .. 80u64).map(|x| (x, ((x / 10) % 4 % 2) as usize)).collect::<Vec<_>>());
+}
+
+#[test]
+fn rekey_then_join_on_new_key() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+        let index = worker.index();
+        let (mut pairs, mut names) = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (pairs, data) = scope.new_collection();
+            let (names, named) = scope.new_collection();
+            data.rekey(|_key: &u64, val: &u64| *val)
+                .join(&named)
+                .inner
+                .capture_into(send);
+            (pairs, names)
+        });
+        if index == 0 {
+            for key in 0 .. 20u64 { pairs.insert((key, key % 4)); }
+            for val in 0 .. 4u64 { names.insert((val, val * 100)); }
+        }
+    }).unwrap();
+
+    // each `(key, val)` pair meets the name of `val`, now its key.
+    let mut results = recv.extract().into_iter().flat_map(|(_, x)| x).map(|(x, _, diff)| (x, diff)).collect::<Vec<_>>();
+    results.sort();
+    let mut expected = (0 .. 20u64).map(|key| ((key % 4, (key, key % 4), (key % 4) * 100), 1isize)).collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn distinct_times_deduplicates() {
+
+    let data = timely::example(|scope| {
+        vec![(1, RootTimestamp::new(1), 1isize), (2, RootTimestamp::new(1), -1), (3, RootTimestamp::new(2), 2), (4, RootTimestamp::new(5), 1)]
+            .into_iter()
+            .to_stream(scope)
+            .as_collection()
+            .distinct_times()
+            .inner
+            .capture()
+    });
+
+    // each time appears once, at itself, however many updates and of whatever sign occur at it.
+    let mut results = data.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        (RootTimestamp::new(1), RootTimestamp::new(1), 1),
+        (RootTimestamp::new(2), RootTimestamp::new(2), 1),
+        (RootTimestamp::new(5), RootTimestamp::new(5), 1),
+    ]);
+}
+
+#[test]
+fn on_frontier_reports_advances() {
+
+    let frontiers = Arc::new(Mutex::new(Vec::new()));
+    let frontiers2 = frontiers.clone();
+    let updates = Arc::new(Mutex::new(0));
+    let updates2 = updates.clone();
+
+    timely::execute(Configuration::Thread, move |worker| {
+        let frontiers = frontiers2.clone();
+        let updates = updates2.clone();
+        let (mut input, probe) = worker.dataflow::<usize, _, _>(|scope| {
+            let (input, data) = scope.new_collection();
+            let probe = data.on_frontier(move |frontier| frontiers.lock().unwrap().push(frontier.to_vec()))
+                            .inspect(move |_| *updates.lock().unwrap() += 1)
+                            .probe();
+            (input, probe)
+        });
+        for round in 0 .. 5 {
+            input.insert(round);
+            input.advance_to(round + 1);
+            input.flush();
+            worker.step_while(|| probe.less_than(input.time()));
+        }
+    }).unwrap();
+
+    // frontiers are reported as they strictly advance, ending with the empty frontier as the input closes.
+    let frontiers = frontiers.lock().unwrap().clone();
+    assert_eq!(frontiers.last(), Some(&Vec::new()));
+    let advanced = &frontiers[.. frontiers.len() - 1];
+    assert!(advanced.windows(2).all(|pair| pair[0][0] < pair[1][0]));
+    assert_eq!(advanced.last(), Some(&vec![RootTimestamp::new(5)]));
+    assert!(advanced.iter().all(|frontier| frontier.len() == 1));
+    assert_eq!(*updates.lock().unwrap(), 5);
+}
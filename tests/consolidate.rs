@@ -0,0 +1,120 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::Consolidate;
+
+// Feeds `(record, round, diff)` updates through `consolidate_stream`, and returns its output.
+fn consolidate_stream(updates: Vec<(u64, usize, isize)>) -> Vec<(u64, usize, isize)> {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut updates = updates.clone();
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection();
+            collection.consolidate_stream()
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        // only the first worker introduces updates.
+        if worker.index() == 0 {
+            updates.sort_by(|x,y| y.1.cmp(&x.1));
+            let mut round = 0;
+            while updates.len() > 0 {
+                while updates.last().map(|x| x.1) == Some(round) {
+                    let (record, _round, diff) = updates.pop().unwrap();
+                    input.update(record, diff);
+                }
+                round += 1;
+                input.advance_to(round);
+            }
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| (record, time.inner, diff)))
+        .collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+#[test]
+fn consolidate_stream_cancels() {
+    assert_eq!(consolidate_stream(vec![(1, 0, 1), (1, 0, -1)]), vec![]);
+}
+
+#[test]
+fn consolidate_stream_sums() {
+    assert_eq!(consolidate_stream(vec![(1, 0, 1), (1, 0, 2), (2, 0, 1)]), vec![(1, 0, 3), (2, 0, 1)]);
+}
+
+#[test]
+fn consolidate_stream_distinct_times() {
+    assert_eq!(consolidate_stream(vec![(1, 0, 1), (1, 1, -1)]), vec![(1, 0, 1), (1, 1, -1)]);
+}
+
+#[test]
+fn consolidate_named_matches_consolidate() {
+
+    let (send1, recv1) = ::std::sync::mpsc::channel();
+    let send1 = Arc::new(Mutex::new(send1));
+    let (send2, recv2) = ::std::sync::mpsc::channel();
+    let send2 = Arc::new(Mutex::new(send2));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send1 = send1.lock().unwrap().clone();
+            let send2 = send2.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<u64, isize>();
+            collection.consolidate_named("NamedConsolidate")
+                      .inner
+                      .capture_into(send1);
+            collection.consolidate()
+                      .inner
+                      .capture_into(send2);
+            input
+        });
+
+        if worker.index() == 0 {
+            input.update(1, 1);
+            input.update(1, 2);
+            input.update(2, 1);
+            input.update(3, 1);
+            input.update(3, -1);
+            input.advance_to(1);
+            input.update(2, -1);
+            input.advance_to(2);
+        }
+
+    }).unwrap();
+
+    let extract = |recv: ::std::sync::mpsc::Receiver<_>| {
+        let mut results = recv.extract()
+            .into_iter()
+            .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| (record, time.inner, diff)))
+            .collect::<Vec<(u64, usize, isize)>>();
+        results.sort();
+        results
+    };
+
+    let named = extract(recv1);
+    assert_eq!(named, vec![(1, 0, 3), (2, 0, 1), (2, 1, -1)]);
+    assert_eq!(named, extract(recv2));
+}
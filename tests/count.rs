@@ -0,0 +1,72 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::CountTotal;
+
+#[test]
+fn count_total_tracks_net() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection();
+            collection.count_total()
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        if worker.index() == 0 {
+            input.insert(1u64);
+            input.insert(1u64);
+            input.insert(2u64);
+            input.advance_to(1);
+            input.remove(1u64);
+            input.advance_to(2);
+            input.remove(2u64);
+            input.insert(3u64);
+            input.advance_to(3);
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(count, time, diff)| ((time.inner, count), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to a count may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, (u64, isize)), isize)> = Vec::new();
+    for (key, diff) in results {
+        if consolidated.last().map(|x| x.0 == key) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((key, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![
+        ((0, (1, 2)), 1),
+        ((0, (2, 1)), 1),
+        ((1, (1, 1)), 1),
+        ((1, (1, 2)), -1),
+        ((2, (2, 1)), -1),
+        ((2, (3, 1)), 1),
+    ]);
+}
@@ -0,0 +1,80 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::{AsCollection, Diff};
+use differential_dataflow::difference::SaturatingI64;
+use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::trace::{consolidate, Batch, BatchReader, Builder};
+use differential_dataflow::trace::cursor::CursorDebug;
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::spine_fueled::merge_batches;
+
+fn sat(value: i64) -> SaturatingI64 { SaturatingI64::new(value) }
+
+#[test]
+fn saturating_arithmetic() {
+    assert_eq!(sat(i64::max_value()) + sat(1), sat(i64::max_value()));
+    assert_eq!(sat(i64::min_value()) - sat(1), sat(i64::min_value()));
+    assert_eq!(-sat(i64::min_value()), sat(i64::max_value()));
+    assert_eq!(sat(3) + sat(-5), sat(-2));
+    assert!(SaturatingI64::zero().is_zero());
+    assert!(!sat(1).is_zero());
+}
+
+#[test]
+fn saturating_consolidate() {
+    let mut updates = vec![
+        ("a", sat(i64::max_value())),
+        ("b", sat(2)),
+        ("a", sat(i64::max_value())),
+        ("b", sat(-2)),
+        ("c", sat(1)),
+    ];
+    consolidate(&mut updates, 0);
+    assert_eq!(updates, vec![("a", sat(i64::max_value())), ("c", sat(1))]);
+}
+
+#[test]
+fn saturating_merge() {
+    type SaturatingBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, SaturatingI64>;
+
+    let mut builder1 = <SaturatingBatch as Batch<UnsignedWrapper<u64>, u64, usize, SaturatingI64>>::Builder::new();
+    builder1.push((1.into(), 1, 0, sat(i64::max_value())));
+    builder1.push((2.into(), 2, 0, sat(5)));
+    let batch1 = builder1.done(&[0], &[1], &[0]);
+
+    let mut builder2 = <SaturatingBatch as Batch<UnsignedWrapper<u64>, u64, usize, SaturatingI64>>::Builder::new();
+    builder2.push((1.into(), 1, 1, sat(i64::max_value())));
+    builder2.push((2.into(), 2, 1, sat(-5)));
+    let batch2 = builder2.done(&[1], &[2], &[0]);
+
+    // advancing times makes the updates of each key coincide, and they accumulate without overflow.
+    let merged = merge_batches(&batch1, &batch2, Some(&[2]));
+    assert_eq!(merged.cursor().to_vec(&merged), vec![((1.into(), 1), vec![(2, sat(i64::max_value()))])]);
+}
+
+#[test]
+fn saturating_consolidate_collection() {
+
+    let data = timely::example(|scope| {
+        vec![
+            (1u64, Default::default(), sat(i64::max_value())),
+            (1u64, Default::default(), sat(1)),
+            (2u64, Default::default(), sat(4)),
+            (2u64, Default::default(), sat(-4)),
+        ]
+        .into_iter()
+        .to_stream(scope)
+        .as_collection()
+        .consolidate()
+        .inner
+        .capture()
+    });
+
+    let results = data.extract().into_iter().flat_map(|(_, x)| x).collect::<Vec<_>>();
+    assert_eq!(results, vec![(1u64, Default::default(), sat(i64::max_value()))]);
+}
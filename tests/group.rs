@@ -1,11 +1,24 @@
 extern crate timely;
+extern crate timely_communication;
 extern crate differential_dataflow;
 
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
 use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
 use timely::dataflow::operators::{ToStream, Capture, Map};
 use timely::dataflow::operators::capture::Extract;
 use differential_dataflow::AsCollection;
-use differential_dataflow::operators::{Group, Count};
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Group, Count, Threshold, TopK};
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::group::GroupArranged;
+use differential_dataflow::trace::{Trace, TraceReader};
+use differential_dataflow::trace::cursor::CursorDebug;
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+use differential_dataflow::trace::implementations::ord_col::OrdColSpine;
 
 #[test]
 fn group() {
@@ -43,4 +56,341 @@ fn group_scaling() {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 1);
-}
\ No newline at end of file
+}
+#[test]
+fn distinct_signed() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<u64, isize>();
+            collection.distinct_signed()
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        if worker.index() == 0 {
+            input.update(5, 2);
+            // an exactly zero accumulation produces nothing.
+            input.update(6, 1);
+            input.update(6, -1);
+            input.advance_to(1);
+            // crosses from positive to negative.
+            input.update(5, -3);
+            input.advance_to(2);
+            input.update(5, 1);
+            input.advance_to(3);
+            input.update(5, -1);
+            input.advance_to(4);
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(key, time, diff)| ((time.inner, key), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to the output may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, u64), isize)> = Vec::new();
+    for (key, diff) in results {
+        if consolidated.last().map(|x| x.0 == key) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((key, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![
+        ((0, 5), 1),
+        ((1, 5), -2),
+        ((2, 5), 1),
+        ((3, 5), -1),
+    ]);
+}
+
+#[test]
+fn group_arranged_columnar_output() {
+
+    let (send1, recv1) = ::std::sync::mpsc::channel();
+    let send1 = Arc::new(Mutex::new(send1));
+    let (send2, recv2) = ::std::sync::mpsc::channel();
+    let send2 = Mutex::new(send2);
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let (mut input, probe, mut trace) = worker.dataflow::<usize, _, _>(|scope| {
+            let send1 = send1.lock().unwrap().clone();
+            let (input, data) = scope.new_collection::<(u32, isize), isize>();
+
+            // sum the values of each key, weighted by their counts, into a columnar trace.
+            let summed = data.arrange_by_key()
+                             .group_arranged(|_k, s, t| t.push((s.iter().map(|&(v, w)| *v * w).sum::<isize>(), 1)), OrdColSpine::new());
+
+            let output = summed.as_collection(|k, v| (*k, *v));
+            output.inner.capture_into(send1);
+            (input, output.probe(), summed.trace.clone())
+        });
+
+        input.insert((0, 1));
+        input.insert((0, 2));
+        input.update((1, 3), 2);
+        input.advance_to(1);
+        input.flush();
+        worker.step_while(|| probe.less_than(input.time()));
+
+        // read the reduced collection back from the columnar trace.
+        let (mut cursor, storage) = trace.cursor();
+        let contents = cursor.to_vec(&storage).into_iter().map(|(kv, _)| kv).collect::<Vec<_>>();
+        send2.lock().unwrap().send(contents).unwrap();
+
+    }).unwrap();
+
+    let extracted = recv1.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0, 3), RootTimestamp::new(0), 1), ((1, 6), RootTimestamp::new(0), 1)]);
+    assert_eq!(recv2.recv().unwrap(), vec![(0, 3), (1, 6)]);
+}
+
+#[test]
+fn group_cancellation_emits_nothing() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<(u64, u64), isize>();
+            collection.group(|_key, s, t| t.push((s.iter().map(|&(v, w)| *v * (w as u64)).sum::<u64>(), 1)))
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        input.insert((0, 1));
+        input.insert((0, 2));
+        input.insert((1, 5));
+        input.advance_to(1);
+        // a value inserted and retracted leaves the input of key 0 unchanged.
+        input.update((0, 3), 1);
+        input.update((0, 3), -1);
+        input.advance_to(2);
+        // a value inserted and later retracted changes the output only while present.
+        input.insert((1, 7));
+        input.advance_to(3);
+        input.remove((1, 7));
+        input.advance_to(4);
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| ((time.inner, record), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to the output may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, (u64, u64)), isize)> = Vec::new();
+    for (record, diff) in results {
+        if consolidated.last().map(|x| x.0 == record) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((record, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![
+        ((0, (0, 3)), 1),
+        ((0, (1, 5)), 1),
+        ((2, (1, 5)), -1),
+        ((2, (1, 12)), 1),
+        ((3, (1, 5)), 1),
+        ((3, (1, 12)), -1),
+    ]);
+}
+
+#[test]
+fn topk_retracts_displaced_values() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<(u64, u64), isize>();
+            collection.topk(2, |&x| x)
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        input.insert((0, 1));
+        input.insert((0, 5));
+        input.insert((0, 3));
+        input.insert((1, 4));
+        input.advance_to(1);
+        // a larger value displaces the second largest.
+        input.insert((0, 7));
+        input.advance_to(2);
+        // a smaller value changes nothing.
+        input.insert((0, 2));
+        input.advance_to(3);
+        // removing the largest value restores the displaced value.
+        input.remove((0, 7));
+        input.advance_to(4);
+        // a repeated value occupies both places.
+        input.update((1, 9), 2);
+        input.advance_to(5);
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| ((time.inner, record), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to the output may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, (u64, u64)), isize)> = Vec::new();
+    for (record, diff) in results {
+        if consolidated.last().map(|x| x.0 == record) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((record, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![
+        ((0, (0, 3)), 1),
+        ((0, (0, 5)), 1),
+        ((0, (1, 4)), 1),
+        ((1, (0, 3)), -1),
+        ((1, (0, 7)), 1),
+        ((3, (0, 3)), 1),
+        ((3, (0, 7)), -1),
+        ((4, (1, 4)), -1),
+        ((4, (1, 9)), 2),
+    ]);
+}
+
+#[test]
+fn group_diagnostics_report_outputs() {
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let reports2 = reports.clone();
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let reports = reports2.clone();
+        let mut input = worker.dataflow(|scope| {
+            let (input, collection) = scope.new_collection::<(u64, u64), isize>();
+            collection.group_arranged_diagnosed(
+                |_key, s, t| t.push((s.iter().map(|&(v, w)| *v * (w as u64)).sum::<u64>(), 1isize)),
+                OrdValSpine::new(),
+                Some(Box::new(move |key: &u64, time: &Product<RootTimestamp, usize>, input: &[(&u64, isize)], prior: &[(u64, isize)], output: &[(u64, isize)]| {
+                    let input = input.iter().map(|&(v, w)| (*v, w)).collect::<Vec<_>>();
+                    reports.lock().unwrap().push((*key, time.inner, input, prior.to_vec(), output.to_vec()));
+                })),
+            );
+            input
+        });
+
+        input.insert((0, 1));
+        input.insert((0, 2));
+        input.advance_to(1);
+        input.insert((0, 4));
+        input.advance_to(2);
+        // the key's input accumulates to nothing, and its output is retracted.
+        input.remove((0, 1));
+        input.remove((0, 2));
+        input.remove((0, 4));
+        input.advance_to(3);
+
+    }).unwrap();
+
+    let mut reports = reports.lock().unwrap().clone();
+    reports.sort();
+    assert_eq!(reports, vec![
+        (0, 0, vec![(1, 1), (2, 1)], vec![], vec![(3, 1)]),
+        (0, 1, vec![(1, 1), (2, 1), (4, 1)], vec![(3, 1)], vec![(7, 1)]),
+        (0, 2, vec![], vec![(7, 1)], vec![]),
+    ]);
+}
+
+#[test]
+fn group_reusing_matches_group() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<(u64, u64), isize>();
+            let plain = collection.group(|_key, s, t| t.push((s.iter().map(|&(v, w)| *v * (w as u64)).sum::<u64>(), 1)));
+            let reusing = collection.group_arranged_reusing(
+                                        |_key, s, t| t.push((s.iter().map(|&(v, w)| *v * (w as u64)).sum::<u64>(), 1isize)),
+                                        OrdValSpine::new()
+                                    )
+                                    .as_collection(|k, v| (*k, *v));
+            // any difference between the two outputs would remain after cancellation.
+            plain.negate()
+                 .concat(&reusing)
+                 .inner
+                 .capture_into(send);
+            input
+        });
+
+        input.insert((0, 1));
+        input.insert((0, 2));
+        input.insert((1, 5));
+        input.advance_to(1);
+        input.update((0, 3), 1);
+        input.update((0, 3), -1);
+        input.insert((1, 7));
+        input.advance_to(2);
+        input.remove((1, 7));
+        input.insert((0, 4));
+        input.advance_to(3);
+        input.remove((0, 4));
+        input.insert((1, 7));
+        input.advance_to(4);
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| ((time.inner, record), diff)))
+        .collect::<Vec<_>>();
+
+    results.sort();
+    let mut consolidated: Vec<((usize, (u64, u64)), isize)> = Vec::new();
+    for (record, diff) in results {
+        if consolidated.last().map(|x| x.0 == record) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((record, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![]);
+}
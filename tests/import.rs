@@ -2,15 +2,20 @@ extern crate timely;
 extern crate itertools;
 extern crate differential_dataflow;
 
+use std::sync::{Arc, Mutex};
+
 use timely::dataflow::operators::*;
 use timely::dataflow::operators::capture::Extract;
 use timely::progress::timestamp::RootTimestamp;
 use timely::progress::nested::product::Product;
 use differential_dataflow::collection::AsCollection;
-use differential_dataflow::operators::arrange::{ArrangeByKey, Arrange};
+use differential_dataflow::input::Input as CollectionInput;
+use differential_dataflow::operators::join::JoinCore;
+use differential_dataflow::operators::arrange::{ArrangeByKey, Arrange, TraceProbe};
 use differential_dataflow::operators::group::GroupArranged;
 use differential_dataflow::trace::implementations::ord::OrdValSpine;
 use differential_dataflow::trace::{Trace, TraceReader};
+use differential_dataflow::trace::cursor::CursorDebug;
 // use differential_dataflow::hashable::{OrdWrapper, UnsignedWrapper};
 use itertools::Itertools;
 
@@ -158,6 +163,82 @@ fn test_import_completed_dataflow() {
     ]);
 }
 
+#[test]
+fn test_import_join() {
+
+    let (send1, recv1) = ::std::sync::mpsc::channel();
+    let send1 = Arc::new(Mutex::new(send1));
+    let (send2, recv2) = ::std::sync::mpsc::channel();
+    let send2 = Arc::new(Mutex::new(send2));
+
+    timely::execute(timely::Configuration::Process(2), move |worker| {
+
+        // the first dataflow arranges `shared`, and joins it with `other1`.
+        let (mut shared, mut other1, mut trace) = worker.dataflow(|scope| {
+            let send1 = send1.lock().unwrap().clone();
+            let (shared_input, shared) = scope.new_collection::<(u64, u64), isize>();
+            let (other_input, other) = scope.new_collection::<(u64, u64), isize>();
+            let arranged = shared.arrange_by_key();
+            arranged.join_core(&other.arrange_by_key(), |k, v1, v2| Some((*k, *v1, *v2)))
+                    .inner
+                    .capture_into(send1);
+            (shared_input, other_input, arranged.trace.clone())
+        });
+
+        // the second dataflow imports the arrangement of `shared`, rather than building its own.
+        let mut other2 = worker.dataflow(move |scope| {
+            let send2 = send2.lock().unwrap().clone();
+            let (other_input, other) = scope.new_collection::<(u64, u64), isize>();
+            let imported = trace.import(scope);
+            ::std::mem::drop(trace);
+            imported.join_core(&other.arrange_by_key(), |k, v1, v2| Some((*k, *v1, *v2)))
+                    .inner
+                    .capture_into(send2);
+            other_input
+        });
+
+        if worker.index() == 0 {
+            let rounds: Vec<(Vec<((u64, u64), isize)>, Vec<((u64, u64), isize)>)> = vec![
+                (vec![((0, 0), 1), ((1, 1), 1), ((1, 2), 1)], vec![((0, 10), 1), ((1, 11), 1)]),
+                (vec![((1, 1), -1), ((2, 3), 1)], vec![((2, 12), 1)]),
+                (vec![((0, 4), 1)], vec![((0, 10), -1), ((1, 13), 1)]),
+            ];
+            for (round, (shared_updates, other_updates)) in rounds.into_iter().enumerate() {
+                for (data, diff) in shared_updates { shared.update(data, diff); }
+                for &(data, diff) in other_updates.iter() {
+                    other1.update(data, diff);
+                    other2.update(data, diff);
+                }
+                shared.advance_to(round + 1);
+                other1.advance_to(round + 1);
+                other2.advance_to(round + 1);
+            }
+        }
+
+    }).unwrap();
+
+    let consolidate = |mut results: Vec<((u64, u64, u64), usize, isize)>| {
+        results.sort();
+        let mut consolidated: Vec<((u64, u64, u64), usize, isize)> = Vec::new();
+        for (data, time, diff) in results {
+            if consolidated.last().map(|x| x.0 == data && x.1 == time) == Some(true) {
+                consolidated.last_mut().unwrap().2 += diff;
+            }
+            else {
+                consolidated.push((data, time, diff));
+            }
+        }
+        consolidated.retain(|x| x.2 != 0);
+        consolidated
+    };
+
+    let results1 = consolidate(recv1.extract().into_iter().flat_map(|(_, data)| data).map(|(d, t, r)| (d, t.inner, r)).collect());
+    let results2 = consolidate(recv2.extract().into_iter().flat_map(|(_, data)| data).map(|(d, t, r)| (d, t.inner, r)).collect());
+
+    assert!(!results1.is_empty());
+    assert_eq!(results1, results2);
+}
+
 #[ignore]
 #[test]
 fn import_skewed() {
@@ -202,3 +283,71 @@ fn import_skewed() {
         (RootTimestamp::new(4), vec![((0, 1), 1)]),
     ]);
 }
+
+#[test]
+fn test_arrange_lazy() {
+    timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut input, mut trace, probe) = worker.dataflow::<usize, _, _>(|scope| {
+            let (input, data) = scope.new_collection();
+            let arranged = data.arrange_lazy(OrdValSpine::new());
+            (input, arranged.trace.clone(), arranged.stream.probe())
+        });
+
+        input.insert((1u64, 1i64));
+        input.insert((2, 2));
+        input.advance_to(1);
+        input.flush();
+        worker.step_while(|| probe.less_than(input.time()));
+
+        // without a reader the updates are discarded, but the arrangement's frontier still advances.
+        assert!(!probe.less_than(&RootTimestamp::new(1)));
+        assert_eq!(trace.num_updates_hint(), Some(0));
+
+        // reading the trace causes subsequent updates to be arranged.
+        let (mut cursor, storage) = trace.cursor();
+        assert!(cursor.to_vec(&storage).is_empty());
+
+        input.insert((3, 3));
+        input.advance_to(2);
+        input.flush();
+        worker.step_while(|| probe.less_than(input.time()));
+
+        let (mut cursor, storage) = trace.cursor();
+        assert_eq!(cursor.to_vec(&storage), vec![((3, 3), vec![(RootTimestamp::new(1), 1)])]);
+
+    }).unwrap();
+}
+
+#[test]
+fn test_probe_with_sink() {
+    timely::execute(timely::Configuration::Thread, |worker| {
+
+        let probe = TraceProbe::new();
+        let mut input = worker.dataflow::<usize, _, _>(|scope| {
+            let (input, data) = scope.new_collection();
+            data.arrange_by_key().probe_with_sink(&probe);
+            input
+        });
+
+        assert_eq!(probe.upper(), vec![RootTimestamp::new(0)]);
+
+        for round in 0 .. 2 {
+            for x in 0 .. 100u64 {
+                input.insert((100 * round + x, x));
+            }
+            input.advance_to(round as usize + 1);
+            input.flush();
+            worker.step_while(|| probe.upper() != vec![input.time().clone()]);
+
+            // the probe reports every update in the trace, and at least the memory of their keys and values.
+            let updates = 100 * (round as usize + 1);
+            assert_eq!(probe.num_updates(), Some(updates));
+            assert!(probe.estimated_bytes().unwrap() >= updates * 2 * ::std::mem::size_of::<u64>());
+        }
+
+        input.close();
+        worker.step_while(|| !probe.upper().is_empty());
+
+    }).unwrap();
+}
@@ -0,0 +1,85 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::Collection;
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::*;
+
+// Extends reachable nodes along edges by one step.
+fn step<G: Scope>(reached: &Collection<G, usize>, edges: &Collection<G, (usize, usize)>, roots: &Collection<G, usize>) -> Collection<G, usize>
+where G::Timestamp: Lattice+Ord {
+    reached.map(|x| (x, ()))
+           .join_map(edges, |_src, &(), &dst| dst)
+           .concat(roots)
+           .distinct()
+}
+
+// Nodes reachable from node 0 along the path 0 -> 1 -> .. -> 9, stopping after `limit` rounds if supplied.
+fn reachable(limit: Option<u64>) -> Vec<usize> {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let (mut roots, mut edges) = worker.dataflow(|scope| {
+
+            let send = send.lock().unwrap().clone();
+
+            let (root_input, roots) = scope.new_collection();
+            let (edge_input, edges) = scope.new_collection();
+
+            let reached = match limit {
+                Some(limit) => roots.iterate_limited(limit, |inner| {
+                    step(inner, &edges.enter(&inner.scope()), &roots.enter(&inner.scope()))
+                }),
+                None => roots.iterate(|inner| {
+                    step(inner, &edges.enter(&inner.scope()), &roots.enter(&inner.scope()))
+                }),
+            };
+
+            reached.inner.capture_into(send);
+
+            (root_input, edge_input)
+        });
+
+        roots.update(0usize, 1);
+        for node in 0 .. 9usize {
+            edges.update((node, node + 1), 1);
+        }
+        roots.advance_to(1);
+        edges.advance_to(1);
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter())
+        .map(|(node, _time, diff)| { assert_eq!(diff, 1); node })
+        .collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+#[test]
+fn iterate_converges() {
+    assert_eq!(reachable(None), (0 .. 10).collect::<Vec<_>>());
+}
+
+#[test]
+fn iterate_limited_stops() {
+    // each round extends the path by one node, so three rounds reach nodes `0 ..= 3`.
+    assert_eq!(reachable(Some(3)), (0 .. 4).collect::<Vec<_>>());
+    // a limit beyond convergence has no effect.
+    assert_eq!(reachable(Some(100)), (0 .. 10).collect::<Vec<_>>());
+}
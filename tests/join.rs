@@ -1,10 +1,19 @@
 extern crate timely;
+extern crate timely_communication;
 extern crate differential_dataflow;
 
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
 use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
 use timely::dataflow::operators::{ToStream, Capture, Map};
 use timely::dataflow::operators::capture::Extract;
 use differential_dataflow::AsCollection;
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::join::{half_join, lookup};
 use differential_dataflow::operators::{Consolidate, Join, Count};
 
 #[test]
@@ -48,6 +57,28 @@ fn join_map() {
     assert_eq!(extracted[0].1, vec![((0,'a'), Default::default(),1), ((3,'B'), Default::default(),1)]);
 }
 
+#[test]
+fn join_map_matches_join() {
+
+    let data = timely::example(|scope| {
+        let col1 = (0 .. 20u64).map(|x| ((x % 5, x), Default::default(), 1isize)).to_stream(scope).as_collection();
+        let col2 = (0 .. 12u64).map(|x| ((x % 4, 100 * x), Default::default(), 2isize)).to_stream(scope).as_collection();
+
+        // the key is available to the output closure by reference, and need not be recovered from a tuple.
+        let mapped = col1.join_map(&col2, |k, v1, v2| (*k, v1 + v2));
+        let tupled = col1.join(&col2).map(|(k, v1, v2)| (k, v1 + v2));
+
+        (mapped.consolidate().inner.capture(), tupled.consolidate().inner.capture())
+    });
+
+    let (mapped, tupled) = data;
+    let mapped = mapped.extract();
+    assert_eq!(mapped, tupled.extract());
+    // keys `0 .. 4` each match four values with three values.
+    assert_eq!(mapped.iter().map(|x| x.1.len()).sum::<usize>(), 4 * 4 * 3);
+    assert!(mapped.iter().flat_map(|x| x.1.iter()).all(|&((k, v), _, r)| r == 2 && k < 4 && (v % 100) % 5 == k));
+}
+
 #[test]
 fn semijoin() {
     let data = timely::example(|scope| {
@@ -63,6 +94,21 @@ fn semijoin() {
     assert_eq!(extracted[0].1, vec![((0,0), Default::default(),1)]);
 }
 
+#[test]
+fn semijoin_ignores_multiplicity() {
+    let data = timely::example(|scope| {
+        let col1 = vec![((0,0), Default::default(),1),((1,2), Default::default(),1)].into_iter().to_stream(scope).as_collection();
+        let col2 = vec![(0, Default::default(),2)].into_iter().to_stream(scope).as_collection();
+
+        // should retain record `(0,0)` once, though its key has count two, and discard `(1,2)`.
+        col1.semijoin(&col2).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,0), Default::default(),1)]);
+}
+
 #[test]
 fn antijoin() {
     let data = timely::example(|scope| {
@@ -77,6 +123,233 @@ fn antijoin() {
     assert_eq!(extracted[0].1, vec![((1,2), Default::default(),1)]);
 }
 
+#[test]
+fn antijoin_incremental() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let (mut left, mut right) = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (left_input, left) = scope.new_collection();
+            let (right_input, right) = scope.new_collection();
+            left.antijoin(&right)
+                .inner
+                .capture_into(send);
+            (left_input, right_input)
+        });
+
+        if worker.index() == 0 {
+            left.insert((0u64, 10u64));
+            left.insert((1u64, 11u64));
+            left.insert((2u64, 12u64));
+            right.insert(0u64);
+            right.insert(0u64);
+            left.advance_to(1); right.advance_to(1);
+            right.insert(1u64);
+            left.advance_to(2); right.advance_to(2);
+            // key 0 remains present, with a smaller count.
+            right.remove(0u64);
+            left.advance_to(3); right.advance_to(3);
+            right.remove(0u64);
+            right.remove(1u64);
+            left.advance_to(4); right.advance_to(4);
+            // key 1 flickers back into `right`.
+            right.insert(1u64);
+            left.advance_to(5); right.advance_to(5);
+        }
+
+    }).unwrap();
+
+    assert_eq!(consolidated(recv.extract()), vec![
+        ((0, (1, 11)), 1),
+        ((0, (2, 12)), 1),
+        ((1, (1, 11)), -1),
+        ((3, (0, 10)), 1),
+        ((3, (1, 11)), 1),
+        ((4, (1, 11)), -1),
+    ]);
+}
+
+#[test]
+fn semijoin_incremental() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let (mut left, mut right) = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (left_input, left) = scope.new_collection();
+            let (right_input, right) = scope.new_collection();
+            left.semijoin(&right)
+                .inner
+                .capture_into(send);
+            (left_input, right_input)
+        });
+
+        if worker.index() == 0 {
+            left.insert((0u64, 10u64));
+            left.insert((1u64, 11u64));
+            left.insert((2u64, 12u64));
+            // key 0 is present with count two, and must not duplicate `(0, 10)`.
+            right.insert(0u64);
+            right.insert(0u64);
+            right.insert(1u64);
+            left.advance_to(1); right.advance_to(1);
+            right.remove(0u64);
+            right.remove(1u64);
+            left.advance_to(2); right.advance_to(2);
+            right.remove(0u64);
+            right.insert(2u64);
+            right.insert(2u64);
+            left.advance_to(3); right.advance_to(3);
+        }
+
+    }).unwrap();
+
+    assert_eq!(consolidated(recv.extract()), vec![
+        ((0, (0, 10)), 1),
+        ((0, (1, 11)), 1),
+        ((1, (1, 11)), -1),
+        ((2, (0, 10)), -1),
+        ((2, (2, 12)), 1),
+    ]);
+}
+
+#[test]
+fn half_join_matches_join() {
+
+    let (send1, recv1) = ::std::sync::mpsc::channel();
+    let send1 = Arc::new(Mutex::new(send1));
+    let (send2, recv2) = ::std::sync::mpsc::channel();
+    let send2 = Arc::new(Mutex::new(send2));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let (mut stream, mut arranged) = worker.dataflow(|scope| {
+            let send1 = send1.lock().unwrap().clone();
+            let send2 = send2.lock().unwrap().clone();
+            let (stream_input, stream) = scope.new_collection::<(u64, u64), isize>();
+            let (arranged_input, arranged) = scope.new_collection::<(u64, u64), isize>();
+            half_join(&stream, &arranged.arrange_by_key(), |time| time.clone(), |k, v1, v2| (*k, *v1, *v2))
+                .inner
+                .capture_into(send1);
+            stream.join(&arranged)
+                  .inner
+                  .capture_into(send2);
+            (stream_input, arranged_input)
+        });
+
+        // every update to `arranged` precedes or coincides with the updates to `stream`, so that
+        // the full join produces no output from updates to `arranged` alone.
+        if worker.index() == 0 {
+            arranged.insert((0u64, 100u64));
+            arranged.insert((1u64, 101u64));
+            arranged.insert((1u64, 102u64));
+            stream.advance_to(1); arranged.advance_to(1);
+            arranged.remove((1u64, 101u64));
+            arranged.insert((2u64, 103u64));
+            stream.insert((0u64, 10u64));
+            stream.insert((1u64, 11u64));
+            stream.advance_to(2); arranged.advance_to(2);
+            stream.remove((0u64, 10u64));
+            stream.insert((1u64, 13u64));
+            stream.insert((2u64, 12u64));
+            stream.advance_to(3); arranged.advance_to(3);
+        }
+
+    }).unwrap();
+
+    let half = consolidated(recv1.extract());
+    assert_eq!(half, vec![
+        ((1, (0, 10, 100)), 1),
+        ((1, (1, 11, 102)), 1),
+        ((2, (0, 10, 100)), -1),
+        ((2, (1, 13, 102)), 1),
+        ((2, (2, 12, 103)), 1),
+    ]);
+    assert_eq!(half, consolidated(recv2.extract()));
+}
+
+// Accumulates captured updates by time and record, discarding those that cancel.
+#[test]
+fn half_join_stream_ahead_of_arrangement() {
+
+    let (send1, recv1) = ::std::sync::mpsc::channel();
+    let send1 = Arc::new(Mutex::new(send1));
+    let (send2, recv2) = ::std::sync::mpsc::channel();
+    let send2 = Arc::new(Mutex::new(send2));
+
+    timely::execute(Configuration::Thread, move |worker| {
+
+        let (mut stream, mut arranged) = worker.dataflow(|scope| {
+            let send1 = send1.lock().unwrap().clone();
+            let send2 = send2.lock().unwrap().clone();
+            let (stream_input, stream) = scope.new_collection::<(u64, u64), isize>();
+            let (arranged_input, arranged) = scope.new_collection::<(u64, u64), isize>();
+            half_join(&stream, &arranged.arrange_by_key(), |time| time.clone(), |k, v1, v2| (*k, *v1, *v2))
+                .inner
+                .capture_into(send1);
+            stream.join(&arranged)
+                  .inner
+                  .capture_into(send2);
+            (stream_input, arranged_input)
+        });
+
+        arranged.insert((0u64, 100u64));
+        arranged.advance_to(1);
+        stream.advance_to(1);
+        stream.insert((0u64, 10u64));
+
+        // the stream runs ahead, while its update at time 1 waits for the arrangement.
+        stream.advance_to(10);
+        for _ in 0 .. 10 { worker.step(); }
+
+        // the arrangement's batches now merge, and must not be compacted past the waiting lookup.
+        arranged.insert((0u64, 101u64));
+        arranged.advance_to(2);
+        for _ in 0 .. 10 { worker.step(); }
+        arranged.insert((0u64, 102u64));
+        arranged.advance_to(3);
+
+    }).unwrap();
+
+    let half = consolidated(recv1.extract());
+    assert_eq!(half, vec![
+        ((1, (0, 10, 100)), 1),
+        ((1, (0, 10, 101)), 1),
+    ]);
+    assert_eq!(consolidated(recv2.extract()), vec![
+        ((1, (0, 10, 100)), 1),
+        ((1, (0, 10, 101)), 1),
+        ((2, (0, 10, 102)), 1),
+    ]);
+}
+
+fn consolidated<D: Ord>(extracted: Vec<(Product<RootTimestamp, usize>, Vec<(D, Product<RootTimestamp, usize>, isize)>)>) -> Vec<((usize, D), isize)> {
+    let mut results = extracted
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(data, time, diff)| ((time.inner, data), diff)))
+        .collect::<Vec<_>>();
+
+    results.sort();
+    let mut consolidated: Vec<((usize, D), isize)> = Vec::new();
+    for (key, diff) in results {
+        if consolidated.last().map(|x| x.0 == key) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((key, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+    consolidated
+}
+
 #[test] fn join_scale_1() { join_scaling(1); }
 #[test] fn join_scale_10() { join_scaling(10); }
 #[test] fn join_scale_100() { join_scaling(100); }
@@ -102,4 +375,56 @@ fn join_scaling(scale: u64) {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 0);
-}
\ No newline at end of file
+}
+#[test]
+fn lookup_present_and_absent_keys() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let (mut keys, mut data) = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (keys_input, keys) = scope.new_collection::<u64, isize>();
+            let (data_input, data) = scope.new_collection::<(u64, u64), isize>();
+            lookup(&keys, &data.arrange_by_key())
+                .inner
+                .capture_into(send);
+            (keys_input, data_input)
+        });
+
+        if worker.index() == 0 {
+            data.insert((1, 10));
+            data.insert((1, 11));
+            data.update((2, 20), 3);
+            // key 4 is in the arrangement, but is never queried.
+            data.insert((4, 40));
+            // key 1 is queried twice, and key 3 is absent from the arrangement.
+            keys.update(1, 2);
+            keys.insert(2);
+            keys.insert(3);
+            data.advance_to(1);
+            keys.advance_to(1);
+            // values changed after the queries do not affect their results.
+            data.remove((1, 10));
+            keys.insert(2);
+            data.advance_to(2);
+            keys.advance_to(2);
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(record, time, diff)| ((time.inner, record), diff)))
+        .collect::<Vec<_>>();
+    results.sort();
+
+    assert_eq!(results, vec![
+        ((0, (1, 10)), 2),
+        ((0, (1, 11)), 2),
+        ((0, (2, 20)), 3),
+        ((1, (2, 20)), 3),
+    ]);
+}
@@ -0,0 +1,126 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Group, ReduceMonotone};
+
+#[test]
+fn reduce_monotone_retracts_max() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection();
+            collection.reduce_monotone(|x: &u64, y: &u64| ::std::cmp::max(*x, *y))
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        if worker.index() == 0 {
+            input.insert((0u64, 1u64));
+            input.insert((0, 5));
+            input.insert((0, 3));
+            input.insert((1, 2));
+            input.advance_to(1);
+            input.remove((0, 5));
+            input.advance_to(2);
+            input.insert((0, 7));
+            input.remove((0, 1));
+            input.insert((1, 4));
+            input.advance_to(3);
+            input.insert((0, 7));
+            input.advance_to(4);
+            input.remove((0, 7));
+            input.advance_to(5);
+            input.remove((0, 7));
+            input.remove((0, 3));
+            input.advance_to(6);
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(data, time, diff)| ((time.inner, data), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to a result may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, (u64, u64)), isize)> = Vec::new();
+    for (key, diff) in results {
+        if consolidated.last().map(|x| x.0 == key) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((key, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    // retracting the maximum reveals the next largest value, and retracting a copy of it changes nothing.
+    assert_eq!(consolidated, vec![
+        ((0, (0, 5)), 1),
+        ((0, (1, 2)), 1),
+        ((1, (0, 3)), 1),
+        ((1, (0, 5)), -1),
+        ((2, (0, 3)), -1),
+        ((2, (0, 7)), 1),
+        ((2, (1, 2)), -1),
+        ((2, (1, 4)), 1),
+        ((5, (0, 7)), -1),
+    ]);
+}
+
+#[test]
+fn reduce_monotone_matches_group() {
+
+    timely::execute(Configuration::Thread, |worker| {
+
+        let (mut input, probe) = worker.dataflow::<usize, _, _>(|scope| {
+            let (input, collection) = scope.new_collection();
+            let minimum = collection.reduce_monotone(|x: &u64, y: &u64| ::std::cmp::min(*x, *y));
+            let grouped = collection.group(|_key, input, output| {
+                if let Some(min) = input.iter().filter(|x| x.1 > 0).map(|x| *x.0).min() {
+                    output.push((min, 1));
+                }
+            });
+            minimum.assert_eq(&grouped);
+            (input, minimum.probe())
+        });
+
+        // insert and remove pseudo-random values, removing only values previously inserted.
+        let mut present = Vec::new();
+        let mut state = 1u64;
+        for round in 0 .. 50 {
+            for _ in 0 .. 20 {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let record = ((state >> 33) % 5, (state >> 40) % 20);
+                if (state >> 20) % 3 == 0 && !present.is_empty() {
+                    let index = (state >> 24) as usize % present.len();
+                    input.remove(present.swap_remove(index));
+                }
+                else {
+                    input.insert(record);
+                    present.push(record);
+                }
+            }
+            input.advance_to(round + 1);
+            input.flush();
+            worker.step_while(|| probe.less_than(input.time()));
+        }
+
+    }).unwrap();
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "rayon")]
+
+extern crate differential_dataflow;
+
+use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::trace::{Batch, BatchReader, Builder};
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::parallel::{merge_parallel, PARALLEL_THRESHOLD};
+use differential_dataflow::trace::implementations::spine_fueled::merge_batches;
+
+type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+// Builds a batch with `keys` keys, each with a few values and times, from a simple pseudo-random sequence.
+fn build(keys: u64, seed: u64, lower: usize, upper: usize) -> IntegerBatch {
+    let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    let mut state = seed;
+    for key in 0 .. keys {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        // skip some keys, so that the batches' keys only partially overlap.
+        if state % 5 == 0 { continue; }
+        for val in 0 .. (state % 3) + 1 {
+            for time in lower .. upper {
+                let diff = if (state >> 32) % 2 == 0 { 1 } else { -1 };
+                builder.push((key.into(), val, time, diff));
+            }
+        }
+    }
+    builder.done(&[lower], &[upper], &[lower])
+}
+
+#[test]
+fn parallel_merge_matches_sequential() {
+    let batch1 = build(40_000, 1, 0, 2);
+    let batch2 = build(40_000, 2, 2, 4);
+    assert!(batch1.len() + batch2.len() >= PARALLEL_THRESHOLD);
+
+    for compaction in vec![None, Some(vec![2]), Some(vec![4])] {
+        let sequential = merge_batches(&batch1, &batch2, compaction.as_ref().map(|x| &x[..]));
+        let parallel = merge_parallel(&batch1, &batch2, compaction.as_ref().map(|x| &x[..]));
+        assert_eq!(parallel.layer, sequential.layer);
+        assert_eq!((parallel.lower(), parallel.upper(), parallel.since()), (sequential.lower(), sequential.upper(), sequential.since()));
+    }
+}
+
+#[test]
+fn parallel_merge_small_and_skewed() {
+    // small batches are merged sequentially, and a small batch merges with a large one.
+    let small = build(10, 3, 0, 2);
+    let large = build(40_000, 4, 2, 4);
+    let empty = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[4], &[6]);
+
+    assert_eq!(merge_parallel(&small, &build(10, 5, 2, 4), None).layer, merge_batches(&small, &build(10, 5, 2, 4), None).layer);
+    assert_eq!(merge_parallel(&small, &large, Some(&[4])).layer, merge_batches(&small, &large, Some(&[4])).layer);
+    assert_eq!(merge_parallel(&large, &empty, None).layer, merge_batches(&large, &empty, None).layer);
+}
@@ -0,0 +1,65 @@
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+
+use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::trace::{Trace, TraceReader, Batch, Batcher};
+use differential_dataflow::trace::cursor::CursorDebug;
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::retain::RetainSpine;
+
+type IntegerBatch = Rc<OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>>;
+type IntegerTrace = RetainSpine<UnsignedWrapper<u64>, u64, usize, i64, IntegerBatch>;
+
+#[test]
+fn test_retain() {
+    // retain the four most recent times.
+    let mut trace = IntegerTrace::with_retention(|t| t.saturating_sub(4));
+    trace.distinguish_since(&[]);
+
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+    for i in 0 .. 8 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    let batches = trace.num_batches();
+    assert!(batches > 0);
+
+    // times in advance of the retention frontier accumulate as before.
+    for frontier in 1 .. 9 {
+        trace.advance_by(&[frontier]);
+        assert_eq!(trace.retention_frontier(), vec![frontier.saturating_sub(4)]);
+        let (mut cursor, storage) = trace.cursor();
+        let updates = cursor.to_vec(&storage);
+        for i in frontier.saturating_sub(4) .. 8 {
+            let key = ((i as u64).into(), i as u64);
+            let total = updates.iter().filter(|x| x.0 == key).flat_map(|x| x.1.iter().map(|y| y.1)).sum::<i64>();
+            assert_eq!(total, 1);
+        }
+    }
+
+    // with all times below the retention frontier, all batches are physically dropped.
+    trace.advance_by(&[12]);
+    assert_eq!(trace.num_batches(), 0);
+    assert_eq!(trace.len(), 0);
+    assert!(trace.is_empty());
+    let (mut cursor, storage) = trace.cursor();
+    assert!(cursor.to_vec(&storage).is_empty());
+}
+
+#[test]
+fn test_retain_default() {
+    // the default trace retains all batches.
+    let mut trace = IntegerTrace::new();
+    trace.distinguish_since(&[]);
+
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+    for i in 0 .. 8 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    trace.advance_by(&[8]);
+    assert!(trace.num_batches() > 0);
+    assert_eq!(trace.len(), 8);
+    assert!(!trace.is_empty());
+}
@@ -0,0 +1,37 @@
+extern crate abomonation;
+extern crate differential_dataflow;
+
+use abomonation::{encode, decode};
+
+use differential_dataflow::hashable::UnsignedWrapper;
+
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::{Batch, BatchReader, Builder};
+use differential_dataflow::trace::cursor::CursorDebug;
+
+type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+#[test]
+fn test_batch_round_trip() {
+    let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in 0 .. 10u64 {
+        for val in 0 .. key {
+            for time in 0 .. 3usize {
+                builder.push((key.into(), val, time, (key + val) as i64 - time as i64));
+            }
+        }
+    }
+    let batch = builder.done(&[0], &[3], &[0]);
+
+    let mut bytes = Vec::new();
+    unsafe { encode(&batch, &mut bytes).unwrap(); }
+
+    let (decoded, remaining) = unsafe { decode::<IntegerBatch>(&mut bytes[..]) }.unwrap();
+    assert_eq!(remaining.len(), 0);
+
+    assert_eq!(decoded.layer, batch.layer);
+    assert_eq!(decoded.lower(), batch.lower());
+    assert_eq!(decoded.upper(), batch.upper());
+    assert_eq!(decoded.description().since(), batch.description().since());
+    assert_eq!(decoded.cursor().to_vec(decoded), batch.cursor().to_vec(&batch));
+}
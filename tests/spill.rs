@@ -0,0 +1,161 @@
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+
+use differential_dataflow::hashable::UnsignedWrapper;
+
+use differential_dataflow::trace::implementations::ord::OrdValBatch;
+use differential_dataflow::trace::implementations::spill::{SpillSpine, BatchSerializer};
+use differential_dataflow::trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Builder};
+use differential_dataflow::trace::cursor::CursorDebug;
+
+type IntegerBatch = Rc<OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>>;
+type IntegerTrace = SpillSpine<UnsignedWrapper<u64>, u64, usize, i64, IntegerBatch, IntegerSerializer>;
+
+/// Writes batches as a sequence of little-endian `u64` values.
+#[derive(Default)]
+struct IntegerSerializer;
+
+fn push(bytes: &mut Vec<u8>, value: u64) {
+    for i in 0 .. 8 { bytes.push((value >> (8 * i)) as u8); }
+}
+
+fn pull(bytes: &mut &[u8]) -> u64 {
+    let mut value = 0;
+    for i in 0 .. 8 { value |= (bytes[i] as u64) << (8 * i); }
+    *bytes = &bytes[8..];
+    value
+}
+
+fn push_frontier(bytes: &mut Vec<u8>, frontier: &[usize]) {
+    push(bytes, frontier.len() as u64);
+    for time in frontier { push(bytes, *time as u64); }
+}
+
+fn pull_frontier(bytes: &mut &[u8]) -> Vec<usize> {
+    let len = pull(bytes);
+    (0 .. len).map(|_| pull(bytes) as usize).collect()
+}
+
+impl BatchSerializer<IntegerBatch> for IntegerSerializer {
+    fn serialize(&mut self, batch: &IntegerBatch, bytes: &mut Vec<u8>) {
+        push_frontier(bytes, batch.lower());
+        push_frontier(bytes, batch.upper());
+        push_frontier(bytes, batch.description().since());
+        let updates = batch.cursor().to_vec(batch);
+        for ((key, val), times) in updates {
+            for (time, diff) in times {
+                push(bytes, *key);
+                push(bytes, val);
+                push(bytes, time as u64);
+                push(bytes, diff as u64);
+            }
+        }
+    }
+    fn deserialize(&mut self, mut bytes: &[u8]) -> IntegerBatch {
+        let lower = pull_frontier(&mut bytes);
+        let upper = pull_frontier(&mut bytes);
+        let since = pull_frontier(&mut bytes);
+        let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+        while bytes.len() > 0 {
+            let key = pull(&mut bytes);
+            let val = pull(&mut bytes);
+            let time = pull(&mut bytes) as usize;
+            let diff = pull(&mut bytes) as i64;
+            builder.push((key.into(), val, time, diff));
+        }
+        builder.done(&lower[..], &upper[..], &since[..])
+    }
+}
+
+#[test]
+fn test_spill() {
+    let directory = ::std::env::temp_dir().join(format!("differential-test-spill-{}", ::std::process::id()));
+    let mut trace = IntegerTrace::new_in(directory.clone(), IntegerSerializer, 1);
+    trace.distinguish_since(&[]);
+
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 32 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+
+        // spilled batches must be reloaded and presented along with resident batches.
+        let (mut cursor, storage) = trace.cursor();
+        assert_eq!(cursor.to_vec(&storage), expected);
+    }
+
+    assert!(trace.num_spilled() > 0);
+    assert_eq!(::std::fs::read_dir(&directory).unwrap().count(), trace.num_spilled());
+
+    // dropping the contents of the trace deletes the files.
+    trace.advance_by(&[]);
+    assert_eq!(trace.num_spilled(), 0);
+    assert_eq!(::std::fs::read_dir(&directory).unwrap().count(), 0);
+
+    // the trace created the directory, and removes it when dropped.
+    drop(trace);
+    assert!(!directory.exists());
+}
+
+#[test]
+fn test_spill_shares_reloaded_batches() {
+    let directory = ::std::env::temp_dir().join(format!("differential-test-spill-shared-{}", ::std::process::id()));
+    let mut trace = IntegerTrace::new_in(directory.clone(), IntegerSerializer, 1);
+    trace.distinguish_since(&[]);
+
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 8 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+    }
+    assert!(trace.num_spilled() > 0);
+
+    // while some cursor's storage holds the reloaded batches, their files are not read again.
+    let (_, held) = trace.cursor();
+    for entry in ::std::fs::read_dir(&directory).unwrap() {
+        ::std::fs::remove_file(entry.unwrap().path()).unwrap();
+    }
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), expected);
+
+    let mut count = 0;
+    trace.map_batches(|batch| count += batch.len());
+    assert_eq!(count, expected.len());
+
+    drop(held);
+    drop(storage);
+    drop(trace);
+    assert!(!directory.exists());
+}
+
+#[test]
+fn test_spill_write_failure() {
+    let directory = ::std::env::temp_dir().join(format!("differential-test-spill-failure-{}", ::std::process::id()));
+    let mut trace = IntegerTrace::new_in(directory.clone(), IntegerSerializer, 1);
+    trace.distinguish_since(&[]);
+
+    // without its directory, no spill file can be created.
+    ::std::fs::remove_dir(&directory).unwrap();
+
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 32 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+
+        // batches that could not be spilled are retained in memory, in order.
+        let (mut cursor, storage) = trace.cursor();
+        assert_eq!(cursor.to_vec(&storage), expected);
+    }
+
+    assert_eq!(trace.num_spilled(), 0);
+    assert!(!directory.exists());
+}
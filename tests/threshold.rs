@@ -0,0 +1,75 @@
+extern crate timely;
+extern crate timely_communication;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+
+use timely_communication::Configuration;
+
+use timely::dataflow::operators::Capture;
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::ThresholdTotal;
+
+#[test]
+fn threshold_total_at_least_two() {
+
+    let (send, recv) = ::std::sync::mpsc::channel();
+    let send = Arc::new(Mutex::new(send));
+
+    timely::execute(Configuration::Process(2), move |worker| {
+
+        let mut input = worker.dataflow(|scope| {
+            let send = send.lock().unwrap().clone();
+            let (input, collection) = scope.new_collection::<u64, isize>();
+            collection.threshold_total(|_k, c| if c >= 2 { 1 } else { 0 })
+                      .inner
+                      .capture_into(send);
+            input
+        });
+
+        if worker.index() == 0 {
+            input.insert(1);
+            input.insert(2);
+            input.insert(2);
+            input.advance_to(1);
+            input.insert(1);
+            input.remove(2);
+            input.remove(2);
+            input.advance_to(2);
+            // a count beyond the threshold changes nothing.
+            input.insert(1);
+            input.advance_to(3);
+            input.remove(1);
+            input.remove(1);
+            input.advance_to(4);
+        }
+
+    }).unwrap();
+
+    let mut results = recv.extract()
+        .into_iter()
+        .flat_map(|(_, list)| list.into_iter().map(|(key, time, diff)| ((time.inner, key), diff)))
+        .collect::<Vec<_>>();
+
+    // consolidate, as changes to the output may be reported in several messages.
+    results.sort();
+    let mut consolidated: Vec<((usize, u64), isize)> = Vec::new();
+    for (key, diff) in results {
+        if consolidated.last().map(|x| x.0 == key) == Some(true) {
+            consolidated.last_mut().unwrap().1 += diff;
+        }
+        else {
+            consolidated.push((key, diff));
+        }
+    }
+    consolidated.retain(|x| x.1 != 0);
+
+    assert_eq!(consolidated, vec![
+        ((0, 2), 1),
+        ((1, 1), 1),
+        ((1, 2), -1),
+        ((3, 1), -1),
+    ]);
+}
@@ -1,13 +1,26 @@
+extern crate rand;
 extern crate differential_dataflow;
 
 use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+
+use rand::{Rng, SeedableRng, StdRng};
 
 use differential_dataflow::hashable::UnsignedWrapper;
 
-use differential_dataflow::trace::implementations::ord::OrdValBatch;
-use differential_dataflow::trace::{Trace, TraceReader, Batch, Batcher};
-use differential_dataflow::trace::cursor::CursorDebug;
-use differential_dataflow::trace::implementations::spine_fueled::Spine;
+use differential_dataflow::trace::implementations::ord::{OrdValBatch, OrdKeyBatch};
+use differential_dataflow::trace::implementations::ord_col::{OrdColBatch, OrdColSpine};
+use differential_dataflow::trace::implementations::rle::RleDiffBatch;
+use differential_dataflow::trace::implementations::dict::{DictBatch, DictSpine};
+use differential_dataflow::trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Builder, Merger, InsertError, CursorError, CompactionError};
+use differential_dataflow::trace::rc_blanket_impls::Shared;
+use differential_dataflow::trace::cursor::{Cursor, CursorDebug, ReverseCursor};
+use differential_dataflow::trace::cursor::cursor_filter::CursorFilterTime;
+use differential_dataflow::trace::cursor::cursor_iter;
+use differential_dataflow::trace::cursor::cursor_join::join_cursors;
+use differential_dataflow::trace::implementations::spine_fueled::{Spine, MergeEvent, CompactStats, MergePolicy, RetainError, BatchKind, BatchLayout, SpineSnapshot, merge_batches};
+use differential_dataflow::trace::implementations::kway::begin_merge_many;
 
 pub type OrdValSpine<K, V, T, R> = Spine<K, V, T, R, Rc<OrdValBatch<K, V, T, R>>>;
 
@@ -16,9 +29,7 @@ type IntegerTrace = OrdValSpine<UnsignedWrapper<u64>, u64, usize, i64>;
 fn get_trace() -> Spine<UnsignedWrapper<u64>, u64, usize, i64, Rc<OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>>> {
     let mut trace = IntegerTrace::new();
     {
-        let mut batcher = <<
-            IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
-            UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        let mut batcher = new_batcher();
 
         batcher.push_batch(&mut vec![
             ((1.into(), 2), 0, 1),
@@ -35,6 +46,10 @@ fn get_trace() -> Spine<UnsignedWrapper<u64>, u64, usize, i64, Rc<OrdValBatch<Un
     trace
 }
 
+fn new_batcher() -> <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher {
+    <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new()
+}
+
 #[test]
 fn test_trace() {
     let mut trace = get_trace();
@@ -84,3 +99,2083 @@ fn test_advance() {
         cursor2.to_vec(&storage2),
         vec![((1.into(), 2), vec![(2, 1)]), ((2.into(), 3), vec![(2, 1), (2, -1)])]);
 }
+
+#[test]
+fn test_progressive_merge() {
+    let mut trace = IntegerTrace::with_effort(1);
+    trace.distinguish_since(&[]);
+
+    let mut batcher = new_batcher();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 64 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+
+        // merges may be in progress, but the cursor must reflect all inserted updates.
+        let (mut cursor, storage) = trace.cursor();
+        assert_eq!(cursor.to_vec(&storage), expected);
+    }
+}
+
+#[test]
+fn test_bounded_merge_work() {
+    let effort = 1;
+    let mut trace = IntegerTrace::with_effort(effort);
+    trace.distinguish_since(&[]);
+
+    let mut batcher = new_batcher();
+
+    // work done by completed merges, and by merges in progress.
+    let work = |trace: &IntegerTrace| trace.compact_stats().fuel + trace.merge_progress().0;
+
+    for i in 0 .. 4096u64 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        let before = work(&trace);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        let spent = work(&trace) - before;
+
+        // each singleton batch supplies `ceil(ratio) * effort` fuel to each of the at most `log2(len) + 2` slots.
+        let slots = (64 - (i + 1).leading_zeros()) as usize + 2;
+        assert!(spent <= 2 * effort * slots, "insert {} performed {} work", i, spent);
+    }
+
+    assert!(trace.compact_stats().merges > 0);
+    assert_eq!(trace.len(), 4096);
+}
+
+#[test]
+fn test_len() {
+    assert!(IntegerTrace::new().is_empty());
+
+    let mut trace = get_trace();
+
+    // nothing has been distinguished, so all three batches are pending.
+    assert_eq!(trace.len(), 3);
+    assert!(!trace.is_empty());
+    assert_eq!(trace.num_batches(), 3);
+
+    // releasing the batches collapses them into one batch.
+    trace.distinguish_since(&[3]);
+    assert_eq!(trace.len(), 3);
+    assert_eq!(trace.num_batches(), 1);
+}
+
+#[test]
+fn test_cursor_through_straddle() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    batcher.push_batch(&mut vec![((1.into(), 2), 0, 1), ((2.into(), 3), 4, 1)]);
+    trace.insert(batcher.seal(&[5]));
+
+    // the pending batch covers [0, 5), so neither cut is clean.
+    assert!(trace.cursor_through(&[3]).is_none());
+    assert!(trace.cursor_through(&[5]).is_some());
+}
+
+#[test]
+fn test_coalesce_pending() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 16 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1), ((0.into(), 0), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+    }
+    assert_eq!(trace.num_batches(), 16);
+
+    expected[0].1 = (0 .. 16).map(|i| (i, if i == 0 { 2 } else { 1 })).collect();
+
+    // releasing all pending batches at once produces a single batch.
+    trace.distinguish_since(&[16]);
+    assert_eq!(trace.num_batches(), 1);
+    assert_eq!(trace.len(), 31);
+
+    let (mut cursor, storage) = trace.cursor_through(&[16]).unwrap();
+    assert_eq!(cursor.to_vec(&storage), expected);
+
+    // the same batches released one at a time are merged pairwise, which reads most updates several times.
+    let mut cascade = IntegerTrace::new();
+    cascade.distinguish_since(&[]);
+    let mut batcher = new_batcher();
+    for i in 0 .. 16 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1), ((0.into(), 0), i as usize, 1)]);
+        cascade.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    let coalesced = trace.compact_stats();
+    let cascaded = cascade.compact_stats().fuel + cascade.merge_progress().0;
+    assert_eq!(coalesced.merges, 1);
+    assert_eq!(coalesced.fuel, 31);
+    assert!(coalesced.fuel < cascaded, "coalesced: {}, cascaded: {}", coalesced.fuel, cascaded);
+}
+
+#[test]
+fn test_coalesce_since() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    for i in 0 .. 16 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    // the coalesced batch has its times advanced, and describes itself as observable only from there.
+    trace.advance_by(&[20]);
+    trace.distinguish_since(&[16]);
+    assert_eq!(trace.num_batches(), 1);
+
+    let mut sinces = Vec::new();
+    trace.map_batches(|batch| sinces.push(batch.description().since().to_vec()));
+    assert_eq!(sinces, vec![vec![20]]);
+
+    let (mut cursor, storage) = trace.cursor();
+    assert!(cursor.to_vec(&storage).iter().all(|x| x.1 == vec![(20, 1)]));
+}
+
+#[test]
+fn test_merge_logger() {
+    let mut trace = IntegerTrace::new();
+    trace.distinguish_since(&[]);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let events2 = events.clone();
+    trace.set_merge_logger(move |event: MergeEvent| events2.borrow_mut().push(event));
+
+    let mut batcher = new_batcher();
+
+    for i in 0 .. 4 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    let lengths = events.borrow().iter().map(|e| (e.length1, e.length2, e.length)).collect::<Vec<_>>();
+    assert_eq!(lengths, vec![(1, 1, 2), (1, 1, 2), (2, 2, 4)]);
+    assert!(events.borrow().iter().all(|e| e.fuel > 0));
+}
+
+#[test]
+fn test_compact_stats() {
+    let mut trace = IntegerTrace::new();
+    trace.distinguish_since(&[]);
+    assert_eq!(trace.compact_stats(), CompactStats::default());
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let events2 = events.clone();
+    trace.set_merge_logger(move |event: MergeEvent| events2.borrow_mut().push(event));
+
+    let mut batcher = new_batcher();
+
+    for i in 0 .. 64 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1), (((i + 1).into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    let stats = trace.compact_stats();
+    assert!(stats.merges > 0);
+    assert!(stats.tuples >= 2 * stats.merges);
+    assert!(stats.fuel > 0);
+    // merges spend one unit of fuel per update they merge, and no fuel they were merely supplied.
+    assert_eq!(stats.fuel, stats.tuples);
+
+    // the counters agree with the events reported to the logger.
+    let events = events.borrow();
+    assert_eq!(stats.merges, events.len());
+    assert_eq!(stats.tuples, events.iter().map(|e| e.length1 + e.length2).sum::<usize>());
+    assert_eq!(stats.fuel, events.iter().map(|e| e.fuel).sum::<usize>());
+}
+
+#[test]
+fn test_compaction_getters() {
+    let mut trace = get_trace();
+    assert_eq!(trace.get_logical_compaction(), &[0]);
+    assert_eq!(trace.get_physical_compaction(), &[0]);
+
+    trace.advance_by(&[1]);
+    trace.distinguish_since(&[2]);
+    assert_eq!(trace.get_logical_compaction(), &[1]);
+    assert_eq!(trace.get_physical_compaction(), &[2]);
+
+    trace.advance_by(&[2]);
+    trace.distinguish_since(&[3]);
+    assert_eq!(trace.get_logical_compaction(), &[2]);
+    assert_eq!(trace.get_physical_compaction(), &[3]);
+    assert_eq!(trace.get_logical_compaction().to_vec(), trace.advance_frontier().to_vec());
+    assert_eq!(trace.get_physical_compaction().to_vec(), trace.distinguish_frontier().to_vec());
+}
+
+#[test]
+fn test_merge_ratio() {
+
+    let resident = |ratio| {
+        let mut trace = IntegerTrace::new();
+        trace.set_merge_ratio(ratio);
+        trace.distinguish_since(&[]);
+
+        let mut batcher = new_batcher();
+
+        batcher.push_batch(&mut vec![((1.into(), 1), 0, 1), ((2.into(), 2), 0, 1), ((3.into(), 3), 0, 1)]);
+        trace.insert(batcher.seal(&[1]));
+        batcher.push_batch(&mut vec![((4.into(), 4), 1, 1), ((5.into(), 5), 1, 1)]);
+        trace.insert(batcher.seal(&[2]));
+        trace.num_batches()
+    };
+
+    // the batches occupy distinct slots at ratio two, but contend for one slot at ratio four.
+    assert_eq!(resident(2.0), 2);
+    assert_eq!(resident(4.0), 1);
+}
+
+#[test]
+#[should_panic]
+fn test_merge_ratio_invalid() {
+    let mut trace = IntegerTrace::new();
+    trace.set_merge_ratio(1.0);
+}
+
+#[test]
+fn test_describe() {
+    let mut trace = get_trace();
+    trace.distinguish_since(&[2]);
+
+    let layout = trace.describe();
+    assert_eq!(layout.merging, vec![BatchLayout { len: 2, lower: vec![0], upper: vec![2] }]);
+    assert_eq!(layout.pending, vec![BatchLayout { len: 1, lower: vec![2], upper: vec![3] }]);
+    assert_eq!(layout.advance_frontier, vec![0]);
+    assert_eq!(layout.through_frontier, vec![2]);
+}
+
+#[test]
+fn test_distinguish_since_range() {
+
+    let mut batcher = new_batcher();
+
+    let mut batches = Vec::new();
+    for i in 0 .. 3 {
+        batcher.push_batch(&mut vec![((1.into(), 1), i, 1), ((5.into(), 5), i, 1)]);
+        batches.push(batcher.seal(&[i + 1]));
+    }
+
+    // batches released together are coalesced, advancing only the in-range key 1.
+    let mut trace = IntegerTrace::new();
+    trace.advance_by(&[3]);
+    for batch in batches.iter() { trace.insert(batch.clone()); }
+    trace.distinguish_since_range(&[3], &0.into(), &4.into());
+
+    let (mut cursor, storage) = trace.cursor_through(&[3]).unwrap();
+    assert_eq!(cursor.to_vec(&storage), vec![
+        ((1.into(), 1), vec![(3, 3)]),
+        ((5.into(), 5), vec![(0, 1), (1, 1), (2, 1)]),
+    ]);
+
+    // batches released one at a time are merged, again advancing only the in-range key 1.
+    let mut trace = IntegerTrace::new();
+    trace.advance_by(&[3]);
+    trace.distinguish_since_range(&[], &0.into(), &4.into());
+    for batch in batches[.. 2].iter() { trace.insert(batch.clone()); }
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![
+        ((1.into(), 1), vec![(3, 2)]),
+        ((5.into(), 5), vec![(0, 1), (1, 1)]),
+    ]);
+}
+
+#[test]
+fn test_insert_order() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    // the first batch is held back, and the second must queue behind it.
+    batcher.push_batch(&mut vec![((1.into(), 1), 0, 1)]);
+    trace.insert(batcher.seal(&[2]));
+    trace.distinguish_since(&[1]);
+    batcher.push_batch(&mut vec![((2.into(), 2), 2, 1)]);
+    trace.insert(batcher.seal(&[3]));
+    assert_eq!(trace.describe().pending.len(), 2);
+
+    // degenerate batches are ignored.
+    let empty = <<
+        IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new().done(&[3], &[3], &[3]);
+    trace.insert(empty);
+    assert_eq!(trace.num_batches(), 2);
+
+    // releasing both batches, subsequent batches are merged immediately.
+    trace.distinguish_since(&[]);
+    batcher.push_batch(&mut vec![((3.into(), 3), 3, 1)]);
+    trace.insert(batcher.seal(&[4]));
+    assert_eq!(trace.describe().pending.len(), 0);
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![
+        ((1.into(), 1), vec![(0, 1)]),
+        ((2.into(), 2), vec![(2, 1)]),
+        ((3.into(), 3), vec![(3, 1)]),
+    ]);
+}
+
+#[test]
+fn test_close() {
+    let mut trace = get_trace();
+    assert!(!trace.is_closed());
+
+    trace.close();
+    assert!(trace.is_closed());
+    assert_eq!(trace.num_batches(), 4);
+
+    // closing again has no effect.
+    trace.close();
+    assert!(trace.is_closed());
+    assert_eq!(trace.num_batches(), 4);
+}
+
+#[test]
+#[should_panic(expected = "trace is closed")]
+fn test_insert_after_close() {
+    let mut trace = get_trace();
+    trace.close();
+
+    let batch = <<
+        IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new().done(&[3], &[4], &[3]);
+    trace.insert(batch);
+}
+
+#[test]
+fn test_insert_checked_after_close() {
+    let mut trace = get_trace();
+    trace.close();
+
+    let batch = <<
+        IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new().done(&[3], &[4], &[3]);
+    assert_eq!(trace.insert_batch_checked(batch), Err(InsertError::Discontinuity { expected: vec![], found: vec![3] }));
+    assert!(trace.is_closed());
+}
+
+#[test]
+fn test_updates() {
+    let mut trace = get_trace();
+    assert_eq!(trace.updates(), vec![
+        (1.into(), 2, 0, 1),
+        (2.into(), 3, 1, 1),
+        (2.into(), 3, 2, -1),
+    ]);
+
+    // times are advanced by the logical frontier, and updates consolidated.
+    trace.advance_by(&[2]);
+    assert_eq!(trace.updates(), vec![(1.into(), 2, 2, 1)]);
+
+    trace.advance_by(&[]);
+    assert_eq!(trace.updates(), vec![]);
+}
+
+#[test]
+fn test_seek_key() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    // scatter even keys across several pending batches.
+    for i in 0 .. 4 {
+        batcher.push_batch(&mut (0 .. 8).map(|j| (((8 * j + 2 * i).into(), j), i as usize, 1)).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    assert_eq!(trace.num_batches(), 4);
+
+    let (mut cursor, storage) = trace.cursor_through(&[4]).unwrap();
+
+    cursor.seek_key(&storage, &12.into());
+    assert_eq!(cursor.key(&storage), &12.into());
+    assert_eq!(cursor.val(&storage), &1);
+
+    // seeking to an absent key lands on the next present key.
+    cursor.seek_key(&storage, &13.into());
+    assert_eq!(cursor.key(&storage), &14.into());
+
+    // seeking backwards does not move the cursor.
+    cursor.seek_key(&storage, &0.into());
+    assert_eq!(cursor.key(&storage), &14.into());
+
+    cursor.seek_key(&storage, &100.into());
+    assert!(!cursor.key_valid(&storage));
+}
+
+#[test]
+fn test_reverse_cursor() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    // interleave keys across several pending batches, with a shared key in each.
+    for i in 0 .. 3 {
+        batcher.push_batch(&mut vec![(((i + 1).into(), i), i as usize, 1), ((4.into(), i), i as usize, 1), (((3 * i + 5).into(), 0), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    let (mut cursor, storage) = trace.cursor_through(&[3]).unwrap();
+
+    let mut backward = Vec::new();
+    cursor.rewind_keys_back(&storage);
+    while cursor.key_valid(&storage) {
+        while cursor.val_valid(&storage) {
+            let mut times = Vec::new();
+            cursor.map_times(&storage, |t, r| times.push((t.clone(), r)));
+            backward.push(((cursor.key(&storage).clone(), cursor.val(&storage).clone()), times));
+            cursor.step_val(&storage);
+        }
+        cursor.step_key_back(&storage);
+    }
+
+    // keys are descending, but values remain ascending within each key.
+    assert_eq!(backward, vec![
+        ((11.into(), 0), vec![(2, 1)]),
+        ((8.into(), 0), vec![(1, 1)]),
+        ((5.into(), 0), vec![(0, 1)]),
+        ((4.into(), 0), vec![(0, 1)]),
+        ((4.into(), 1), vec![(1, 1)]),
+        ((4.into(), 2), vec![(2, 1)]),
+        ((3.into(), 2), vec![(2, 1)]),
+        ((2.into(), 1), vec![(1, 1)]),
+        ((1.into(), 0), vec![(0, 1)]),
+    ]);
+}
+
+#[test]
+fn test_cursor_filter_time() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    batcher.push_batch(&mut vec![
+        ((1.into(), 1), 0, 1),
+        ((1.into(), 2), 10, 1),
+        ((2.into(), 2), 10, 1),
+        ((3.into(), 3), 5, 1),
+        ((3.into(), 3), 10, -1),
+    ]);
+    trace.insert(batcher.seal(&[6]));
+    trace.insert(batcher.seal(&[11]));
+
+    let (cursor, storage) = trace.cursor_through(&[11]).unwrap();
+    let mut cursor = CursorFilterTime::new(cursor, &storage, 5);
+
+    // key 2 and value (1, 2) have no updates at times less or equal to five.
+    assert_eq!(cursor.to_vec(&storage), vec![
+        ((1.into(), 1), vec![(0, 1)]),
+        ((3.into(), 3), vec![(5, 1)]),
+    ]);
+
+    cursor.rewind_keys(&storage);
+    cursor.seek_key(&storage, &2.into());
+    assert_eq!(cursor.key(&storage), &3.into());
+}
+
+#[test]
+fn test_empty_batch() {
+    type IntegerBatch = Rc<OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>>;
+    let batch = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[2], &[5]);
+    assert_eq!(batch.len(), 0);
+    assert_eq!(batch.lower(), &[2]);
+    assert_eq!(batch.upper(), &[5]);
+    assert_eq!(batch.cursor().to_vec(&batch), vec![]);
+}
+
+#[test]
+fn test_columnar_merge() {
+    type ColumnarBatch = OrdColBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut builder1 = <ColumnarBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder1.push((1.into(), 1, 0, 1));
+    builder1.push((1.into(), 2, 1, 1));
+    builder1.push((3.into(), 3, 0, 2));
+    let batch1 = builder1.done(&[0], &[2], &[0]);
+
+    let mut builder2 = <ColumnarBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder2.push((1.into(), 2, 2, -1));
+    builder2.push((2.into(), 2, 3, 1));
+    builder2.push((3.into(), 3, 2, 1));
+    let batch2 = builder2.done(&[2], &[4], &[0]);
+
+    // a merge without compaction retains all times.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &None, &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.len(), 6);
+    assert_eq!(merged.lower(), &[0]);
+    assert_eq!(merged.upper(), &[4]);
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), 1), vec![(0, 1)]),
+        ((1.into(), 2), vec![(1, 1), (2, -1)]),
+        ((2.into(), 2), vec![(3, 1)]),
+        ((3.into(), 3), vec![(0, 2), (2, 1)]),
+    ]);
+
+    // a merge with compaction consolidates advanced times, and drops cancelled updates.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &Some(vec![3]), &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), 1), vec![(3, 1)]),
+        ((2.into(), 2), vec![(3, 1)]),
+        ((3.into(), 3), vec![(3, 3)]),
+    ]);
+}
+
+#[test]
+fn test_columnar_spine() {
+    type ColumnarTrace = OrdColSpine<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut trace = ColumnarTrace::new();
+    let mut batcher = <<ColumnarTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+
+    let mut expected = Vec::new();
+    for i in 0 .. 16 {
+        batcher.push_batch(&mut vec![((i.into(), i), i as usize, 1)]);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        expected.push(((i.into(), i), vec![(i as usize, 1)]));
+    }
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), expected);
+}
+
+#[test]
+fn test_rle_diffs() {
+    type RleBatch = RleDiffBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // all differences equal one, and should be stored as a single run.
+    let mut builder = <RleBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    let mut expected = Vec::new();
+    for i in 0 .. 1000u64 {
+        builder.push(((i / 10).into(), i % 10, 0, 1));
+    }
+    for i in 0 .. 1000u64 {
+        expected.push((((i / 10).into(), i % 10), vec![(0, 1)]));
+    }
+    let batch = builder.done(&[0], &[1], &[0]);
+    assert_eq!(batch.len(), 1000);
+    assert_eq!(batch.layer.vals.vals.runs.len(), 1);
+
+    let encoded = batch.layer.vals.vals.runs.len() * ::std::mem::size_of::<(i64, usize)>();
+    let plain = batch.len() * ::std::mem::size_of::<i64>();
+    assert!(encoded * 100 < plain);
+
+    assert_eq!(batch.cursor().to_vec(&batch), expected);
+}
+
+#[test]
+fn test_rle_merge() {
+    type RleBatch = RleDiffBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut builder1 = <RleBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder1.push((1.into(), 1, 0, 1));
+    builder1.push((1.into(), 2, 0, 1));
+    builder1.push((1.into(), 2, 1, 1));
+    builder1.push((2.into(), 1, 0, 3));
+    builder1.push((3.into(), 3, 1, 1));
+    let batch1 = builder1.done(&[0], &[2], &[0]);
+
+    let mut builder2 = <RleBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder2.push((1.into(), 2, 2, -1));
+    builder2.push((2.into(), 1, 2, 1));
+    builder2.push((3.into(), 3, 2, 1));
+    builder2.push((4.into(), 4, 3, 1));
+    let batch2 = builder2.done(&[2], &[4], &[0]);
+
+    // merging decodes and re-encodes, and must reproduce the multiset of updates.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &None, &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.len(), 9);
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), 1), vec![(0, 1)]),
+        ((1.into(), 2), vec![(0, 1), (1, 1), (2, -1)]),
+        ((2.into(), 1), vec![(0, 3), (2, 1)]),
+        ((3.into(), 3), vec![(1, 1), (2, 1)]),
+        ((4.into(), 4), vec![(3, 1)]),
+    ]);
+    assert_eq!(merged.layer.vals.vals.runs, vec![(1, 3), (-1, 4), (3, 5), (1, 9)]);
+
+    // compaction consolidates advanced times, and re-encodes the resulting differences.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &Some(vec![3]), &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), 1), vec![(3, 1)]),
+        ((1.into(), 2), vec![(3, 1)]),
+        ((2.into(), 1), vec![(3, 4)]),
+        ((3.into(), 3), vec![(3, 2)]),
+        ((4.into(), 4), vec![(3, 1)]),
+    ]);
+    assert_eq!(merged.layer.vals.vals.runs, vec![(1, 2), (4, 3), (2, 4), (1, 5)]);
+}
+
+#[test]
+fn test_consolidating_builder() {
+    let mut builder = OrdValBatch::<UnsignedWrapper<u64>, u64, usize, i64>::consolidating_builder();
+    builder.push((1.into(), 1, 0, 1));
+    builder.push((1.into(), 1, 0, -1));
+    builder.push((1.into(), 1, 0, 1));
+    builder.push((2.into(), 2, 0, 1));
+    builder.push((2.into(), 2, 0, -1));
+    let batch = builder.done(&[0], &[1], &[0]);
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch.cursor().to_vec(&batch), vec![((1.into(), 1), vec![(0, 1)])]);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut trace = IntegerTrace::new();
+    trace.distinguish_since(&[10]);
+    let mut batcher = new_batcher();
+
+    for i in 0 .. 20 {
+        batcher.push_batch(&mut (0 .. 100).map(|j| ((j.into(), i), i as usize, 1)).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+
+    let snapshot: SpineSnapshot<usize, _> = trace.snapshot();
+    assert_eq!(snapshot.pending.len(), 10);
+    assert_eq!(snapshot.through_frontier, vec![10]);
+
+    let mut restored = IntegerTrace::restore(snapshot);
+    assert_eq!(restored.len(), trace.len());
+    assert_eq!(restored.get_physical_compaction(), &[10]);
+
+    let (mut cursor1, storage1) = trace.cursor();
+    let (mut cursor2, storage2) = restored.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+
+    // the restored trace continues from the upper frontier of the original.
+    batcher.push_batch(&mut vec![((0.into(), 20), 20, 1)]);
+    restored.insert(batcher.seal(&[21]));
+    restored.distinguish_since(&[21]);
+    let updates = restored.updates();
+    assert_eq!(updates.len(), 2001);
+    assert_eq!(updates[20], (0.into(), 20, 20, 1));
+}
+
+#[test]
+fn test_restore_leading_empty() {
+    let mut trace = IntegerTrace::new();
+    let mut batcher = new_batcher();
+
+    // the leading empty batch is pruned, so the oldest remaining batch starts at one.
+    trace.insert(batcher.seal(&[1]));
+    batcher.push_batch(&mut vec![((0.into(), 1), 1, 1)]);
+    trace.insert(batcher.seal(&[2]));
+
+    let snapshot: SpineSnapshot<usize, _> = trace.snapshot();
+    assert_eq!(snapshot.lower, vec![1]);
+    assert_eq!(snapshot.upper, vec![2]);
+
+    let mut restored = IntegerTrace::restore(snapshot);
+    assert_eq!(restored.upper(), &[2]);
+    batcher.push_batch(&mut vec![((0.into(), 2), 2, 1)]);
+    assert_eq!(restored.insert_batch_checked(batcher.seal(&[3])), Ok(()));
+    assert_eq!(restored.len(), 2);
+
+    // a trace whose batches were all pruned restores to its upper frontier.
+    let mut trace = IntegerTrace::new();
+    let mut batcher = new_batcher();
+    trace.insert(batcher.seal(&[1]));
+    trace.insert(batcher.seal(&[2]));
+    let mut restored = IntegerTrace::restore(trace.snapshot());
+    assert_eq!(restored.upper(), &[2]);
+    batcher.push_batch(&mut vec![((0.into(), 2), 2, 1)]);
+    assert_eq!(restored.insert_batch_checked(batcher.seal(&[3])), Ok(()));
+    assert_eq!(restored.len(), 1);
+}
+
+#[test]
+fn test_restore_new_from() {
+    let mut trace = IntegerTrace::new_from(vec![10]);
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((1.into(), 1, 10, 1));
+    trace.insert(builder.done(&[10], &[12], &[10]));
+
+    let snapshot: SpineSnapshot<usize, _> = trace.snapshot();
+    assert_eq!(snapshot.lower, vec![10]);
+
+    let mut restored = IntegerTrace::restore(snapshot);
+    assert_eq!(restored.upper(), &[12]);
+    assert_eq!(restored.advance_frontier(), &[10]);
+
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((2.into(), 2, 12, 1));
+    assert_eq!(restored.insert_batch_checked(builder.done(&[12], &[15], &[10])), Ok(()));
+
+    let (mut cursor, storage) = restored.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 1), vec![(10, 1)]), ((2.into(), 2), vec![(12, 1)])]);
+}
+
+#[test]
+fn test_merge_progress() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut builder1 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    let mut builder2 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for i in 0 .. 1000u64 {
+        builder1.push(((2 * i).into(), i, 0, 1));
+        builder2.push(((2 * i + 1).into(), i, 1, 1));
+    }
+    let batch1 = builder1.done(&[0], &[1], &[0]);
+    let batch2 = builder2.done(&[1], &[2], &[0]);
+
+    let mut merger = batch1.begin_merge(&batch2);
+    assert_eq!(merger.progress(), (0, 2000));
+
+    let mut previous = 0;
+    let mut rounds = 0;
+    loop {
+        let mut fuel = 100;
+        merger.work(&batch1, &batch2, &None, &mut fuel);
+        let (done, total) = merger.progress();
+        assert_eq!(total, 2000);
+        assert!(done >= previous);
+        assert!(done <= total);
+        previous = done;
+        rounds += 1;
+        if fuel > 0 { break; }
+    }
+    assert!(rounds > 5);
+    assert_eq!(merger.progress(), (2000, 2000));
+    assert_eq!(merger.done().len(), 2000);
+}
+
+#[test]
+fn test_advance_by_empty_aborts_merges() {
+    let mut trace = IntegerTrace::with_effort(1);
+    trace.distinguish_since(&[2]);
+    let mut batcher = new_batcher();
+
+    // two batches filling the same slot produce a merge whose output exhausts the fuel of the insertion,
+    // leaving the merge parked.
+    for round in 0 .. 2 {
+        batcher.push_batch(&mut (0 .. 128).map(|j| ((j.into(), round), round as usize, 1)).collect());
+        trace.insert(batcher.seal(&[round as usize + 1]));
+    }
+    assert_eq!(trace.merge_progress().1, 256);
+
+    trace.advance_by(&[]);
+    assert_eq!(trace.merge_progress(), (0, 0));
+    assert_eq!(trace.num_batches(), 0);
+    assert_eq!(trace.len(), 0);
+    assert_eq!(trace.updates(), vec![]);
+    drop(trace);
+}
+
+#[test]
+fn test_num_updates_hint() {
+    let mut trace = IntegerTrace::new();
+    assert_eq!(trace.num_updates_hint(), Some(0));
+
+    let mut batcher = new_batcher();
+
+    // each key is inserted, and then retracted.
+    for i in 0 .. 10 {
+        batcher.push_batch(&mut (0 .. 100).map(|j| ((j.into(), j), i as usize, if i % 2 == 0 { 1 } else { -1 })).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+        assert_eq!(trace.num_updates_hint(), Some(100 * (i + 1)));
+    }
+
+    // once times are advanced and batches merged, retractions cancel insertions.
+    trace.advance_by(&[10]);
+    trace.distinguish_since(&[10]);
+    assert_eq!(trace.num_updates_hint(), Some(0));
+}
+
+#[test]
+fn test_dict_batch() {
+    type DictTrace = DictSpine<UnsignedWrapper<u64>, String, usize, i64>;
+
+    let colors = ["red", "green", "blue"];
+
+    let mut trace = DictTrace::new();
+    let mut batcher = <<DictTrace as TraceReader<UnsignedWrapper<u64>, String, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, String, usize, i64>>::Batcher::new();
+
+    // 1000 rows over three distinct values, inserted in ten batches of which the last retracts some rows.
+    let mut expected = Vec::new();
+    for i in 0 .. 10u64 {
+        let mut updates = (100 * i .. 100 * (i + 1)).map(|j| ((j.into(), colors[(j % 3) as usize].to_string()), i as usize, 1)).collect::<Vec<_>>();
+        if i == 9 {
+            updates.extend((0 .. 10u64).map(|j| ((j.into(), colors[(j % 3) as usize].to_string()), 9, -1)));
+        }
+        batcher.push_batch(&mut updates);
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    for j in 10 .. 1000u64 {
+        expected.push((j.into(), colors[(j % 3) as usize].to_string(), 9, 1));
+    }
+    trace.advance_by(&[9]);
+    trace.distinguish_since(&[10]);
+
+    assert_eq!(trace.updates(), expected);
+
+    // seeking by value translates through the dictionary.
+    let (mut cursor, storage) = trace.cursor();
+    cursor.seek_key(&storage, &12.into());
+    cursor.seek_val(&storage, &"green".to_string());
+    assert_eq!(cursor.val(&storage), "red");
+
+    // the value column holds one small index per row, and each dictionary at most three strings.
+    let mut batches = Vec::new();
+    trace.map_batches(|batch| batches.push(batch.clone()));
+    let rows = batches.iter().map(|batch| batch.layer.vals.keys.len()).sum::<usize>();
+    let values = batches.iter().map(|batch| batch.dict.len()).max().unwrap();
+    assert_eq!(rows, trace.len());
+    assert_eq!(values, 3);
+    let encoded = rows * ::std::mem::size_of::<u32>() + values * ::std::mem::size_of::<String>();
+    assert!(2 * encoded < rows * ::std::mem::size_of::<String>());
+}
+
+#[test]
+fn test_dict_merge() {
+    type Dict = DictBatch<UnsignedWrapper<u64>, String, usize, i64>;
+
+    let mut builder1 = <Dict as Batch<UnsignedWrapper<u64>, String, usize, i64>>::Builder::new();
+    builder1.push((1.into(), "a".to_string(), 0, 1));
+    builder1.push((1.into(), "c".to_string(), 0, 1));
+    builder1.push((2.into(), "c".to_string(), 0, 1));
+    let batch1 = builder1.done(&[0], &[1], &[0]);
+
+    let mut builder2 = <Dict as Batch<UnsignedWrapper<u64>, String, usize, i64>>::Builder::new();
+    builder2.push((1.into(), "b".to_string(), 1, 1));
+    builder2.push((1.into(), "c".to_string(), 1, -1));
+    builder2.push((3.into(), "b".to_string(), 1, 1));
+    let batch2 = builder2.done(&[1], &[2], &[0]);
+
+    // indices of both batches are translated into the merged dictionary.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &Some(vec![1]), &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.dict, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), "a".to_string()), vec![(1, 1)]),
+        ((1.into(), "b".to_string()), vec![(1, 1)]),
+        ((2.into(), "c".to_string()), vec![(1, 1)]),
+        ((3.into(), "b".to_string()), vec![(1, 1)]),
+    ]);
+}
+
+#[test]
+fn test_merge_all() {
+    let mut trace = IntegerTrace::new();
+    let mut batcher = new_batcher();
+
+    for i in 0 .. 50 {
+        batcher.push_batch(&mut (0 .. 10 + 7 * i).map(|j| ((j.into(), i), i as usize, 1)).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    trace.distinguish_since(&[40]);
+    assert!(trace.num_batches() > 1);
+
+    let (mut cursor, storage) = trace.cursor();
+    let before = cursor.to_vec(&storage);
+
+    // batches in advance of the physical compaction frontier must remain distinct.
+    trace.merge_all();
+    assert_eq!(trace.num_batches(), 11);
+    assert!(trace.cursor_through(&[40]).is_some());
+
+    trace.distinguish_since(&[50]);
+    trace.merge_all();
+    assert!(trace.num_batches() <= 1);
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), before);
+}
+
+thread_local!(static COMPARISONS: Cell<usize> = Cell::new(0));
+
+// A key that counts how often it is compared.
+#[derive(Clone, Debug)]
+struct CountedKey(u64);
+
+impl PartialEq for CountedKey {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+impl Eq for CountedKey { }
+impl PartialOrd for CountedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for CountedKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn test_cursor_through_range() {
+    type CountedTrace = OrdValSpine<CountedKey, u64, usize, i64>;
+    let mut trace = CountedTrace::new();
+    let mut batcher = <<CountedTrace as TraceReader<CountedKey, u64, usize, i64>>::Batch as Batch<
+        CountedKey, u64, usize, i64>>::Batcher::new();
+
+    for i in 0 .. 4u64 {
+        batcher.push_batch(&mut (0 .. 1000).filter(|j| j % 4 != i).map(|j| ((CountedKey(j), i), i as usize, 1)).collect());
+        trace.insert(batcher.seal(&[i as usize + 1]));
+    }
+    assert!(trace.num_batches() > 1);
+
+    let lower = CountedKey(100);
+    let upper = CountedKey(110);
+
+    COMPARISONS.with(|c| c.set(0));
+    let (mut cursor, storage) = trace.cursor_through(&[4]).unwrap();
+    let full = cursor.to_vec(&storage);
+    let full_comparisons = COMPARISONS.with(|c| c.get());
+
+    COMPARISONS.with(|c| c.set(0));
+    let (mut cursor, storage) = trace.cursor_through_range(&[4], &lower, &upper).unwrap();
+    let range = cursor.to_vec(&storage);
+    let range_comparisons = COMPARISONS.with(|c| c.get());
+
+    let expected = full.into_iter().filter(|x| (x.0).0 .0 >= 100 && (x.0).0 .0 < 110).collect::<Vec<_>>();
+    assert_eq!(expected.len(), 30);
+    assert_eq!(range, expected);
+    assert!(range_comparisons * 10 < full_comparisons);
+
+    // rewinding returns to the lower bound, and seeking below it stays within the range.
+    cursor.rewind_keys(&storage);
+    assert_eq!(cursor.key(&storage).0, 100);
+    cursor.seek_key(&storage, &CountedKey(50));
+    assert_eq!(cursor.key(&storage).0, 100);
+}
+
+#[test]
+fn test_shared_batch_clone() {
+    type SharedBatch = Shared<OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>>;
+    let mut trace = Spine::<UnsignedWrapper<u64>, u64, usize, i64, SharedBatch>::new();
+    let mut batcher = <SharedBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+
+    batcher.push_batch(&mut (0 .. 100).map(|i| ((i.into(), i), 0, 1)).collect());
+    let batch = batcher.seal(&[1]);
+    trace.insert(batch.clone());
+    assert_eq!(Rc::strong_count(&batch), 2);
+
+    // cursor storage holds further references to the same batch, rather than copies of it.
+    let (_cursor, storage) = trace.cursor_through(&[1]).unwrap();
+    assert_eq!(storage.len(), 1);
+    assert!(Rc::ptr_eq(&storage[0], &batch));
+    assert_eq!(Rc::strong_count(&batch), 3);
+
+    drop(storage);
+    assert_eq!(Rc::strong_count(&batch), 2);
+}
+
+#[test]
+fn test_insert_batch_checked() {
+    let mut trace = get_trace();
+    let len = trace.len();
+
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((5.into(), 5, 5, 1));
+    let batch = builder.done(&[5], &[6], &[0]);
+
+    assert_eq!(trace.insert_batch_checked(batch), Err(InsertError::Discontinuity { expected: vec![3], found: vec![5] }));
+    assert_eq!(trace.len(), len);
+
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((5.into(), 5, 5, 1));
+    let batch = builder.done(&[3], &[6], &[0]);
+
+    assert_eq!(trace.insert_batch_checked(batch), Ok(()));
+    assert_eq!(trace.len(), len + 1);
+}
+
+#[test]
+#[should_panic(expected = "Discontinuity")]
+fn test_insert_discontinuity() {
+    let mut trace = get_trace();
+    trace.insert(<<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[5], &[6]));
+}
+
+#[test]
+fn test_batcher_unsorted_chunks() {
+    let mut batcher = new_batcher();
+
+    // chunks in descending order, each repeating and cancelling updates of the others.
+    for chunk in 0 .. 10u64 {
+        batcher.push_batch(&mut (0 .. 100u64).rev().map(|i| (((i % 10).into(), i % 3), (i % 2) as usize, if chunk % 5 == 4 { -1 } else { 1 })).collect());
+    }
+    let batch = batcher.seal(&[2]);
+
+    let mut expected = Vec::new();
+    for key in 0 .. 10u64 {
+        for val in 0 .. 3u64 {
+            let mut times = Vec::new();
+            for time in 0 .. 2usize {
+                let count = (0 .. 100u64).filter(|i| i % 10 == key && i % 3 == val && (i % 2) as usize == time).count() as i64;
+                if count != 0 { times.push((time, 6 * count)); }
+            }
+            if !times.is_empty() { expected.push(((key.into(), val), times)); }
+        }
+    }
+    assert_eq!(batch.cursor().to_vec(&batch), expected);
+    assert_eq!(batch.len(), expected.iter().map(|x| x.1.len()).sum::<usize>());
+
+    // updates that cancel entirely are not retained.
+    batcher.push_batch(&mut vec![((1.into(), 1), 2, 1), ((0.into(), 0), 2, 1)]);
+    batcher.push_batch(&mut vec![((1.into(), 1), 2, -1)]);
+    let batch = batcher.seal(&[3]);
+    assert_eq!(batch.cursor().to_vec(&batch), vec![((0.into(), 0), vec![(2, 1)])]);
+}
+
+#[test]
+fn test_compact_to() {
+    let mut trace = get_trace();
+    trace.compact_to(&[3]);
+
+    assert_eq!(trace.get_logical_compaction(), &[3]);
+    assert_eq!(trace.get_physical_compaction(), &[3]);
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 2), vec![(3, 1)])]);
+}
+
+#[test]
+#[should_panic(expected = "not in advance of")]
+fn test_compact_to_regression() {
+    let mut trace = get_trace();
+    trace.compact_to(&[3]);
+    trace.compact_to(&[2]);
+}
+
+#[test]
+fn test_try_cursor_through() {
+    let mut trace = IntegerTrace::new();
+
+    let mut batcher = new_batcher();
+
+    batcher.push_batch(&mut vec![((1.into(), 2), 0, 1), ((2.into(), 3), 4, 1)]);
+    trace.insert(batcher.seal(&[5]));
+    batcher.push_batch(&mut vec![((3.into(), 4), 6, 1)]);
+    trace.insert(batcher.seal(&[8]));
+    trace.distinguish_since(&[5]);
+
+    // a clean cut at or beyond the physical compaction frontier.
+    let (mut cursor, storage) = trace.try_cursor_through(&[5]).unwrap().unwrap();
+    assert_eq!(cursor.to_vec(&storage).len(), 2);
+
+    // a cut straddling the pending batch covering [5, 8).
+    assert!(trace.try_cursor_through(&[6]).unwrap().is_none());
+    assert!(trace.cursor_through(&[6]).is_none());
+
+    // a cut below the physical compaction frontier.
+    assert_eq!(trace.try_cursor_through(&[3]).err(), Some(CursorError::UpperTooLow { through_frontier: vec![5] }));
+    assert!(trace.cursor_through(&[3]).is_none());
+}
+
+#[test]
+fn test_extend() {
+    let mut batcher = new_batcher();
+    let mut batches = Vec::new();
+    for i in 0 .. 8u64 {
+        batcher.push_batch(&mut (0 .. 5).map(|j| ((j.into(), i), i as usize, 1)).collect());
+        batches.push(batcher.seal(&[i as usize + 1]));
+    }
+
+    let mut inserted = IntegerTrace::new();
+    inserted.distinguish_since(&[8]);
+    let inserted_events = Rc::new(RefCell::new(0));
+    let inserted_events2 = inserted_events.clone();
+    inserted.set_merge_logger(move |_| *inserted_events2.borrow_mut() += 1);
+    for batch in batches.iter().cloned() {
+        inserted.insert(batch);
+    }
+
+    let mut extended = IntegerTrace::new();
+    extended.distinguish_since(&[8]);
+    let extended_events = Rc::new(RefCell::new(0));
+    let extended_events2 = extended_events.clone();
+    extended.set_merge_logger(move |_| *extended_events2.borrow_mut() += 1);
+    extended.extend(batches);
+
+    // the small batches are coalesced on release in one merge, rather than merged as they are inserted.
+    assert!(*inserted_events.borrow() > 1);
+    assert_eq!(*extended_events.borrow(), 1);
+    assert_eq!(extended.num_batches(), 1);
+
+    let (mut cursor1, storage1) = inserted.cursor();
+    let (mut cursor2, storage2) = extended.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+}
+
+#[test]
+#[should_panic(expected = "Discontinuity")]
+fn test_extend_discontinuity() {
+    let mut trace = get_trace();
+    trace.extend(vec![
+        <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[3], &[4]),
+        <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[5], &[6]),
+    ]);
+}
+
+#[test]
+fn test_merge_policy_bounded_count() {
+    let mut batcher = new_batcher();
+
+    // batches of halving sizes each fit a vacant slot, so the default policy never merges them.
+    let mut batches = Vec::new();
+    for i in 0 .. 10 {
+        batcher.push_batch(&mut (0 .. (1024 >> i) as u64).map(|j| ((j.into(), i), i as usize, 1)).collect());
+        batches.push(batcher.seal(&[i as usize + 1]));
+    }
+
+    let mut geometric = IntegerTrace::new();
+    geometric.distinguish_since(&[]);
+    let mut bounded = IntegerTrace::new();
+    bounded.distinguish_since(&[]);
+    bounded.set_merge_policy(MergePolicy::BoundedCount(3));
+
+    for batch in batches {
+        geometric.insert(batch.clone());
+        bounded.insert(batch);
+        assert!(bounded.num_batches() <= 3);
+    }
+
+    assert!(geometric.num_batches() > 3);
+    assert_eq!(geometric.len(), bounded.len());
+    assert_eq!(geometric.updates(), bounded.updates());
+}
+
+#[test]
+fn test_batch_description() {
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((1.into(), 1, 3, 1));
+    let batch = builder.done(&[2], &[5], &[3]);
+
+    let description = batch.description();
+    assert_eq!(description.lower(), batch.lower());
+    assert_eq!(description.upper(), batch.upper());
+    assert_eq!(description.since(), batch.since());
+    assert_eq!((batch.lower(), batch.upper(), batch.since()), (&[2][..], &[5][..], &[3][..]));
+}
+
+#[test]
+fn test_retain_batches() {
+    let mut trace = IntegerTrace::new();
+    let mut batcher = new_batcher();
+
+    // two empty batches followed by two batches of updates, all pending.
+    trace.insert(batcher.seal(&[1]));
+    trace.insert(batcher.seal(&[2]));
+    batcher.push_batch(&mut vec![((1.into(), 1), 2, 1)]);
+    trace.insert(batcher.seal(&[3]));
+    batcher.push_batch(&mut vec![((2.into(), 2), 3, 1)]);
+    trace.insert(batcher.seal(&[4]));
+    let updates = trace.updates();
+
+    assert_eq!(trace.retain_batches(|batch| batch.len() > 0), Ok(()));
+    assert_eq!(trace.num_batches(), 2);
+    assert_eq!(trace.updates(), updates);
+    assert_eq!(trace.describe().pending.iter().map(|b| (b.lower.clone(), b.upper.clone())).collect::<Vec<_>>(), vec![(vec![2], vec![3]), (vec![3], vec![4])]);
+
+    // an empty batch between batches of updates may not be removed.
+    trace.insert(batcher.seal(&[5]));
+    batcher.push_batch(&mut vec![((3.into(), 3), 5, 1)]);
+    trace.insert(batcher.seal(&[6]));
+    assert_eq!(trace.retain_batches(|batch| batch.len() > 0), Err(RetainError::Discontinuity { expected: vec![4], found: vec![5] }));
+    assert_eq!(trace.num_batches(), 4);
+
+    // nor may the newest batch, whose upper frontier is that of the trace.
+    assert_eq!(trace.retain_batches(|batch| batch.upper() != &[6]), Err(RetainError::Discontinuity { expected: vec![6], found: vec![5] }));
+    assert_eq!(trace.num_batches(), 4);
+
+    // the trace continues to accept batches, and to provide cursors.
+    batcher.push_batch(&mut vec![((4.into(), 4), 6, 1)]);
+    trace.insert(batcher.seal(&[7]));
+    assert!(trace.cursor_through(&[7]).is_some());
+    assert_eq!(trace.len(), 4);
+}
+
+#[test]
+fn test_degenerate_inserts() {
+    let mut trace = get_trace();
+    assert_eq!(trace.degenerate_inserts(), 0);
+
+    trace.insert(<<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[3], &[3]));
+    assert_eq!(trace.degenerate_inserts(), 1);
+    assert_eq!(trace.num_batches(), 3);
+
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((5.into(), 5, 3, 1));
+    let batch = builder.done(&[3], &[3], &[0]);
+
+    assert_eq!(trace.insert_batch_checked(batch), Err(InsertError::NonEmptyDegenerate { bounds: vec![3], len: 1 }));
+    assert_eq!(trace.degenerate_inserts(), 1);
+    assert_eq!(trace.num_batches(), 3);
+}
+
+#[test]
+fn test_map_batches_classified() {
+    let mut trace = get_trace();
+
+    let mut kinds = Vec::new();
+    trace.map_batches_classified(|kind, batch| kinds.push((kind, batch.upper().to_vec())));
+    assert_eq!(kinds, vec![(BatchKind::Pending, vec![1]), (BatchKind::Pending, vec![2]), (BatchKind::Pending, vec![3])]);
+
+    // releasing the first two batches moves them to merging.
+    trace.distinguish_since(&[2]);
+    let mut kinds = Vec::new();
+    trace.map_batches_classified(|kind, _| kinds.push(kind));
+    assert_eq!(kinds.last(), Some(&BatchKind::Pending));
+    assert!(kinds[.. kinds.len() - 1].iter().all(|kind| kind == &BatchKind::Merging));
+
+    let mut count = 0;
+    trace.map_batches(|_| count += 1);
+    assert_eq!(count, kinds.len());
+}
+
+#[test]
+fn test_cursor_iter() {
+    let mut builder = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in 0 .. 5u64 {
+        for val in 0 .. key {
+            for time in 0 .. 2usize {
+                builder.push((key.into(), val, time, (key * val) as i64 + 1));
+            }
+        }
+    }
+    let batch = builder.done(&[0], &[2], &[0]);
+
+    // manually walk the cursor.
+    let mut manual = Vec::new();
+    let mut cursor = batch.cursor();
+    while cursor.key_valid(&batch) {
+        while cursor.val_valid(&batch) {
+            let mut updates = Vec::new();
+            cursor.map_times(&batch, |time, diff| updates.push((*time, diff)));
+            manual.push((cursor.key(&batch).clone(), *cursor.val(&batch), updates));
+            cursor.step_val(&batch);
+        }
+        cursor.step_key(&batch);
+    }
+
+    let iterated = cursor_iter::into_iter(batch.cursor(), &batch).collect::<Vec<_>>();
+    assert_eq!(iterated.len(), 10);
+    assert_eq!(iterated, manual);
+
+    // iteration starts from the cursor's position.
+    let mut cursor = batch.cursor();
+    cursor.seek_key(&batch, &3.into());
+    let suffix = cursor_iter::into_iter(cursor, &batch).map(|(key, val, _)| (key.item, val)).collect::<Vec<_>>();
+    assert_eq!(suffix, vec![(3, 0), (3, 1), (3, 2), (4, 0), (4, 1), (4, 2), (4, 3)]);
+}
+
+#[test]
+fn test_with_capacity() {
+    let mut trace = IntegerTrace::with_capacity(16);
+    assert_eq!(trace.num_batches(), 0);
+    assert!(trace.capacity() >= 16);
+
+    // the trace otherwise behaves as one created with `new`.
+    let mut other = IntegerTrace::new();
+    for trace in vec![&mut trace, &mut other] {
+        let mut batcher = new_batcher();
+        batcher.push_batch(&mut vec![((1.into(), 2), 0, 1), ((2.into(), 3), 1, 1)]);
+        trace.insert(batcher.seal(&[1]));
+        trace.insert(batcher.seal(&[2]));
+    }
+    assert_eq!(trace.num_batches(), other.num_batches());
+    let (mut cursor1, storage1) = trace.cursor();
+    let (mut cursor2, storage2) = other.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+}
+
+#[test]
+fn test_merge_since() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut builder1 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder1.push((1.into(), 1, 3, 1));
+    let batch1 = builder1.done(&[0], &[4], &[3]);
+
+    let mut builder2 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder2.push((2.into(), 2, 5, 1));
+    let batch2 = builder2.done(&[4], &[6], &[5]);
+
+    // the merged batch may only be observed from the more advanced `since` frontier, in either order.
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &None, &mut usize::max_value());
+    let merged = merger.done();
+    assert_eq!(merged.description().since(), &[5]);
+    assert_eq!(batch2.description().merged_since(batch1.description()), &[5]);
+    assert_eq!((merged.lower(), merged.upper()), (&[0][..], &[6][..]));
+    assert_eq!(merged.len(), 2);
+}
+
+#[test]
+fn test_cursor_whole_trace() {
+    let mut trace = get_trace();
+    assert_eq!(trace.upper(), &[3]);
+
+    let upper = trace.upper().to_vec();
+    let (mut cursor1, storage1) = trace.cursor();
+    let (mut cursor2, storage2) = trace.cursor_through(&upper[..]).unwrap();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+    assert_eq!(cursor1.to_vec(&storage1), vec![
+        ((1.into(), 2), vec![(0, 1)]),
+        ((2.into(), 3), vec![(1, 1), (2, -1)]),
+    ]);
+}
+
+#[test]
+fn test_key_bounds() {
+    let mut builder = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in vec![3u64, 5, 8, 13] {
+        for val in 0 .. 3 {
+            builder.push((key.into(), val, 0, 1));
+        }
+    }
+    let batch = builder.done(&[0], &[1], &[0]);
+    assert_eq!(batch.key_bounds(), Some((&3.into(), &13.into())));
+    assert_eq!(batch.distinct_keys_hint(), 4);
+
+    // shared batches forward to the batch they share.
+    let shared = Rc::new(batch);
+    assert_eq!(shared.key_bounds(), Some((&3.into(), &13.into())));
+    assert_eq!(shared.distinct_keys_hint(), 4);
+
+    // the default implementations, which step a cursor, agree.
+    type ColumnarBatch = OrdColBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+    let mut builder = <ColumnarBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in vec![3u64, 5, 8, 13] {
+        for val in 0 .. 3 {
+            builder.push((key.into(), val, 0, 1));
+        }
+    }
+    let columnar = builder.done(&[0], &[1], &[0]);
+    assert_eq!(columnar.key_bounds(), Some((&3.into(), &13.into())));
+    assert_eq!(columnar.distinct_keys_hint(), 4);
+
+    let empty = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[0], &[1]);
+    assert_eq!(empty.key_bounds(), None);
+    assert_eq!(empty.distinct_keys_hint(), 0);
+
+    // the spine aggregates over its batches.
+    let mut trace = IntegerTrace::new();
+    assert_eq!(trace.key_bounds(), None);
+    let mut batcher = new_batcher();
+    batcher.push_batch(&mut vec![((7.into(), 0), 0, 1), ((2.into(), 0), 0, 1)]);
+    trace.insert(batcher.seal(&[1]));
+    batcher.push_batch(&mut vec![((9.into(), 0), 1, 1), ((4.into(), 0), 1, 1)]);
+    trace.insert(batcher.seal(&[2]));
+    assert_eq!(trace.key_bounds(), Some((&2.into(), &9.into())));
+    assert_eq!(trace.distinct_keys_hint(), 4);
+}
+
+#[test]
+fn test_merge_batches() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut builder1 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder1.push((1.into(), 1, 0, 1));
+    builder1.push((2.into(), 2, 1, 1));
+    let batch1 = builder1.done(&[0], &[2], &[0]);
+
+    let mut builder2 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder2.push((1.into(), 1, 2, 1));
+    builder2.push((2.into(), 2, 3, -1));
+    builder2.push((3.into(), 3, 2, 1));
+    let batch2 = builder2.done(&[2], &[4], &[0]);
+
+    // without compaction, the updates of overlapping keys are interleaved.
+    let merged = merge_batches(&batch1, &batch2, None);
+    assert_eq!((merged.lower(), merged.upper()), (&[0][..], &[4][..]));
+    assert_eq!(merged.cursor().to_vec(&merged), vec![
+        ((1.into(), 1), vec![(0, 1), (2, 1)]),
+        ((2.into(), 2), vec![(1, 1), (3, -1)]),
+        ((3.into(), 3), vec![(2, 1)]),
+    ]);
+
+    // with compaction, times are advanced and cancelled updates are discarded.
+    let compacted = merge_batches(&batch1, &batch2, Some(&[4]));
+    assert_eq!(compacted.cursor().to_vec(&compacted), vec![
+        ((1.into(), 1), vec![(4, 2)]),
+        ((3.into(), 3), vec![(4, 1)]),
+    ]);
+    assert_eq!(compacted.len(), 2);
+}
+
+#[test]
+fn test_prune_empty() {
+    type Layout = Vec<(Vec<usize>, Vec<usize>, usize)>;
+    fn layout(batches: &[BatchLayout<usize>]) -> Layout {
+        batches.iter().map(|b| (b.lower.clone(), b.upper.clone(), b.len)).collect()
+    }
+
+    for &through in [0, 10].iter() {
+
+        let mut trace = IntegerTrace::new();
+        trace.distinguish_since(&[through]);
+
+        let mut batcher = new_batcher();
+        trace.insert(batcher.seal(&[1]));
+        batcher.push_batch(&mut vec![((1.into(), 1), 1, 1)]);
+        trace.insert(batcher.seal(&[2]));
+        trace.insert(batcher.seal(&[3]));
+        trace.insert(batcher.seal(&[4]));
+        batcher.push_batch(&mut vec![((2.into(), 2), 4, 1)]);
+        trace.insert(batcher.seal(&[5]));
+        trace.close();
+
+        let description = trace.describe();
+        let batches = layout(&description.merging).into_iter().chain(layout(&description.pending)).collect::<Layout>();
+
+        // no leading or consecutive empty batches remain, and the bounds are contiguous through the trace's upper.
+        assert!(batches[0].2 > 0);
+        assert!(batches.windows(2).all(|pair| pair[0].2 > 0 || pair[1].2 > 0));
+        assert!(batches.windows(2).all(|pair| pair[0].1 == pair[1].0));
+        assert_eq!(batches.last().map(|b| b.1.clone()), Some(trace.upper().to_vec()));
+        assert_eq!(trace.upper(), &[]);
+        assert_eq!(trace.len(), 2);
+
+        if through == 0 {
+            // all batches are pending, and so none have been merged.
+            assert_eq!(batches, vec![
+                (vec![1], vec![2], 1),
+                (vec![2], vec![4], 0),
+                (vec![4], vec![5], 1),
+                (vec![5], vec![], 0),
+            ]);
+            // a cut within the coalesced empty batch is clean.
+            let (mut cursor, storage) = trace.cursor_through(&[3]).unwrap();
+            assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 1), vec![(1, 1)])]);
+        }
+    }
+}
+
+#[test]
+fn test_set_logical_compaction() {
+    let mut trace = get_trace();
+
+    assert_eq!(trace.set_logical_compaction(&[2]), Ok(()));
+    assert_eq!(trace.advance_frontier(), &[2]);
+    assert_eq!(trace.set_logical_compaction(&[2]), Ok(()));
+
+    // a frontier moving backward is refused, and leaves the trace unchanged.
+    assert_eq!(trace.set_logical_compaction(&[1]), Err(CompactionError::Regression { current: vec![2], proposed: vec![1] }));
+    assert_eq!(trace.advance_frontier(), &[2]);
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 2), vec![(0, 1)]), ((2.into(), 3), vec![(1, 1), (2, -1)])]);
+
+    assert_eq!(trace.set_logical_compaction(&[3]), Ok(()));
+
+    // the empty frontier is always permitted, after which no non-empty frontier is.
+    assert_eq!(trace.set_logical_compaction(&[]), Ok(()));
+    assert_eq!(trace.advance_frontier(), &[]);
+    assert_eq!(trace.set_logical_compaction(&[4]), Err(CompactionError::Regression { current: vec![], proposed: vec![4] }));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "regresses")]
+fn test_advance_by_regression() {
+    let mut trace = get_trace();
+    trace.advance_by(&[2]);
+    trace.advance_by(&[1]);
+}
+
+#[test]
+fn test_count_and_accumulate() {
+    let mut builder = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((1.into(), 1, 0, 1));
+    builder.push((2.into(), 2, 0, 3));
+    builder.push((2.into(), 2, 1, -1));
+    builder.push((2.into(), 2, 3, 2));
+    builder.push((2.into(), 2, 4, -4));
+    let batch = builder.done(&[0], &[5], &[0]);
+
+    let mut cursor = batch.cursor();
+    cursor.seek_key(&batch, &2.into());
+    assert_eq!(cursor.count_times(&batch), 4);
+    assert_eq!(cursor.accumulate(&batch, &[]), 0);
+    assert_eq!(cursor.accumulate(&batch, &[0]), 0);
+    assert_eq!(cursor.accumulate(&batch, &[1]), 3);
+    assert_eq!(cursor.accumulate(&batch, &[2]), 2);
+    assert_eq!(cursor.accumulate(&batch, &[4]), 4);
+
+    cursor.rewind_keys(&batch);
+    assert_eq!(cursor.count_times(&batch), 1);
+    assert_eq!(cursor.accumulate(&batch, &[]), 1);
+
+    // a cursor over several batches counts and accumulates across them.
+    let mut trace = get_trace();
+    let (mut cursor, storage) = trace.cursor();
+    cursor.seek_key(&storage, &2.into());
+    assert_eq!(cursor.count_times(&storage), 2);
+    assert_eq!(cursor.accumulate(&storage, &[2]), 1);
+    assert_eq!(cursor.accumulate(&storage, &[]), 0);
+}
+
+#[test]
+fn test_from_sorted() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    let mut updates = Vec::new();
+    for key in 0 .. 50u64 {
+        for val in 0 .. (key % 4) {
+            for time in 0 .. (val as usize % 3) + 1 {
+                updates.push((key.into(), val, time, (key + val) as i64 + 1));
+            }
+        }
+    }
+
+    let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for update in updates.iter().cloned() {
+        builder.push(update);
+    }
+    let pushed = builder.done(&[0], &[3], &[0]);
+    let loaded = IntegerBatch::from_sorted(updates, &[0], &[3], &[0]);
+
+    assert_eq!(loaded.layer, pushed.layer);
+    assert_eq!(loaded.len(), pushed.len());
+    assert_eq!(loaded.cursor().to_vec(&loaded), pushed.cursor().to_vec(&pushed));
+    assert_eq!((loaded.lower(), loaded.upper(), loaded.since()), (&[0][..], &[3][..], &[0][..]));
+
+    let empty = IntegerBatch::from_sorted(Vec::new(), &[0], &[3], &[0]);
+    assert_eq!(empty.len(), 0);
+    assert!(!empty.cursor().key_valid(&empty));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "not sorted")]
+fn test_from_sorted_unsorted() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+    IntegerBatch::from_sorted(vec![(2.into(), 0, 0, 1), (1.into(), 0, 0, 1)], &[0], &[1], &[0]);
+}
+
+#[test]
+fn test_from_merge_stream() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // a sorted, consolidated stream, produced lazily rather than from a vector.
+    let updates = || (0 .. 50u64).flat_map(|key| (0 .. (key % 4)).flat_map(move |val| {
+        (0 .. (val as usize % 3) + 1).map(move |time| (key.into(), val, time, (key + val) as i64 + 1))
+    }));
+
+    let mut builder = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for update in updates() {
+        builder.push(update);
+    }
+    let pushed = builder.done(&[0], &[3], &[0]);
+    let streamed = IntegerBatch::from_merge_stream(updates(), &[0], &[3]);
+
+    assert_eq!(streamed.layer, pushed.layer);
+    assert_eq!(streamed.cursor().to_vec(&streamed), pushed.cursor().to_vec(&pushed));
+    assert_eq!((streamed.lower(), streamed.upper(), streamed.since()), (&[0][..], &[3][..], &[0][..]));
+
+    let empty = IntegerBatch::from_merge_stream(None, &[0], &[3]);
+    assert_eq!(empty.len(), 0);
+    assert!(!empty.cursor().key_valid(&empty));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "not sorted")]
+fn test_from_merge_stream_unsorted() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+    let updates = vec![(1.into(), 0, 0, 1), (1.into(), 1, 2, 1), (1.into(), 1, 1, 1)];
+    IntegerBatch::from_merge_stream(updates.into_iter(), &[0], &[3]);
+}
+
+#[test]
+fn test_cursor_through_since() {
+    let mut trace = get_trace();
+
+    // before compaction, the cursor does not claim any advancement beyond the minimum time.
+    {
+        let (mut cursor, storage, since) = trace.cursor_through_since(&[3]).unwrap();
+        assert_eq!(since, vec![0]);
+        assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 2), vec![(0, 1)]), ((2.into(), 3), vec![(1, 1), (2, -1)])]);
+    }
+
+    // after compaction, the returned frontier is the trace's advance frontier, and times are advanced by it.
+    trace.compact_to(&[3]);
+    trace.merge_all();
+    let (mut cursor, storage, since) = trace.cursor_through_since(&[3]).unwrap();
+    assert_eq!(&since[..], trace.advance_frontier());
+    assert_eq!(since, vec![3]);
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 2), vec![(3, 1)])]);
+
+    // the unannotated method presents the same updates.
+    let (mut cursor, storage) = trace.cursor_through(&[3]).unwrap();
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 2), vec![(3, 1)])]);
+}
+
+#[test]
+fn test_estimated_bytes() {
+    let mut trace = IntegerTrace::new();
+    assert_eq!(trace.estimated_bytes(), 0);
+
+    // each batch inserts a record and retracts the previous one, and so the estimate grows with each batch.
+    let mut batcher = new_batcher();
+    let mut previous = 0;
+    for time in 0 .. 16u64 {
+        batcher.push_batch(&mut vec![((time.into(), time), time as usize, 1)]);
+        if time > 0 {
+            batcher.push_batch(&mut vec![(((time - 1).into(), time - 1), time as usize, -1)]);
+        }
+        trace.insert(batcher.seal(&[time as usize + 1]));
+        let estimate = trace.estimated_bytes();
+        assert!(estimate > previous);
+        previous = estimate;
+    }
+    assert_eq!(trace.len(), 31);
+
+    // compaction cancels all but the last record, and the estimate shrinks with the consolidated batches.
+    trace.compact_to(&[16]);
+    trace.merge_all();
+    assert_eq!(trace.len(), 1);
+    assert!(trace.estimated_bytes() < previous);
+
+    let mut total = 0;
+    trace.map_batches(|batch| total += batch.estimated_bytes());
+    assert_eq!(trace.estimated_bytes(), total);
+}
+
+#[test]
+fn test_new_from() {
+    let mut trace = IntegerTrace::new_from(vec![10]);
+    assert_eq!(trace.upper(), &[10]);
+    assert_eq!(trace.advance_frontier(), &[10]);
+    assert_eq!(trace.distinguish_frontier(), &[10]);
+
+    // a batch starting at the minimum time does not continue the trace.
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((1.into(), 1, 5, 1));
+    let early = builder.done(&[0], &[11], &[0]);
+    assert_eq!(trace.insert_batch_checked(early), Err(InsertError::Discontinuity { expected: vec![10], found: vec![0] }));
+
+    // batches starting at the initial frontier are accepted, and inserts continue from the new upper.
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((1.into(), 1, 10, 1));
+    trace.insert(builder.done(&[10], &[12], &[10]));
+    assert_eq!(trace.upper(), &[12]);
+
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((2.into(), 2, 12, 1));
+    trace.insert(builder.done(&[12], &[15], &[10]));
+    assert_eq!(trace.upper(), &[15]);
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![((1.into(), 1), vec![(10, 1)]), ((2.into(), 2), vec![(12, 1)])]);
+}
+
+#[test]
+#[should_panic(expected = "initial frontier must be non-empty")]
+fn test_new_from_empty() {
+    IntegerTrace::new_from(vec![]);
+}
+
+#[test]
+fn test_merge_fuel() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // keys with varying numbers of updates, some present in both batches.
+    let mut builder1 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    let mut builder2 = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in 0 .. 20u64 {
+        for val in 0 .. (key % 4) {
+            if key % 3 != 1 { builder1.push((key.into(), val, 0, 1)); }
+            if key % 3 != 2 { builder2.push((key.into(), val, 1, 1)); }
+        }
+    }
+    let batch1 = builder1.done(&[0], &[1], &[0]);
+    let batch2 = builder2.done(&[1], &[2], &[0]);
+    let total = batch1.len() + batch2.len();
+
+    let mut merger = batch1.begin_merge(&batch2);
+    merger.work(&batch1, &batch2, &None, &mut usize::max_value());
+    let expected = merger.done();
+
+    // a small amount of fuel is spent without exceeding it, and the merge is not yet complete.
+    let mut merger = batch1.begin_merge(&batch2);
+    let mut fuel = 3;
+    merger.work(&batch1, &batch2, &None, &mut fuel);
+    assert_eq!(fuel, 0);
+    assert!(merger.progress().0 <= 3);
+
+    // fuel is not retained across calls: little fuel at a time stops at the first key with more updates.
+    let mut stalled = false;
+    for _ in 0 .. total {
+        let before = merger.progress().0;
+        let mut fuel = 2;
+        merger.work(&batch1, &batch2, &None, &mut fuel);
+        assert_eq!(fuel, 0);
+        assert!(merger.progress().0 - before <= 2);
+        if merger.progress().0 == before { stalled = true; break; }
+    }
+    assert!(stalled);
+
+    // resuming with at least the updates of the largest key completes the merge, within the fuel of each call.
+    loop {
+        let before = merger.progress().0;
+        let mut fuel = 6;
+        merger.work(&batch1, &batch2, &None, &mut fuel);
+        assert!(merger.progress().0 - before <= 6);
+        assert!(merger.progress().0 > before || fuel > 0);
+        if fuel > 0 { break; }
+    }
+    assert_eq!(merger.progress(), (total, total));
+
+    let merged = merger.done();
+    assert_eq!(merged.cursor().to_vec(&merged), expected.cursor().to_vec(&expected));
+    assert_eq!(merged.len(), total);
+}
+
+#[test]
+fn test_join_cursors() {
+    let mut builder1 = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for &key in [0, 1, 3, 5, 6u64].iter() {
+        builder1.push((key.into(), key * 10, 0, 1));
+        builder1.push((key.into(), key * 10 + 1, 0, 1));
+    }
+    let batch1 = builder1.done(&[0], &[1], &[0]);
+
+    let mut builder2 = <OrdKeyBatch<UnsignedWrapper<u64>, usize, i64> as Batch<UnsignedWrapper<u64>, (), usize, i64>>::Builder::new();
+    for &key in [1, 2, 3, 6, 7u64].iter() {
+        builder2.push((key.into(), (), 0, key as i64));
+    }
+    let batch2 = builder2.done(&[0], &[1], &[0]);
+
+    // only common keys are presented, each with cursors positioned at the key in both batches.
+    let mut joined = Vec::new();
+    for (key, mut cursor1, mut cursor2) in join_cursors(&batch1, &batch2) {
+        assert_eq!(cursor1.key(&batch1), &key);
+        assert_eq!(cursor2.key(&batch2), &key);
+        let mut vals = Vec::new();
+        while cursor1.val_valid(&batch1) {
+            vals.push(*cursor1.val(&batch1));
+            cursor1.step_val(&batch1);
+        }
+        let mut count = 0;
+        cursor2.map_times(&batch2, |_, diff| count += diff);
+        joined.push((key.item, vals, count));
+    }
+    assert_eq!(joined, vec![
+        (1, vec![10, 11], 1),
+        (3, vec![30, 31], 3),
+        (6, vec![60, 61], 6),
+    ]);
+
+    // batches without common keys present nothing, in either order.
+    let empty = <OrdKeyBatch<UnsignedWrapper<u64>, usize, i64> as Batch<UnsignedWrapper<u64>, (), usize, i64>>::Builder::new().done(&[0], &[1], &[0]);
+    assert_eq!(join_cursors(&batch1, &empty).count(), 0);
+    assert_eq!(join_cursors(&empty, &batch1).count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Discontinuity")]
+fn test_insert_strict() {
+    let mut trace = get_trace();
+    trace.set_strict(true);
+    assert!(trace.is_strict());
+    trace.insert(<<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[5], &[6]));
+}
+
+#[test]
+fn test_insert_not_strict() {
+    let mut trace = get_trace();
+    assert!(trace.is_strict());
+    trace.set_strict(false);
+    let len = trace.len();
+
+    // the discontinuous batch is refused, and leaves the trace unchanged.
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((5.into(), 5, 5, 1));
+    trace.insert(builder.done(&[5], &[6], &[0]));
+    assert_eq!(trace.refused_inserts(), 1);
+    assert_eq!(trace.upper(), &[3]);
+    assert_eq!(trace.len(), len);
+
+    // the error remains available to `insert_batch_checked`, and is not counted as refused.
+    let batch = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::empty(&[5], &[6]);
+    assert_eq!(trace.insert_batch_checked(batch), Err(InsertError::Discontinuity { expected: vec![3], found: vec![5] }));
+    assert_eq!(trace.refused_inserts(), 1);
+
+    // continuous batches are inserted as usual.
+    let mut builder = <<IntegerTrace as TraceReader<UnsignedWrapper<u64>, u64, usize, i64>>::Batch as Batch<
+        UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    builder.push((5.into(), 5, 5, 1));
+    trace.insert(builder.done(&[3], &[6], &[0]));
+    assert_eq!(trace.upper(), &[6]);
+    assert_eq!(trace.len(), len + 1);
+}
+
+#[test]
+fn test_frontier_history() {
+    let mut trace = IntegerTrace::new();
+
+    // compaction before history is enabled is not recorded.
+    trace.advance_by(&[1]);
+    assert!(trace.frontier_history().is_empty());
+
+    trace.enable_frontier_history(3);
+    trace.advance_by(&[2]);
+    trace.distinguish_since(&[1]);
+    trace.advance_by(&[3]);
+    assert_eq!(trace.frontier_history(), &[
+        (vec![2], vec![0]),
+        (vec![2], vec![1]),
+        (vec![3], vec![1]),
+    ][..]);
+
+    // at capacity, each snapshot displaces the oldest.
+    trace.distinguish_since(&[3]);
+    trace.advance_by(&[5]);
+    assert_eq!(trace.frontier_history(), &[
+        (vec![3], vec![1]),
+        (vec![3], vec![3]),
+        (vec![5], vec![3]),
+    ][..]);
+
+    // reducing the capacity retains the most recent snapshots, and zero disables recording.
+    trace.enable_frontier_history(1);
+    assert_eq!(trace.frontier_history(), &[(vec![5], vec![3])][..]);
+    trace.enable_frontier_history(0);
+    trace.advance_by(&[6]);
+    assert!(trace.frontier_history().is_empty());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_check_invariants_fuzz() {
+    let seed: &[_] = &[1, 2, 3, 4];
+    let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+    for round in 0 .. 12 {
+        let mut trace = IntegerTrace::with_effort(1 + round % 3);
+        trace.set_merge_ratio([1.5, 2.0, 3.0][(round / 3) % 3]);
+        if round % 4 == 3 { trace.set_merge_policy(MergePolicy::BoundedCount(4)); }
+        let mut frontier = 0;
+        let mut batcher = new_batcher();
+
+        for time in 0 .. 300usize {
+            // mostly small batches, with occasional batches orders of magnitude larger and cancelling updates.
+            let choice = rng.gen_range(0u64, 10);
+            let count = if choice == 0 { rng.gen_range(0u64, 5000) } else if choice < 5 { rng.gen_range(0u64, 20) } else { rng.gen_range(0u64, 3) };
+            let mut updates = Vec::new();
+            for _ in 0 .. count {
+                let diff = if rng.gen_range(0u64, 4) == 0 { -1 } else { 1 };
+                updates.push(((rng.gen_range(0u64, 10000).into(), rng.gen_range(0u64, 5)), time, diff));
+            }
+            batcher.push_batch(&mut updates);
+            trace.insert(batcher.seal(&[time + 1]));
+            trace.check_invariants();
+            // shrinking slots, or leaving a bounded count behind, must not leave batches exceeding their slots.
+            if time == 150 { trace.set_merge_ratio(1.2); trace.check_invariants(); }
+            if time == 200 && round % 4 == 3 { trace.set_merge_policy(MergePolicy::Geometric); trace.check_invariants(); }
+
+            if round >= 4 && rng.gen_range(0u64, 5) == 0 {
+                frontier = ::std::cmp::max(frontier, time.saturating_sub(rng.gen_range(0u64, 10) as usize));
+                trace.advance_by(&[frontier]);
+                trace.distinguish_since(&[time]);
+                trace.check_invariants();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_split_at_key() {
+    let mut builder = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for key in 0 .. 10u64 {
+        for val in 0 .. key % 3 + 1 {
+            builder.push(((2 * key).into(), val, key as usize % 4, 1));
+            builder.push(((2 * key).into(), val, 4, -1));
+        }
+    }
+    let batch = builder.done(&[0], &[5], &[0]);
+    let mut original = batch.cursor();
+    let original = original.to_vec(&batch);
+
+    // split at present and absent keys, and at keys before and after all others.
+    for &split in [0u64, 7, 8, 18, 19, 100].iter() {
+        let (lower, upper) = batch.split_at_key(&split.into());
+        assert_eq!(lower.len() + upper.len(), batch.len());
+        assert_eq!((lower.lower(), lower.upper()), (batch.lower(), batch.upper()));
+        assert_eq!((upper.lower(), upper.upper()), (batch.lower(), batch.upper()));
+
+        let mut cursor = lower.cursor();
+        let mut union = cursor.to_vec(&lower);
+        assert!(union.iter().all(|x| (x.0).0.item < split));
+        let mut cursor = upper.cursor();
+        let upper_vec = cursor.to_vec(&upper);
+        assert!(upper_vec.iter().all(|x| (x.0).0.item >= split));
+        union.extend(upper_vec);
+        assert_eq!(union, original);
+    }
+
+    let mut builder = <OrdKeyBatch<UnsignedWrapper<u64>, usize, i64> as Batch<UnsignedWrapper<u64>, (), usize, i64>>::Builder::new();
+    for key in 0 .. 10u64 {
+        builder.push((key.into(), (), 0, key as i64 + 1));
+    }
+    let batch = builder.done(&[0], &[1], &[0]);
+    let (lower, upper) = batch.split_at_key(&4.into());
+    assert_eq!((lower.len(), upper.len()), (4, 6));
+}
+
+#[test]
+fn test_seek_val() {
+    // key 1 holds the even values below 1000, spread across four batches; keys 0 and 2 hold a few others.
+    let mut trace = IntegerTrace::new();
+    let mut batcher = new_batcher();
+    for round in 0 .. 4u64 {
+        let mut updates = (0 .. 500u64).filter(|val| val % 4 == round).map(|val| ((1.into(), 2 * val), round as usize, 1)).collect::<Vec<_>>();
+        updates.push(((0.into(), round), round as usize, 1));
+        updates.push(((2.into(), round), round as usize, 1));
+        batcher.push_batch(&mut updates);
+        trace.insert(batcher.seal(&[round as usize + 1]));
+    }
+
+    // the cursor of a single batch.
+    let mut builder = <OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64> as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Builder::new();
+    for val in 0 .. 500u64 {
+        builder.push((1.into(), 2 * val, 0, 1));
+    }
+    let batch = builder.done(&[0], &[1], &[0]);
+    let mut cursor = batch.cursor();
+    cursor.seek_key(&batch, &1.into());
+    cursor.seek_val(&batch, &101);
+    assert_eq!(cursor.get_val(&batch), Some(&102));
+    cursor.seek_val(&batch, &998);
+    assert_eq!(cursor.get_val(&batch), Some(&998));
+    cursor.step_val(&batch);
+    assert!(!cursor.val_valid(&batch));
+
+    // the cursor list over the trace's batches.
+    let (mut cursor, storage) = trace.cursor();
+    cursor.seek_key(&storage, &1.into());
+
+    // seeking to a present value positions at it, and to an absent value at the next present value.
+    cursor.seek_val(&storage, &500);
+    assert_eq!(cursor.get_val(&storage), Some(&500));
+    assert_eq!(cursor.accumulate(&storage, &[]), 1);
+    cursor.seek_val(&storage, &501);
+    assert_eq!(cursor.get_val(&storage), Some(&502));
+
+    // seeking backwards leaves the cursor in place, and stepping continues from the new position.
+    cursor.seek_val(&storage, &10);
+    assert_eq!(cursor.get_val(&storage), Some(&502));
+    cursor.step_val(&storage);
+    assert_eq!(cursor.get_val(&storage), Some(&504));
+
+    // seeking past the last value exhausts the key's values, but not its keys.
+    cursor.seek_val(&storage, &1000);
+    assert!(!cursor.val_valid(&storage));
+    assert_eq!(cursor.get_key(&storage), Some(&1.into()));
+    cursor.step_key(&storage);
+    assert_eq!(cursor.get_key(&storage), Some(&2.into()));
+    assert_eq!(cursor.get_val(&storage), Some(&0));
+}
+
+#[test]
+fn test_merge_many() {
+    type IntegerBatch = OrdValBatch<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // four contiguous batches of updates to overlapping keys and values, some of which cancel.
+    let mut rng: StdRng = SeedableRng::from_seed(&[9, 9, 9][..]);
+    let mut batcher = <IntegerBatch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+    let mut batches = Vec::new();
+    for round in 0 .. 4 {
+        let mut updates = (0 .. 200).map(|_| {
+            let time = 10 * round + rng.gen_range(0, 10u64) as usize;
+            ((rng.gen_range(0, 30u64).into(), rng.gen_range(0, 5u64)), time, if rng.gen_range(0, 3u64) == 0 { -1 } else { 1 })
+        }).collect::<Vec<_>>();
+        batcher.push_batch(&mut updates);
+        batches.push(batcher.seal(&[10 * round + 10]));
+    }
+
+    for &compaction in [None, Some(&[25][..]), Some(&[40][..])].iter() {
+
+        let pairwise = batches[2 ..].iter().fold(merge_batches(&batches[0], &batches[1], compaction), |merged, batch| merge_batches(&merged, batch, compaction));
+
+        let sources = batches.iter().collect::<Vec<_>>();
+        let frontier = compaction.map(|frontier| frontier.to_vec());
+
+        // merged in one step, and in many small steps of fuel.
+        for &step in [usize::max_value(), 7].iter() {
+            let mut merger = begin_merge_many(&sources[..]);
+            let mut fuel = 0;
+            while fuel == 0 {
+                fuel = step;
+                merger.work(&sources[..], &frontier, &mut fuel);
+                assert!(merger.progress().0 <= merger.progress().1);
+            }
+            assert_eq!(merger.progress(), (batches.iter().map(|b| b.len()).sum(), batches.iter().map(|b| b.len()).sum()));
+            let merged = merger.done();
+
+            assert_eq!((merged.lower(), merged.upper(), merged.since()), (pairwise.lower(), pairwise.upper(), pairwise.since()));
+            assert_eq!(merged.len(), pairwise.len());
+            let mut expected = pairwise.cursor().to_vec(&pairwise);
+            let mut actual = merged.cursor().to_vec(&merged);
+            for &mut (_, ref mut times) in expected.iter_mut().chain(actual.iter_mut()) { times.sort(); }
+            assert_eq!(actual, expected);
+        }
+    }
+}
+
+#[test]
+fn test_key_batch_matches_unit_values() {
+    type KeyBatch = OrdKeyBatch<UnsignedWrapper<u64>, usize, i64>;
+    type UnitBatch = OrdValBatch<UnsignedWrapper<u64>, (), usize, i64>;
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[9, 5][..]);
+    let mut key_batcher = <KeyBatch as Batch<UnsignedWrapper<u64>, (), usize, i64>>::Batcher::new();
+    let mut unit_batcher = <UnitBatch as Batch<UnsignedWrapper<u64>, (), usize, i64>>::Batcher::new();
+    let mut key_batches = Vec::new();
+    let mut unit_batches = Vec::new();
+    for round in 0 .. 3 {
+        let updates = (0 .. 1000).map(|_| ((rng.gen_range(0, 500u64).into(), ()), round, if rng.gen_range(0, 4u64) == 0 { -1 } else { 1 })).collect::<Vec<_>>();
+        key_batcher.push_batch(&mut updates.clone());
+        unit_batcher.push_batch(&mut updates.clone());
+        key_batches.push(key_batcher.seal(&[round + 1]));
+        unit_batches.push(unit_batcher.seal(&[round + 1]));
+    }
+
+    // the batches, and their merges with and without compaction, present the same updates.
+    for index in 0 .. 3 {
+        assert_eq!(key_batches[index].cursor().to_vec(&key_batches[index]), unit_batches[index].cursor().to_vec(&unit_batches[index]));
+    }
+    for &compaction in [None, Some(&[3][..])].iter() {
+        let key_merged = merge_batches(&merge_batches(&key_batches[0], &key_batches[1], compaction), &key_batches[2], compaction);
+        let unit_merged = merge_batches(&merge_batches(&unit_batches[0], &unit_batches[1], compaction), &unit_batches[2], compaction);
+        assert_eq!(key_merged.len(), unit_merged.len());
+        assert_eq!(key_merged.cursor().to_vec(&key_merged), unit_merged.cursor().to_vec(&unit_merged));
+
+        // without a value layer, there is no offset for each key's single unit value.
+        assert_eq!(key_merged.layer.keys.len(), unit_merged.layer.keys.len());
+        assert!(key_merged.estimated_bytes() + key_merged.layer.keys.len() * ::std::mem::size_of::<usize>() <= unit_merged.estimated_bytes());
+    }
+}